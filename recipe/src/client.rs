@@ -1,21 +1,20 @@
-use cookbook::recipe_service_client::RecipeServiceClient;
-use cookbook::ListTableOfContentsRequest;
-
-pub mod cookbook {
-    tonic::include_proto!("cookbook");
-}
+use recipers::grpc::cookbook::recipe_service_client::RecipeServiceClient;
+use recipers::grpc::cookbook::{ListTableOfContentsRequest, Range};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut client = RecipeServiceClient::connect("http://[::1]:50051").await?;
 
     let request = tonic::Request::new(ListTableOfContentsRequest {
-        name: "Gandalf".into(),
-        age: 18,
+        range: Some(Range { start: 0, end: 9 }),
+        ..Default::default()
     });
 
-    let response = client.list_table_of_contents(request).await?;
-    println!("RESPONSE: {:?}", response);
+    let response = client.list_table_of_contents(request).await?.into_inner();
+    println!("{} recipes", response.total);
+    for summary in response.content {
+        println!("{}  {}", summary.id, summary.title);
+    }
 
     Ok(())
 }
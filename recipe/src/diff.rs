@@ -0,0 +1,214 @@
+use crate::{Ingredient, Recipe};
+use serde::Serialize;
+use std::fmt;
+
+/// The differences between two versions of a recipe.
+///
+/// Only the title, the servings, the ingredients and the
+/// preparation are compared.
+#[derive(Debug, PartialEq, Clone, Default, Serialize)]
+pub struct RecipeDiff {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<Change<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    servings: Option<Change<u8>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ingredients: Vec<IngredientChange>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preparation: Option<Change<String>>,
+}
+
+/// The old and the new value of a changed field.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct Change<T> {
+    old: T,
+    new: T,
+}
+
+/// A change of a single ingredient.
+///
+/// Ingredients are matched by name, so a renamed ingredient is
+/// reported as removed and added.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum IngredientChange {
+    Added { ingredient: Ingredient },
+    Removed { ingredient: Ingredient },
+    Modified { old: Ingredient, new: Ingredient },
+}
+
+impl RecipeDiff {
+    /// Returns true if both recipes are equal in the compared fields.
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.servings.is_none()
+            && self.ingredients.is_empty()
+            && self.preparation.is_none()
+    }
+
+    pub fn ingredients(&self) -> &[IngredientChange] {
+        &self.ingredients
+    }
+}
+
+impl Recipe {
+    /// Describes how `other` differs from this recipe.
+    pub fn diff(&self, other: &Recipe) -> RecipeDiff {
+        RecipeDiff {
+            title: change(&self.title, &other.title),
+            servings: change(&self.servings, &other.servings),
+            ingredients: diff_ingredients(&self.ingredients, &other.ingredients),
+            preparation: change(&self.preparation, &other.preparation),
+        }
+    }
+}
+
+fn change<T: PartialEq + Clone>(old: &T, new: &T) -> Option<Change<T>> {
+    if old == new {
+        None
+    } else {
+        Some(Change {
+            old: old.clone(),
+            new: new.clone(),
+        })
+    }
+}
+
+/// Lists removed and modified ingredients in their old order,
+/// followed by the added ingredients in their new order.
+fn diff_ingredients(old: &[Ingredient], new: &[Ingredient]) -> Vec<IngredientChange> {
+    let mut changes = Vec::new();
+
+    for before in old {
+        match new.iter().find(|after| after.name == before.name) {
+            None => changes.push(IngredientChange::Removed {
+                ingredient: before.clone(),
+            }),
+            Some(after) if after != before => changes.push(IngredientChange::Modified {
+                old: before.clone(),
+                new: after.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for after in new {
+        if !old.iter().any(|before| before.name == after.name) {
+            changes.push(IngredientChange::Added {
+                ingredient: after.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+impl fmt::Display for RecipeDiff {
+    /// Writes one line per change, for example "+ 2 Zucchini" for an
+    /// added ingredient.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(Change { old, new }) = &self.title {
+            writeln!(f, "Titel: {:?} -> {:?}", old, new)?;
+        }
+        if let Some(Change { old, new }) = &self.servings {
+            writeln!(f, "Portionen: {} -> {}", old, new)?;
+        }
+        for change in &self.ingredients {
+            match change {
+                IngredientChange::Added { ingredient } => writeln!(f, "+ {}", ingredient)?,
+                IngredientChange::Removed { ingredient } => writeln!(f, "- {}", ingredient)?,
+                IngredientChange::Modified { old, new } => writeln!(f, "~ {} -> {}", old, new)?,
+            }
+        }
+        if self.preparation.is_some() {
+            writeln!(f, "Zubereitung geändert")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::IngredientChange;
+    use crate::testing::lasagne;
+    use crate::{rat, Ingredient, Rational, Recipe};
+
+    fn vegetarian_lasagne() -> Recipe {
+        Recipe {
+            title: "Vegetarische Lasagne".into(),
+            servings: 2,
+            ingredients: vec![
+                Ingredient {
+                    name: "Pasta".into(),
+                    quantity: rat!(2),
+                    unit: "pc".into(),
+                },
+                Ingredient {
+                    name: "Tomaten".into(),
+                    quantity: rat!(2),
+                    unit: "".into(),
+                },
+                Ingredient {
+                    name: "Zucchini".into(),
+                    quantity: rat!(2),
+                    unit: "".into(),
+                },
+            ],
+            ..lasagne()
+        }
+    }
+
+    #[test]
+    fn diff_with_itself() {
+        let diff = lasagne().diff(&lasagne());
+
+        assert!(diff.is_empty());
+        assert_eq!("", diff.to_string());
+        assert_eq!("{}", serde_json::to_string(&diff).unwrap());
+    }
+
+    #[test]
+    fn diff_with_vegetarian_variant() {
+        let diff = lasagne().diff(&vegetarian_lasagne());
+
+        assert!(!diff.is_empty());
+        assert_eq!(
+            "Titel: \"Lasagne\" -> \"Vegetarische Lasagne\"\nPortionen: 4 -> 2\n~ 500 g Pasta -> 2 pc Pasta\n+ 2 Zucchini\n",
+            diff.to_string()
+        );
+
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(json["servings"]["old"], 4);
+        assert_eq!(json["servings"]["new"], 2);
+        assert_eq!(json["ingredients"][1]["change"], "added");
+        assert_eq!(json["ingredients"][1]["ingredient"]["name"], "Zucchini");
+        assert!(json.get("preparation").is_none());
+    }
+
+    #[test]
+    fn diff_removed_ingredient() {
+        let diff = vegetarian_lasagne().diff(&lasagne());
+
+        assert_eq!(
+            diff.ingredients().last(),
+            Some(&IngredientChange::Removed {
+                ingredient: Ingredient {
+                    name: "Zucchini".into(),
+                    quantity: rat!(2),
+                    unit: "".into()
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn diff_preparation() {
+        let changed = Recipe {
+            preparation: "Im Ofen backen.".into(),
+            ..lasagne()
+        };
+        let diff = lasagne().diff(&changed);
+
+        assert_eq!("Zubereitung geändert\n", diff.to_string());
+    }
+}
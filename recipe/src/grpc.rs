@@ -0,0 +1,507 @@
+//! The gRPC interface of the cookbook.
+
+// Status is large, but it is what every RPC returns anyway.
+#![allow(clippy::result_large_err)]
+
+use crate::repository::{Filter, Page, RemoveResult, Repository, RepositoryError};
+use crate::{normalize_tags, Category, Difficulty, ImageRef, Ingredient, Recipe};
+use std::ops::Bound;
+use std::sync::Arc;
+use std::vec::IntoIter;
+use tokio::sync::RwLock;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use cookbook::recipe_service_server::RecipeService;
+use cookbook::{
+    CreateRecipeResponse, DeleteRecipeRequest, DeleteRecipeResponse, GetRecipeRequest,
+    ListTableOfContentsRequest, StreamRecipesRequest, TableOfContentsResponse,
+};
+
+pub use cookbook::recipe_service_server::RecipeServiceServer;
+
+/// Types generated from `protos/recipe.proto`.
+pub mod cookbook {
+    tonic::include_proto!("cookbook");
+}
+
+/// Implements the RecipeService on top of a repository.
+///
+/// The repository can be shared with the HTTP interface.
+pub struct Service {
+    repository: Arc<RwLock<DynRepository>>,
+}
+
+/// The repository behind the service, independent of its backend.
+type DynRepository = dyn Repository;
+
+impl Service {
+    pub fn new(repository: Arc<RwLock<DynRepository>>) -> Service {
+        Service { repository }
+    }
+}
+
+impl From<RepositoryError> for Status {
+    fn from(error: RepositoryError) -> Self {
+        match error {
+            RepositoryError::DuplicateId(id) => {
+                Status::already_exists(format!("recipe {} already exists", id))
+            }
+            RepositoryError::Conflict { current_revision } => Status::failed_precondition(format!(
+                "recipe has changed, current revision {}",
+                current_revision
+            )),
+            RepositoryError::CapacityExceeded { capacity } => Status::resource_exhausted(format!(
+                "repository is full, it holds at most {} recipes",
+                capacity
+            )),
+            RepositoryError::Backend(cause) => {
+                tracing::error!("repository backend failed: {}", cause);
+                Status::internal("repository backend failed")
+            }
+        }
+    }
+}
+
+/// Parses the id of a recipe sent by a client.
+fn parse_id(id: &str) -> Result<Uuid, Status> {
+    Uuid::parse_str(id).map_err(|_| Status::invalid_argument(format!("invalid id {:?}", id)))
+}
+
+impl From<&crate::Summary> for cookbook::Summary {
+    fn from(summary: &crate::Summary) -> Self {
+        cookbook::Summary {
+            id: summary.id.to_string(),
+            title: summary.title.clone(),
+            prep_time_minutes: summary.prep_time_minutes.unwrap_or_default(),
+            cook_time_minutes: summary.cook_time_minutes.unwrap_or_default(),
+            thumbnail: summary.thumbnail.clone().unwrap_or_default(),
+            updated_at: summary.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+impl From<&Recipe> for cookbook::Recipe {
+    fn from(recipe: &Recipe) -> Self {
+        cookbook::Recipe {
+            title: recipe.title.clone(),
+            preparation: recipe.preparation.clone(),
+            servings: recipe.servings.into(),
+            yield_description: recipe.yield_description.clone().unwrap_or_default(),
+            ingredients: recipe
+                .ingredients
+                .iter()
+                .map(|ingredient| cookbook::Ingredient {
+                    name: ingredient.name.clone(),
+                    quantity: ingredient.quantity.to_string(),
+                    unit: ingredient.unit.to_string(),
+                })
+                .collect(),
+            tags: recipe.tags.clone(),
+            prep_time_minutes: recipe.prep_time_minutes.unwrap_or_default(),
+            cook_time_minutes: recipe.cook_time_minutes.unwrap_or_default(),
+            images: recipe
+                .images
+                .iter()
+                .map(|image| cookbook::Image {
+                    url: image.url.clone(),
+                    caption: image.caption.clone().unwrap_or_default(),
+                })
+                .collect(),
+            source: recipe.source.clone().unwrap_or_default(),
+            author: recipe.author.clone().unwrap_or_default(),
+            difficulty: cookbook::Difficulty::from(recipe.difficulty).into(),
+            category: recipe
+                .category
+                .as_ref()
+                .map(Category::to_string)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<cookbook::Recipe> for Recipe {
+    type Error = Status;
+
+    /// Converts a recipe received from a client.
+    ///
+    /// Fails with `INVALID_ARGUMENT` if the servings or a quantity are
+    /// out of range.
+    fn try_from(recipe: cookbook::Recipe) -> Result<Self, Self::Error> {
+        let mut ingredients = Vec::with_capacity(recipe.ingredients.len());
+        for ingredient in recipe.ingredients {
+            let quantity = ingredient.quantity.parse().map_err(|error| {
+                Status::invalid_argument(format!("invalid quantity: {}", error))
+            })?;
+            ingredients.push(Ingredient {
+                name: ingredient.name,
+                quantity,
+                unit: ingredient.unit.into(),
+            });
+        }
+
+        let difficulty = match cookbook::Difficulty::from_i32(recipe.difficulty) {
+            Some(difficulty) => difficulty.into(),
+            None => return Err(Status::invalid_argument("unknown difficulty")),
+        };
+
+        Ok(Recipe {
+            title: recipe.title,
+            preparation: recipe.preparation,
+            servings: recipe
+                .servings
+                .try_into()
+                .map_err(|_| Status::invalid_argument("too many servings"))?,
+            yield_description: non_empty(recipe.yield_description),
+            ingredients,
+            tags: normalize_tags(&recipe.tags),
+            prep_time_minutes: non_zero(recipe.prep_time_minutes),
+            cook_time_minutes: non_zero(recipe.cook_time_minutes),
+            images: recipe
+                .images
+                .into_iter()
+                .map(|image| ImageRef {
+                    url: image.url,
+                    caption: non_empty(image.caption),
+                })
+                .collect(),
+            source: non_empty(recipe.source),
+            author: non_empty(recipe.author),
+            difficulty,
+            category: non_empty(recipe.category).map(|name| Category::from(name.as_str())),
+        })
+    }
+}
+
+impl From<Option<Difficulty>> for cookbook::Difficulty {
+    fn from(difficulty: Option<Difficulty>) -> Self {
+        match difficulty {
+            None => cookbook::Difficulty::Unspecified,
+            Some(Difficulty::Easy) => cookbook::Difficulty::Easy,
+            Some(Difficulty::Medium) => cookbook::Difficulty::Medium,
+            Some(Difficulty::Hard) => cookbook::Difficulty::Hard,
+        }
+    }
+}
+
+impl From<cookbook::Difficulty> for Option<Difficulty> {
+    fn from(difficulty: cookbook::Difficulty) -> Self {
+        match difficulty {
+            cookbook::Difficulty::Unspecified => None,
+            cookbook::Difficulty::Easy => Some(Difficulty::Easy),
+            cookbook::Difficulty::Medium => Some(Difficulty::Medium),
+            cookbook::Difficulty::Hard => Some(Difficulty::Hard),
+        }
+    }
+}
+
+/// Proto3 has no optional scalars, unset texts are empty.
+fn non_empty(text: String) -> Option<String> {
+    Some(text).filter(|text| !text.is_empty())
+}
+
+/// Proto3 has no optional scalars, unset numbers are 0.
+fn non_zero(number: u32) -> Option<u32> {
+    Some(number).filter(|number| *number != 0)
+}
+
+impl From<&ListTableOfContentsRequest> for Filter {
+    fn from(request: &ListTableOfContentsRequest) -> Self {
+        filter(&request.search, &request.tags)
+    }
+}
+
+impl From<&StreamRecipesRequest> for Filter {
+    fn from(request: &StreamRecipesRequest) -> Self {
+        filter(&request.search, &request.tags)
+    }
+}
+
+fn filter(search: &str, tags: &[String]) -> Filter {
+    tags.iter()
+        .fold(Filter::new().search(search), |filter, tag| filter.tag(tag))
+}
+
+#[tonic::async_trait]
+impl RecipeService for Service {
+    type StreamRecipesStream = tokio_stream::Iter<IntoIter<Result<cookbook::Summary, Status>>>;
+
+    async fn list_table_of_contents(
+        &self,
+        request: Request<ListTableOfContentsRequest>,
+    ) -> Result<Response<TableOfContentsResponse>, Status> {
+        let request = request.into_inner();
+        let range = match &request.range {
+            Some(range) => {
+                Page::from_bounds(&(Bound::Included(range.start), Bound::Included(range.end)))
+            }
+            None => Page::all(),
+        };
+
+        let filter = Filter::from(&request);
+        let toc = self.repository.read().await.list(&range, &filter).await?;
+
+        Ok(Response::new(TableOfContentsResponse {
+            total: toc.total() as u64,
+            filtered: toc.filtered() as u64,
+            content: toc.content().iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn get_recipe(
+        &self,
+        request: Request<GetRecipeRequest>,
+    ) -> Result<Response<cookbook::Recipe>, Status> {
+        let id = parse_id(&request.get_ref().id)?;
+
+        match self.repository.read().await.get(&id).await? {
+            Some(recipe) => Ok(Response::new(recipe.value().into())),
+            None => Err(Status::not_found(format!("recipe {} not found", id))),
+        }
+    }
+
+    async fn create_recipe(
+        &self,
+        request: Request<cookbook::Recipe>,
+    ) -> Result<Response<CreateRecipeResponse>, Status> {
+        let recipe = Recipe::try_from(request.into_inner())?;
+        let id = self.repository.write().await.insert(&recipe).await?;
+
+        Ok(Response::new(CreateRecipeResponse { id: id.to_string() }))
+    }
+
+    async fn delete_recipe(
+        &self,
+        request: Request<DeleteRecipeRequest>,
+    ) -> Result<Response<DeleteRecipeResponse>, Status> {
+        let id = parse_id(&request.get_ref().id)?;
+
+        match self.repository.write().await.remove(&id).await? {
+            RemoveResult::Removed => Ok(Response::new(DeleteRecipeResponse {})),
+            RemoveResult::NotFound => Err(Status::not_found(format!("recipe {} not found", id))),
+        }
+    }
+
+    /// Streams the summaries of the selected recipes.
+    ///
+    /// The summaries are collected while the repository is locked, so
+    /// the stream does not hold the lock.
+    async fn stream_recipes(
+        &self,
+        request: Request<StreamRecipesRequest>,
+    ) -> Result<Response<Self::StreamRecipesStream>, Status> {
+        let filter = Filter::from(request.get_ref());
+        let toc = self
+            .repository
+            .read()
+            .await
+            .list(&Page::all(), &filter)
+            .await?;
+
+        let summaries: Vec<_> = toc.content().iter().map(|s| Ok(s.into())).collect();
+        Ok(Response::new(tokio_stream::iter(summaries)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::cookbook::recipe_service_client::RecipeServiceClient;
+    use super::cookbook::{
+        self, CreateRecipeResponse, DeleteRecipeRequest, GetRecipeRequest,
+        ListTableOfContentsRequest, StreamRecipesRequest,
+    };
+    use super::{RecipeServiceServer, Service};
+    use crate::repository::{memory::Ephemeral, Repository};
+    use crate::repository::{Filter, Page};
+    use crate::Recipe;
+    use prost::Message;
+    use std::sync::Arc;
+    use tokio::{net::TcpListener, sync::RwLock};
+    use tokio_stream::{wrappers::TcpListenerStream, StreamExt};
+    use tonic::{transport::Channel, Code};
+
+    /// Serves the repository on a free local port and connects a
+    /// client to it.
+    async fn serve(
+        repository: Ephemeral,
+    ) -> Result<RecipeServiceClient<Channel>, Box<dyn std::error::Error>> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let service = Service::new(Arc::new(RwLock::new(repository)));
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(RecipeServiceServer::new(service))
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        Ok(RecipeServiceClient::connect(format!("http://{}", address)).await?)
+    }
+
+    #[test]
+    fn recipe_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        let recipe: Recipe = serde_json::from_str(include_str!("fixture/lasagne.json"))?;
+
+        let bytes = cookbook::Recipe::from(&recipe).encode_to_vec();
+        let decoded = Recipe::try_from(cookbook::Recipe::decode(bytes.as_slice())?)?;
+
+        assert_eq!(recipe, decoded);
+        Ok(())
+    }
+
+    #[test]
+    fn category_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+        for category in [None, Some("main"), Some("brotzeit")] {
+            let recipe: Recipe = serde_json::from_value(serde_json::json!({
+                "title": "Tee",
+                "servings": 1,
+                "ingredients": [],
+                "category": category,
+            }))?;
+
+            let decoded = Recipe::try_from(cookbook::Recipe::from(&recipe))?;
+            assert_eq!(recipe.category(), decoded.category(), "{:?}", category);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn reject_invalid_quantity() {
+        let message = cookbook::Recipe {
+            title: "Tee".into(),
+            servings: 1,
+            ingredients: vec![cookbook::Ingredient {
+                name: "Wasser".into(),
+                quantity: "viel".into(),
+                unit: "l".into(),
+            }],
+            ..Default::default()
+        };
+
+        let status = Recipe::try_from(message).unwrap_err();
+        assert_eq!(tonic::Code::InvalidArgument, status.code());
+    }
+
+    #[tokio::test]
+    async fn list_table_of_contents() -> Result<(), Box<dyn std::error::Error>> {
+        let mut repository = Ephemeral::new();
+        for title in ["Pfannkuchen", "Lasagne", "Pfannenbrot"] {
+            let recipe: Recipe = serde_json::from_value(serde_json::json!({
+                "title": title,
+                "servings": 2,
+                "ingredients": [],
+            }))?;
+            repository.insert(&recipe).await?;
+        }
+
+        let mut client = serve(repository).await?;
+        let response = client
+            .list_table_of_contents(ListTableOfContentsRequest::default())
+            .await?
+            .into_inner();
+
+        let titles: Vec<&str> = response.content.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(3, response.total);
+        assert_eq!(vec!["Lasagne", "Pfannenbrot", "Pfannkuchen"], titles);
+
+        let response = client
+            .list_table_of_contents(ListTableOfContentsRequest {
+                range: Some(cookbook::Range { start: 1, end: 1 }),
+                search: "Pfann".into(),
+                ..Default::default()
+            })
+            .await?
+            .into_inner();
+
+        let titles: Vec<&str> = response.content.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(vec!["Pfannkuchen"], titles);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_then_get_recipe() -> Result<(), Box<dyn std::error::Error>> {
+        let recipe: Recipe = serde_json::from_str(include_str!("fixture/lasagne.json"))?;
+        let mut client = serve(Ephemeral::new()).await?;
+
+        let CreateRecipeResponse { id } = client
+            .create_recipe(cookbook::Recipe::from(&recipe))
+            .await?
+            .into_inner();
+
+        let got = client
+            .get_recipe(GetRecipeRequest { id: id.clone() })
+            .await?
+            .into_inner();
+        assert_eq!(recipe, Recipe::try_from(got)?);
+
+        let status = client
+            .get_recipe(GetRecipeRequest {
+                id: uuid::Uuid::new_v4().to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(Code::NotFound, status.code());
+
+        let status = client
+            .get_recipe(GetRecipeRequest { id: "42".into() })
+            .await
+            .unwrap_err();
+        assert_eq!(Code::InvalidArgument, status.code());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_recipe() -> Result<(), Box<dyn std::error::Error>> {
+        let mut repository = Ephemeral::new();
+        let recipe: Recipe = serde_json::from_str(include_str!("fixture/lasagne.json"))?;
+        let id = repository.insert(&recipe).await?.to_string();
+        let mut client = serve(repository).await?;
+
+        client
+            .delete_recipe(DeleteRecipeRequest { id: id.clone() })
+            .await?;
+
+        let status = client
+            .get_recipe(GetRecipeRequest { id: id.clone() })
+            .await
+            .unwrap_err();
+        assert_eq!(Code::NotFound, status.code());
+
+        let status = client
+            .delete_recipe(DeleteRecipeRequest { id })
+            .await
+            .unwrap_err();
+        assert_eq!(Code::NotFound, status.code());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stream_recipes() -> Result<(), Box<dyn std::error::Error>> {
+        let mut repository = Ephemeral::new();
+        for title in ["Pfannkuchen", "Lasagne", "Pfannenbrot"] {
+            let recipe: Recipe = serde_json::from_value(serde_json::json!({
+                "title": title,
+                "servings": 2,
+                "ingredients": [],
+            }))?;
+            repository.insert(&recipe).await?;
+        }
+        let toc = repository
+            .list(&Page::all(), &Filter::new().search("Pfann"))
+            .await?;
+        let want: Vec<cookbook::Summary> = toc.content().iter().map(Into::into).collect();
+        let mut client = serve(repository).await?;
+
+        let stream = client
+            .stream_recipes(StreamRecipesRequest {
+                search: "Pfann".into(),
+                ..Default::default()
+            })
+            .await?
+            .into_inner();
+        let got: Vec<cookbook::Summary> = stream.collect::<Result<_, _>>().await?;
+
+        assert_eq!(2, got.len());
+        assert_eq!(want, got);
+        Ok(())
+    }
+}
@@ -1,55 +1,788 @@
-use crate::rational::Rational;
+use chrono::{DateTime, Utc};
+use repository::SearchQuery;
 use serde::{Deserialize, Serialize};
+use std::{
+    error, fmt,
+    ops::{self, Deref},
+    str::FromStr,
+};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
+mod diff;
+pub mod grpc;
+mod markdown;
 mod rational;
 pub mod repository;
+pub mod seed;
+mod strict;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+mod text;
+pub mod units;
+mod validation;
+
+pub use crate::diff::{Change, IngredientChange, RecipeDiff};
+pub use crate::markdown::RecipeParseError;
+pub use crate::rational::Rational;
+pub use crate::units::Unit;
+pub use crate::validation::{ValidationCode, ValidationError};
 
 #[macro_use]
 extern crate lazy_static;
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
-struct Ingredient {
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, ToSchema)]
+pub struct Ingredient {
     name: String,
+    #[schema(value_type = String)]
     quantity: Rational,
-    unit: String,
+    /// Known spellings of a unit are replaced by the canonical one.
+    #[schema(value_type = String)]
+    unit: Unit,
+}
+
+impl fmt::Display for Ingredient {
+    /// Displays the ingredient as a line of an ingredient list, for
+    /// example "1½ kg Mehl".
+    ///
+    /// The unit is omitted if it is empty.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.unit.is_empty() {
+            write!(f, "{} {}", self.quantity, self.name)
+        } else {
+            write!(f, "{} {} {}", self.quantity, self.unit, self.name)
+        }
+    }
+}
+
+impl FromStr for Ingredient {
+    type Err = IngredientParseError;
+
+    /// Parses an ingredient from a line of an ingredient list.
+    ///
+    /// The line starts with the quantity, followed by the unit and
+    /// the name of the ingredient. The word after the quantity is the
+    /// unit only if it is a known spelling of a [Unit]. Otherwise all
+    /// words after the quantity are the name and the unit is empty,
+    /// like in "2 Eier Größe M".
+    ///
+    /// The Display of an ingredient parses back to the same
+    /// ingredient, unless its unit has no known spelling, like
+    /// "Bund", or its name starts with one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+
+        // A mixed number like "1 1/2" spans two words.
+        let mixed = match words.as_slice() {
+            [number, fraction, rest @ ..] => format!("{} {}", number, fraction)
+                .parse::<Rational>()
+                .ok()
+                .map(|quantity| (quantity, rest)),
+            _ => None,
+        };
+
+        let (quantity, rest) = match (mixed, words.split_first()) {
+            (Some(mixed), _) => mixed,
+            (None, Some((quantity, rest))) => (
+                quantity
+                    .parse()
+                    .map_err(|_| IngredientParseError::MissingQuantity)?,
+                rest,
+            ),
+            (None, None) => return Err(IngredientParseError::MissingQuantity),
+        };
+
+        let (unit, name) = match rest {
+            [] => return Err(IngredientParseError::MissingName),
+            [unit, name @ ..] if !name.is_empty() => match Unit::from(*unit) {
+                Unit::Other(_) => (Unit::default(), rest),
+                known => (known, name),
+            },
+            _ => (Unit::default(), rest),
+        };
+
+        Ok(Ingredient {
+            name: name.join(" "),
+            quantity,
+            unit,
+        })
+    }
+}
+
+/// The error returned if a line is not an ingredient.
+#[derive(Debug, PartialEq)]
+pub enum IngredientParseError {
+    MissingQuantity,
+    MissingName,
+}
+
+impl fmt::Display for IngredientParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IngredientParseError::MissingQuantity => write!(f, "quantity expected"),
+            IngredientParseError::MissingName => write!(f, "name of ingredient expected"),
+        }
+    }
+}
+
+impl error::Error for IngredientParseError {}
+
+/// Reference to an image of a recipe.
+///
+/// The url is either an absolute http(s) url or a path relative to
+/// the service.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize, ToSchema)]
+pub struct ImageRef {
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    caption: Option<String>,
+}
+
+impl ImageRef {
+    /// Checks whether the url of the image is syntactically valid.
+    pub fn validate(&self) -> Result<(), InvalidUrl> {
+        if is_valid_url(&self.url) {
+            Ok(())
+        } else {
+            Err(InvalidUrl(self.url.clone()))
+        }
+    }
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Serialize)]
-struct Summary {
+/// Returns false if `source` looks like a url but is not an
+/// absolute http(s) url.
+///
+/// Sources without a scheme, for example book references, are
+/// always valid.
+fn is_valid_source(source: &str) -> bool {
+    if !source.contains("://") {
+        return true;
+    }
+
+    match url::Url::parse(source) {
+        Ok(url) => matches!(url.scheme(), "http" | "https") && url.host().is_some(),
+        Err(_) => false,
+    }
+}
+
+/// Returns true if `url` is an absolute http(s) url or a relative
+/// path without whitespace.
+fn is_valid_url(url: &str) -> bool {
+    if url.is_empty() || url.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    match url::Url::parse(url) {
+        Ok(absolute) => matches!(absolute.scheme(), "http" | "https") && absolute.host().is_some(),
+        Err(url::ParseError::RelativeUrlWithoutBase) => true,
+        Err(_) => false,
+    }
+}
+
+/// The error returned if a url is not valid.
+#[derive(Debug, PartialEq)]
+pub struct InvalidUrl(String);
+
+impl fmt::Display for InvalidUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid url: {}", self.0)
+    }
+}
+
+impl error::Error for InvalidUrl {}
+
+/// Short description of a recipe in the table of contents.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Serialize, ToSchema)]
+pub struct Summary {
     title: String,
     id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prep_time_minutes: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cook_time_minutes: Option<u32>,
+    /// Url of the first image of the recipe.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumbnail: Option<String>,
+    /// Time of the last change of the recipe.
+    #[schema(value_type = String, format = DateTime)]
+    updated_at: DateTime<Utc>,
+    /// Revision of the recipe, see [Stored::revision].
+    revision: u64,
+    /// Start and end of the part of the title matching the search, as
+    /// byte offsets. Only present in search results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Vec<usize>>)]
+    highlight: Option<(usize, usize)>,
+    /// The course of the recipe, for grouping the table of contents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    category: Option<Category>,
+}
+
+impl Summary {
+    /// Returns the time of the last change of the recipe.
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    /// Returns the revision of the recipe.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns the course of the recipe.
+    pub fn category(&self) -> Option<&Category> {
+        self.category.as_ref()
+    }
+
+    /// Returns the byte range of the title which matches the query, or
+    /// `None` if the title does not match.
+    ///
+    /// The title is compared like [Filter::search] compares it,
+    /// regardless of the fields the query searches.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::repository::{SearchMode, SearchQuery};
+    /// # use recipers::{repository::{memory, Filter, Page, Repository}, Recipe};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let mut repository = memory::Repository::new();
+    /// # let json = r#"{"title": "Apfelkuchen", "servings": 1, "ingredients": []}"#;
+    /// # repository.insert(&serde_json::from_str::<Recipe>(json).unwrap()).await.unwrap();
+    /// # let toc = repository.list(&Page::all(), &Filter::new()).await.unwrap();
+    /// let summary = &toc.content()[0]; // "Apfelkuchen"
+    ///
+    /// let query = SearchQuery::new("kuchen", SearchMode::Contains);
+    /// assert_eq!(Some(5..11), summary.matches(&query));
+    /// # }
+    /// ```
+    ///
+    /// [Filter::search]: repository::Filter::search
+    pub fn matches(&self, query: &SearchQuery) -> Option<ops::Range<usize>> {
+        query.find(&self.title)
+    }
+
+    /// Returns the part of the title which matched the search, see
+    /// [TableOfContents::highlight].
+    pub fn highlight(&self) -> Option<ops::Range<usize>> {
+        self.highlight.map(|(start, end)| start..end)
+    }
 }
 
-impl Into<Summary> for (&Uuid, &Recipe) {
-    fn into(self) -> Summary {
+impl From<(&Uuid, &Stored<Recipe>)> for Summary {
+    fn from((id, stored): (&Uuid, &Stored<Recipe>)) -> Self {
+        let recipe = stored.value();
         Summary {
-            id: *self.0,
-            title: self.1.title.clone(),
+            id: *id,
+            title: recipe.title.clone(),
+            prep_time_minutes: recipe.prep_time_minutes,
+            cook_time_minutes: recipe.cook_time_minutes,
+            thumbnail: recipe.images.first().map(|image| image.url.clone()),
+            updated_at: stored.updated_at,
+            revision: stored.revision,
+            highlight: None,
+            category: recipe.category.clone(),
         }
     }
 }
 
-#[derive(Debug, Serialize)]
+/// A value kept in a repository together with its storage metadata.
+///
+/// The timestamps and the revision are maintained by the repository.
+/// The timestamps are serialized as RFC 3339 strings next to the
+/// fields of the value.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct Stored<T> {
+    #[serde(flatten)]
+    value: T,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    revision: u64,
+}
+
+impl<T> Stored<T> {
+    /// Wraps a value which has just been created.
+    pub(crate) fn new(value: T, now: DateTime<Utc>) -> Stored<T> {
+        Stored {
+            value,
+            created_at: now,
+            updated_at: now,
+            revision: 1,
+        }
+    }
+
+    /// Replaces the value and keeps the time it was created.
+    ///
+    /// Every replacement counts as a new revision.
+    pub(crate) fn replace(&mut self, value: T, now: DateTime<Utc>) {
+        self.value = value;
+        self.updated_at = now;
+        self.revision += 1;
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Returns the time the value was added to the repository.
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// Returns the time of the last change of the value.
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    /// Returns the revision of the value.
+    ///
+    /// A new value has revision 1. Every change increments the
+    /// revision, so a client can tell whether the value changed since
+    /// it was read.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+}
+
+impl<T> Deref for Stored<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TableOfContents {
+    /// Number of recipes in the repository.
     total: usize,
+    /// Number of recipes selected by the filter, before the range
+    /// is applied.
+    filtered: usize,
     content: Vec<Summary>,
 }
 
-#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+impl TableOfContents {
+    /// Returns the number of recipes in the repository.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the number of recipes selected by the filter.
+    ///
+    /// Ranges refer to these recipes, so `filtered` is the length a
+    /// client pages through.
+    pub fn filtered(&self) -> usize {
+        self.filtered
+    }
+
+    /// Returns the summaries within the requested range.
+    pub fn content(&self) -> &[Summary] {
+        &self.content
+    }
+
+    /// Marks the part of every title which matches the query, so a
+    /// client can highlight it.
+    pub fn highlight(&mut self, query: &SearchQuery) {
+        for summary in &mut self.content {
+            summary.highlight = summary.matches(query).map(|range| (range.start, range.end));
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone, Deserialize, Serialize, ToSchema)]
 pub struct Recipe {
     title: String,
     #[serde(default)]
     preparation: String,
     servings: u8,
+    /// What the recipe yields, for example "1 Blech", if servings
+    /// do not fit. The servings remain the basis for scaling.
+    #[serde(default, rename = "yield", skip_serializing_if = "Option::is_none")]
+    yield_description: Option<String>,
     ingredients: Vec<Ingredient>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_tags",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prep_time_minutes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cook_time_minutes: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    images: Vec<ImageRef>,
+    /// Url or book reference the recipe was taken from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    difficulty: Option<Difficulty>,
+    /// The course of the recipe. Unlike tags a recipe has at most one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    category: Option<Category>,
+}
+
+/// How demanding it is to cook a recipe.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Returns the German label shown to readers of the cookbook.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "einfach",
+            Difficulty::Medium => "mittel",
+            Difficulty::Hard => "schwer",
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// The course a recipe belongs to, by which the table of contents
+/// can be grouped.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum Category {
+    /// Vorspeise
+    Starter,
+    /// Hauptgericht
+    Main,
+    Dessert,
+    /// Beilage
+    Side,
+    /// Getränk
+    Drink,
+    /// Gebäck
+    Baking,
+    /// Any other category, normalized like a tag.
+    Other(String),
+}
+
+impl Category {
+    /// Returns the name of the category in recipes.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Category::Starter => "starter",
+            Category::Main => "main",
+            Category::Dessert => "dessert",
+            Category::Side => "side",
+            Category::Drink => "drink",
+            Category::Baking => "baking",
+            Category::Other(name) => name,
+        }
+    }
+}
+
+impl From<&str> for Category {
+    /// Looks up the category by its name.
+    ///
+    /// The lookup ignores case and surrounding whitespace. Unknown
+    /// names become [Category::Other] in lower case.
+    fn from(name: &str) -> Self {
+        let name = normalize_tag(name);
+        match name.as_str() {
+            "starter" => Category::Starter,
+            "main" => Category::Main,
+            "dessert" => Category::Dessert,
+            "side" => Category::Side,
+            "drink" => Category::Drink,
+            "baking" => Category::Baking,
+            _ => Category::Other(name),
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Category {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Category {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|name| Category::from(name.as_str()))
+    }
+}
+
+impl Recipe {
+    /// Returns the tags of the recipe in their normalized form.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Returns true if the recipe is tagged with `tag`.
+    ///
+    /// The tag is normalized before the comparison.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(&normalize_tag(tag))
+    }
+
+    /// Returns the url or book reference the recipe was taken from.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn difficulty(&self) -> Option<Difficulty> {
+        self.difficulty
+    }
+
+    pub fn category(&self) -> Option<&Category> {
+        self.category.as_ref()
+    }
+
+    /// Returns the number of servings the quantities are meant for.
+    pub fn servings(&self) -> u8 {
+        self.servings
+    }
+
+    pub fn ingredients(&self) -> &[Ingredient] {
+        &self.ingredients
+    }
+
+    /// Multiplies the quantities of all ingredients by `factor`.
+    ///
+    /// Returns `None` and leaves the recipe unchanged if a quantity
+    /// gets too large for a [Rational]. The servings and the yield
+    /// description are left unchanged, see [Recipe::scale_to] for
+    /// changing the servings.
+    #[must_use]
+    pub fn scale(&mut self, factor: Rational) -> Option<()> {
+        let quantities = self
+            .ingredients
+            .iter()
+            .map(|ingredient| ingredient.quantity.checked_mul(factor))
+            .collect::<Option<Vec<Rational>>>()?;
+
+        for (ingredient, quantity) in self.ingredients.iter_mut().zip(quantities) {
+            ingredient.quantity = quantity;
+        }
+        Some(())
+    }
+
+    /// Returns the recipe for another number of servings.
+    ///
+    /// The quantities are scaled by the ratio of the new and the old
+    /// servings. The yield description is kept as written, because
+    /// it cannot be scaled. Returns `None` if either number of
+    /// servings is 0 or if a quantity gets too large.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::Recipe;
+    ///
+    /// let json = r#"{"title": "Tee", "servings": 2, "ingredients": [
+    ///     {"name": "Wasser", "quantity": "500", "unit": "ml"}
+    /// ]}"#;
+    /// let tea: Recipe = serde_json::from_str(json).unwrap();
+    ///
+    /// let cup = tea.scale_to(1).unwrap();
+    /// assert_eq!(1, cup.servings());
+    /// assert_eq!("250 ml Wasser", cup.ingredients()[0].to_string());
+    /// ```
+    pub fn scale_to(&self, servings: u8) -> Option<Recipe> {
+        if servings == 0 || self.servings == 0 {
+            return None;
+        }
+
+        let mut scaled = self.clone();
+        scaled.scale(rat!(servings as i64, self.servings as i64))?;
+        scaled.servings = servings;
+        Some(scaled)
+    }
+
+    /// Returns what the recipe yields, for example "2 Gläser".
+    pub fn yield_description(&self) -> Option<&str> {
+        self.yield_description.as_deref()
+    }
+
+    /// Returns the total time in minutes needed to prepare and cook
+    /// the recipe.
+    ///
+    /// Missing times are ignored. If neither the preparation time
+    /// nor the cooking time is known, the total time is unknown as
+    /// well. A sum too large for an `u32` is capped at `u32::MAX`.
+    pub fn total_time(&self) -> Option<u32> {
+        match (self.prep_time_minutes, self.cook_time_minutes) {
+            (None, None) => None,
+            (prep, cook) => Some(prep.unwrap_or(0).saturating_add(cook.unwrap_or(0))),
+        }
+    }
+
+    /// Returns true if both recipes describe the same dish.
+    ///
+    /// Unlike `==` the comparison ignores the case and surrounding
+    /// whitespace of the title, the whitespace of the preparation and
+    /// the order of the ingredients. Ingredients are compared by
+    /// name and unit in lowercase and by quantity. All other fields
+    /// are ignored.
+    pub fn semantically_equals(&self, other: &Recipe) -> bool {
+        fn words(text: &str) -> impl Iterator<Item = &str> {
+            text.split_whitespace()
+        }
+
+        normalize_tag(&self.title) == normalize_tag(&other.title)
+            && self.servings == other.servings
+            && words(&self.preparation).eq(words(&other.preparation))
+            && same_ingredients(&self.ingredients, &other.ingredients)
+    }
+
+    /// Merges ingredients which are listed more than once.
+    ///
+    /// Ingredients with the same name and unit, compared in
+    /// lowercase without surrounding whitespace, are combined by
+    /// adding their quantities. The merged ingredient keeps the
+    /// position and spelling of its first appearance. Ingredients
+    /// with different units are kept apart.
+    pub fn normalize_ingredients(&mut self) {
+        self.ingredients = merge_ingredients(std::mem::take(&mut self.ingredients));
+    }
+}
+
+/// Combines the ingredients of several recipes into one list.
+///
+/// Ingredients are merged like [Recipe::normalize_ingredients] does,
+/// so the same ingredient in the same unit appears once with the sum
+/// of its quantities. The list is ordered by first appearance.
+///
+/// # Example
+///
+/// ```rust
+/// use recipers::{shopping_list, Recipe};
+///
+/// let json = r#"{"title": "Tee", "servings": 1, "ingredients": [
+///     {"name": "Wasser", "quantity": "250", "unit": "ml"}
+/// ]}"#;
+/// let tea: Recipe = serde_json::from_str(json).unwrap();
+///
+/// let list = shopping_list(&[tea.clone(), tea]);
+/// assert_eq!("500 ml Wasser", list[0].to_string());
+/// ```
+pub fn shopping_list(recipes: &[Recipe]) -> Vec<Ingredient> {
+    merge_ingredients(
+        recipes
+            .iter()
+            .flat_map(|recipe| recipe.ingredients.iter().cloned()),
+    )
+}
+
+/// Adds up the quantities of ingredients with the same name and unit.
+fn merge_ingredients(ingredients: impl IntoIterator<Item = Ingredient>) -> Vec<Ingredient> {
+    let mut merged: Vec<Ingredient> = Vec::new();
+
+    for ingredient in ingredients {
+        let same = merged.iter_mut().find(|existing| {
+            normalize_tag(&existing.name) == normalize_tag(&ingredient.name)
+                && normalize_tag(existing.unit.as_str()) == normalize_tag(ingredient.unit.as_str())
+        });
+
+        match same {
+            Some(existing) => existing.quantity = existing.quantity + ingredient.quantity,
+            None => merged.push(ingredient),
+        }
+    }
+
+    merged
+}
+
+/// Compares the ingredients as multisets.
+fn same_ingredients(left: &[Ingredient], right: &[Ingredient]) -> bool {
+    fn key(ingredient: &Ingredient) -> (String, String, Rational) {
+        (
+            normalize_tag(&ingredient.name),
+            normalize_tag(ingredient.unit.as_str()),
+            ingredient.quantity,
+        )
+    }
+
+    let left: Vec<_> = left.iter().map(key).collect();
+    let right: Vec<_> = right.iter().map(key).collect();
+    same_elements(&left, &right)
+}
+
+/// Returns true if both slices contain the same elements equally
+/// often, in any order.
+fn same_elements<T: PartialEq>(left: &[T], right: &[T]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+
+    let mut unmatched: Vec<&T> = right.iter().collect();
+    left.iter().all(
+        |wanted| match unmatched.iter().position(|candidate| *candidate == wanted) {
+            Some(index) => {
+                unmatched.swap_remove(index);
+                true
+            }
+            None => false,
+        },
+    )
+}
+
+/// Tags are compared in lowercase without surrounding whitespace.
+pub(crate) fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// Normalizes the tags of a recipe.
+///
+/// Empty tags are dropped and duplicates are removed, keeping the
+/// first occurrence.
+pub(crate) fn normalize_tags(raw: &[String]) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::with_capacity(raw.len());
+    for tag in raw.iter().map(|t| normalize_tag(t)) {
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Normalizes the tags of a recipe while deserializing it.
+fn deserialize_tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw: Vec<String> = Deserialize::deserialize(deserializer)?;
+    Ok(normalize_tags(&raw))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
+    use repository::SearchMode;
     use spucky::spec;
+    use testing::lasagne;
 
     spec! {
         serialize_json {
@@ -60,6 +793,7 @@ mod test {
                     preparation: "Du weist schon wie".into(),
                     servings: 4,
                     ingredients: vec![Ingredient { name: "Pasta".into(), quantity: rat!(5, 3), unit: "pc".into()}],
+                    ..Default::default()
                 };
 
                 let want = include_str!("fixture/lasagne.json");
@@ -81,7 +815,8 @@ mod test {
                     title: "Lasagne".into(),
                     preparation: "Du weist schon wie".into(),
                     servings: 4,
-                    ingredients: vec![Ingredient {name: "Pasta".into(), quantity: rat!(5, 3), unit: "pc".into()}]
+                    ingredients: vec![Ingredient {name: "Pasta".into(), quantity: rat!(5, 3), unit: "pc".into()}],
+                    ..Default::default()
                 };
             }
 
@@ -90,4 +825,768 @@ mod test {
         }
 
     }
+
+    spec! {
+        deserialize_tags {
+            case normalized {
+                let json = r#"{"title": "T", "servings": 1, "ingredients": [], "tags": [" Vegetarisch ", "SCHNELL"]}"#;
+                let want = vec!["vegetarisch".to_string(), "schnell".to_string()];
+            }
+
+            case duplicates_and_empty {
+                let json = r#"{"title": "T", "servings": 1, "ingredients": [], "tags": ["schnell", "", "Schnell"]}"#;
+                let want = vec!["schnell".to_string()];
+            }
+
+            case missing {
+                let json = r#"{"title": "T", "servings": 1, "ingredients": []}"#;
+                let want: Vec<String> = vec![];
+            }
+
+            let got: Recipe = serde_json::from_str(json).unwrap();
+            assert_eq!(want, got.tags);
+        }
+    }
+
+    spec! {
+        total_time {
+            case both {
+                let (prep, cook, want) = (Some(20), Some(45), Some(65));
+            }
+
+            case prep_only {
+                let (prep, cook, want) = (Some(20), None, Some(20));
+            }
+
+            case cook_only {
+                let (prep, cook, want) = (None, Some(45), Some(45));
+            }
+
+            case unknown {
+                let (prep, cook, want) = (None, None, None);
+            }
+
+            case too_long {
+                let (prep, cook, want) = (Some(u32::MAX), Some(1), Some(u32::MAX));
+            }
+
+            let recipe = Recipe {
+                prep_time_minutes: prep,
+                cook_time_minutes: cook,
+                ..Default::default()
+            };
+
+            assert_eq!(want, recipe.total_time());
+        }
+    }
+
+    #[test]
+    fn times_round_trip() {
+        let recipe = Recipe {
+            title: "Lasagne".into(),
+            servings: 4,
+            prep_time_minutes: Some(30),
+            cook_time_minutes: Some(45),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&recipe).unwrap();
+        assert!(json.contains(r#""prep_time_minutes":30"#));
+        assert!(json.contains(r#""cook_time_minutes":45"#));
+
+        let got: Recipe = serde_json::from_str(&json).unwrap();
+        assert_eq!(recipe, got);
+    }
+
+    #[test]
+    fn summary_contains_times() {
+        let id = Uuid::new_v4();
+        let recipe = Recipe {
+            title: "Lasagne".into(),
+            prep_time_minutes: Some(30),
+            cook_time_minutes: None,
+            ..Default::default()
+        };
+
+        let summary: Summary = (&id, &Stored::new(recipe, Utc::now())).into();
+        assert_eq!(summary.prep_time_minutes, Some(30));
+        assert_eq!(summary.cook_time_minutes, None);
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["prep_time_minutes"], 30);
+        assert!(json.get("cook_time_minutes").is_none());
+    }
+
+    spec! {
+        summary_matches {
+            case prefix {
+                let title = "Lasagne al forno";
+                let query = SearchQuery::new("las", SearchMode::Prefix);
+                let want = Some(0..3);
+            }
+
+            case prefix_elsewhere {
+                let title = "Lasagne al forno";
+                let query = SearchQuery::new("forno", SearchMode::Prefix);
+                let want = None;
+            }
+
+            case contains {
+                let title = "Apfelkuchen";
+                let query = SearchQuery::new("KUCHEN", SearchMode::Contains);
+                let want = Some(5..11);
+            }
+
+            case case_sensitive {
+                let title = "Apfelkuchen";
+                let query = SearchQuery::case_sensitive("Kuchen", SearchMode::Contains);
+                let want = None;
+            }
+
+            case umlaut {
+                let title = "Überbackene Äpfel";
+                let query = SearchQuery::new("äpf", SearchMode::Contains);
+                let want = Some(13..17);
+            }
+
+            case all_tokens {
+                let title = "Kartoffelsuppe mit Würstchen";
+                let query = SearchQuery::new("wür kart", SearchMode::AllTokens);
+                let want = Some(0..23);
+            }
+
+            case empty_query {
+                let title = "Lasagne";
+                let query = SearchQuery::new("", SearchMode::Contains);
+                let want = Some(0..0);
+            }
+
+            let recipe = Recipe {
+                title: title.into(),
+                ..Default::default()
+            };
+            let summary: Summary = (&Uuid::new_v4(), &Stored::new(recipe, Utc::now())).into();
+            assert_eq!(want, summary.matches(&query));
+            if let Some(range) = want {
+                assert!(title.get(range).is_some());
+            }
+        }
+    }
+
+    spec! {
+        category_from_json {
+            case known {
+                let json = r#""main""#;
+                let want = Category::Main;
+                let name = "main";
+            }
+
+            case ignores_case {
+                let json = r#"" Dessert ""#;
+                let want = Category::Dessert;
+                let name = "dessert";
+            }
+
+            case other {
+                let json = r#""Brotzeit""#;
+                let want = Category::Other("brotzeit".into());
+                let name = "brotzeit";
+            }
+
+            let category: Category = serde_json::from_str(json).unwrap();
+            assert_eq!(want, category);
+            assert_eq!(format!("{:?}", name), serde_json::to_string(&category).unwrap());
+        }
+    }
+
+    #[test]
+    fn summary_contains_category() {
+        let recipe: Recipe = serde_json::from_str(
+            r#"{"title": "Tiramisu", "servings": 4, "ingredients": [], "category": "dessert"}"#,
+        )
+        .unwrap();
+        assert_eq!(Some(&Category::Dessert), recipe.category());
+
+        let summary: Summary = (&Uuid::new_v4(), &Stored::new(recipe, Utc::now())).into();
+        assert_eq!(Some(&Category::Dessert), summary.category());
+        assert_eq!(
+            "dessert",
+            serde_json::to_value(&summary).unwrap()["category"]
+        );
+
+        let recipe = Recipe {
+            title: "Tee".into(),
+            ..Default::default()
+        };
+        let summary: Summary = (&Uuid::new_v4(), &Stored::new(recipe, Utc::now())).into();
+        assert!(serde_json::to_value(&summary)
+            .unwrap()
+            .get("category")
+            .is_none());
+    }
+
+    #[test]
+    fn attribution_round_trip() {
+        let json = r#"{
+            "title": "Lasagne",
+            "servings": 4,
+            "ingredients": [],
+            "source": "https://example.com/lasagne",
+            "author": "Oma Erna"
+        }"#;
+
+        let recipe: Recipe = serde_json::from_str(json).unwrap();
+        assert_eq!(recipe.source(), Some("https://example.com/lasagne"));
+        assert_eq!(recipe.author(), Some("Oma Erna"));
+
+        let got: Recipe = serde_json::from_str(&serde_json::to_string(&recipe).unwrap()).unwrap();
+        assert_eq!(recipe, got);
+
+        let plain: Recipe =
+            serde_json::from_str(r#"{"title": "Tee", "servings": 1, "ingredients": []}"#).unwrap();
+        let json = serde_json::to_value(&plain).unwrap();
+        assert!(json.get("source").is_none());
+        assert!(json.get("author").is_none());
+    }
+
+    spec! {
+        deserialize_difficulty {
+            case easy {
+                let json = r#"{"title": "Tee", "servings": 1, "ingredients": [], "difficulty": "easy"}"#;
+                let want = Some(Difficulty::Easy);
+            }
+
+            case hard {
+                let json = r#"{"title": "Tee", "servings": 1, "ingredients": [], "difficulty": "hard"}"#;
+                let want = Some(Difficulty::Hard);
+            }
+
+            case missing {
+                let json = r#"{"title": "Tee", "servings": 1, "ingredients": []}"#;
+                let want = None;
+            }
+
+            let recipe: Recipe = serde_json::from_str(json).unwrap();
+            assert_eq!(want, recipe.difficulty());
+
+            let got: Recipe = serde_json::from_str(&serde_json::to_string(&recipe).unwrap()).unwrap();
+            assert_eq!(recipe, got);
+        }
+    }
+
+    #[test]
+    fn reject_unknown_difficulty() {
+        for difficulty in ["einfach", "Easy", "extreme"] {
+            let json = format!(
+                r#"{{"title": "Tee", "servings": 1, "ingredients": [], "difficulty": "{}"}}"#,
+                difficulty
+            );
+            assert!(
+                serde_json::from_str::<Recipe>(&json).is_err(),
+                "{}",
+                difficulty
+            );
+        }
+    }
+
+    #[test]
+    fn display_difficulty() {
+        assert_eq!("einfach", Difficulty::Easy.to_string());
+        assert_eq!("mittel", Difficulty::Medium.to_string());
+        assert_eq!("schwer", Difficulty::Hard.to_string());
+        assert_eq!(
+            "\"medium\"",
+            serde_json::to_string(&Difficulty::Medium).unwrap()
+        );
+    }
+
+    spec! {
+        semantically_equals {
+            case identical {
+                let other = lasagne();
+                let want = true;
+            }
+
+            case reordered_ingredients {
+                let mut other = lasagne();
+                other.ingredients.reverse();
+                let want = true;
+            }
+
+            case whitespace_and_case {
+                let other = Recipe {
+                    title: "  LASAGNE ".into(),
+                    preparation: "Nudeln   kochen.\n\nSoße\tdarüber.".into(),
+                    ingredients: vec![
+                        Ingredient { name: "tomaten".into(), quantity: rat!(4, 2), unit: "".into() },
+                        Ingredient { name: " Pasta".into(), quantity: rat!(500), unit: "G".into() },
+                    ],
+                    ..lasagne()
+                };
+                let want = true;
+            }
+
+            case other_ingredient {
+                let mut other = lasagne();
+                other.ingredients[0].quantity = rat!(250);
+                let want = false;
+            }
+
+            case duplicated_ingredient {
+                let mut other = lasagne();
+                other.ingredients[1] = other.ingredients[0].clone();
+                let want = false;
+            }
+
+            case other_servings {
+                let other = Recipe { servings: 2, ..lasagne() };
+                let want = false;
+            }
+
+            case other_tags {
+                let other = Recipe { tags: vec!["vegetarisch".into()], ..lasagne() };
+                let want = true;
+            }
+
+            case other_recipe {
+                let other = Recipe { title: "Pfannkuchen".into(), ..lasagne() };
+                let want = false;
+            }
+
+            let recipe = lasagne();
+            assert_eq!(want, recipe.semantically_equals(&other));
+            assert_eq!(want, other.semantically_equals(&recipe));
+        }
+    }
+
+    spec! {
+        ingredient_order_only_matters_to_eq {
+            case swapped {
+                let recipe = lasagne();
+                let mut other = lasagne();
+                other.ingredients.swap(0, 1);
+            }
+
+            case rotated {
+                let mut recipe = lasagne();
+                recipe.ingredients.push(Ingredient {
+                    name: "Käse".into(),
+                    quantity: rat!(200),
+                    unit: "g".into(),
+                });
+                let mut other = recipe.clone();
+                other.ingredients.rotate_left(1);
+            }
+
+            assert_ne!(recipe, other);
+            assert!(recipe.semantically_equals(&other));
+        }
+    }
+
+    spec! {
+        normalize_ingredients {
+            case no_duplicates {
+                let ingredients = vec![
+                    Ingredient { name: "Mehl".into(), quantity: rat!(500), unit: "g".into() },
+                    Ingredient { name: "Salz".into(), quantity: rat!(1), unit: "Prise".into() },
+                ];
+                let want = ingredients.clone();
+            }
+
+            case duplicates {
+                let ingredients = vec![
+                    Ingredient { name: "Salz".into(), quantity: rat!(1), unit: "TL".into() },
+                    Ingredient { name: "Mehl".into(), quantity: rat!(500), unit: "g".into() },
+                    Ingredient { name: "Salz".into(), quantity: rat!(1, 2), unit: "TL".into() },
+                ];
+                let want = vec![
+                    Ingredient { name: "Salz".into(), quantity: rat!(3, 2), unit: "TL".into() },
+                    Ingredient { name: "Mehl".into(), quantity: rat!(500), unit: "g".into() },
+                ];
+            }
+
+            case differing_case {
+                let ingredients = vec![
+                    Ingredient { name: "Eier".into(), quantity: rat!(2), unit: "".into() },
+                    Ingredient { name: " eier".into(), quantity: rat!(1), unit: "".into() },
+                    Ingredient { name: "Zucker".into(), quantity: rat!(100), unit: "g".into() },
+                    Ingredient { name: "zucker".into(), quantity: rat!(50), unit: "G".into() },
+                ];
+                let want = vec![
+                    Ingredient { name: "Eier".into(), quantity: rat!(3), unit: "".into() },
+                    Ingredient { name: "Zucker".into(), quantity: rat!(150), unit: "g".into() },
+                ];
+            }
+
+            case incompatible_units {
+                let ingredients = vec![
+                    Ingredient { name: "Salz".into(), quantity: rat!(1), unit: "TL".into() },
+                    Ingredient { name: "Salz".into(), quantity: rat!(1), unit: "Prise".into() },
+                ];
+                let want = ingredients.clone();
+            }
+
+            let mut recipe = Recipe { ingredients, ..Default::default() };
+            recipe.normalize_ingredients();
+            assert_eq!(want, recipe.ingredients);
+        }
+    }
+
+    spec! {
+        shopping_list {
+            case shared_ingredient {
+                let recipes = [
+                    lasagne(),
+                    Recipe {
+                        ingredients: vec![
+                            Ingredient { name: "Tomaten".into(), quantity: rat!(3), unit: "".into() },
+                            Ingredient { name: "Basilikum".into(), quantity: rat!(1), unit: "Bund".into() },
+                        ],
+                        ..Default::default()
+                    },
+                ];
+                let want = vec![
+                    Ingredient { name: "Pasta".into(), quantity: rat!(500), unit: "g".into() },
+                    Ingredient { name: "Tomaten".into(), quantity: rat!(5), unit: "".into() },
+                    Ingredient { name: "Basilikum".into(), quantity: rat!(1), unit: "Bund".into() },
+                ];
+            }
+
+            case aliased_units {
+                let recipes = [
+                    lasagne(),
+                    Recipe {
+                        ingredients: vec![
+                            Ingredient { name: "pasta".into(), quantity: rat!(250), unit: "Gramm".into() },
+                        ],
+                        ..Default::default()
+                    },
+                ];
+                let want = vec![
+                    Ingredient { name: "Pasta".into(), quantity: rat!(750), unit: "g".into() },
+                    Ingredient { name: "Tomaten".into(), quantity: rat!(2), unit: "".into() },
+                ];
+            }
+
+            case differing_units {
+                let recipes = [
+                    lasagne(),
+                    Recipe {
+                        ingredients: vec![
+                            Ingredient { name: "Pasta".into(), quantity: rat!(1), unit: "kg".into() },
+                        ],
+                        ..Default::default()
+                    },
+                ];
+                let want = vec![
+                    Ingredient { name: "Pasta".into(), quantity: rat!(500), unit: "g".into() },
+                    Ingredient { name: "Tomaten".into(), quantity: rat!(2), unit: "".into() },
+                    Ingredient { name: "Pasta".into(), quantity: rat!(1), unit: "kg".into() },
+                ];
+            }
+
+            case no_recipes {
+                let recipes: [Recipe; 0] = [];
+                let want: Vec<Ingredient> = vec![];
+            }
+
+            assert_eq!(want, super::shopping_list(&recipes));
+        }
+    }
+
+    spec! {
+        scale_to_servings {
+            case double {
+                let servings = 8;
+                let want = Some(vec![rat!(1000), rat!(4)]);
+            }
+
+            case half {
+                let servings = 2;
+                let want = Some(vec![rat!(250), rat!(1)]);
+            }
+
+            case odd {
+                let servings = 3;
+                let want = Some(vec![rat!(375), rat!(3, 2)]);
+            }
+
+            case same {
+                let servings = 4;
+                let want = Some(vec![rat!(500), rat!(2)]);
+            }
+
+            case nothing {
+                let servings = 0;
+                let want = None;
+            }
+
+            let recipe = Recipe { yield_description: Some("1 Auflaufform".into()), ..lasagne() };
+            let scaled = recipe.scale_to(servings);
+
+            let quantities = scaled.as_ref().map(|scaled| {
+                scaled.ingredients.iter().map(|i| i.quantity).collect::<Vec<_>>()
+            });
+            assert_eq!(want, quantities);
+            if let Some(scaled) = scaled {
+                assert_eq!(servings, scaled.servings);
+                assert_eq!(recipe.yield_description, scaled.yield_description);
+                assert_eq!(recipe.title, scaled.title);
+            }
+        }
+    }
+
+    #[test]
+    fn scale_recipe_without_servings() {
+        let recipe = Recipe {
+            servings: 0,
+            ..lasagne()
+        };
+        assert_eq!(None, recipe.scale_to(4));
+    }
+
+    #[test]
+    fn scale_recipe_too_large() {
+        let mut recipe = lasagne();
+        recipe.ingredients[1].quantity = rat!(i64::MAX);
+
+        assert_eq!(None, recipe.scale_to(recipe.servings * 3));
+        assert!(recipe.clone().scale(rat!(1, 3)).is_some());
+        assert_eq!(None, recipe.scale(rat!(2)));
+        assert_eq!(rat!(500), recipe.ingredients[0].quantity);
+        assert_eq!(rat!(i64::MAX), recipe.ingredients[1].quantity);
+    }
+
+    #[test]
+    fn yield_round_trip() {
+        let json =
+            r#"{"title": "Marmelade", "servings": 8, "yield": "2 Gläser", "ingredients": []}"#;
+
+        let recipe: Recipe = serde_json::from_str(json).unwrap();
+        assert_eq!(recipe.yield_description(), Some("2 Gläser"));
+        assert_eq!(recipe.servings, 8);
+
+        let json = serde_json::to_value(&recipe).unwrap();
+        assert_eq!(json["yield"], "2 Gläser");
+        assert!(json.get("yield_description").is_none());
+
+        let got: Recipe = serde_json::from_value(json).unwrap();
+        assert_eq!(recipe, got);
+    }
+
+    #[test]
+    fn serialize_timestamps() {
+        let now = DateTime::parse_from_rfc3339("2023-04-01T12:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let recipe = Recipe {
+            title: "Lasagne".into(),
+            ..Default::default()
+        };
+        let stored = Stored::new(recipe, now);
+
+        let json = serde_json::to_value(&stored).unwrap();
+        assert_eq!(json["title"], "Lasagne");
+        assert_eq!(json["created_at"], "2023-04-01T12:30:00Z");
+        assert_eq!(json["updated_at"], "2023-04-01T12:30:00Z");
+        assert_eq!(json["revision"], 1);
+
+        let summary: Summary = (&Uuid::new_v4(), &stored).into();
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["updated_at"], "2023-04-01T12:30:00Z");
+    }
+
+    #[test]
+    fn images_round_trip() {
+        let json = r#"{
+            "title": "Lasagne",
+            "servings": 4,
+            "ingredients": [],
+            "images": [
+                {"url": "https://example.com/lasagne.jpg", "caption": "Frisch aus dem Ofen"},
+                {"url": "/images/lasagne-2.jpg"}
+            ]
+        }"#;
+
+        let recipe: Recipe = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            recipe.images,
+            vec![
+                ImageRef {
+                    url: "https://example.com/lasagne.jpg".into(),
+                    caption: Some("Frisch aus dem Ofen".into())
+                },
+                ImageRef {
+                    url: "/images/lasagne-2.jpg".into(),
+                    caption: None
+                },
+            ]
+        );
+
+        let copy: Recipe = serde_json::from_str(&serde_json::to_string(&recipe).unwrap()).unwrap();
+        assert_eq!(recipe, copy);
+    }
+
+    spec! {
+        validate_image_url {
+            case https {
+                let url = "https://example.com/lasagne.jpg";
+                let valid = true;
+            }
+
+            case http {
+                let url = "http://example.com/lasagne.jpg";
+                let valid = true;
+            }
+
+            case absolute_path {
+                let url = "/images/lasagne.jpg";
+                let valid = true;
+            }
+
+            case relative_path {
+                let url = "images/lasagne.jpg";
+                let valid = true;
+            }
+
+            case empty {
+                let url = "";
+                let valid = false;
+            }
+
+            case whitespace {
+                let url = "https://example.com/my lasagne.jpg";
+                let valid = false;
+            }
+
+            case other_scheme {
+                let url = "ftp://example.com/lasagne.jpg";
+                let valid = false;
+            }
+
+            case missing_host {
+                let url = "http://";
+                let valid = false;
+            }
+
+            let image = ImageRef { url: url.into(), caption: None };
+            assert_eq!(valid, image.validate().is_ok());
+        }
+    }
+
+    spec! {
+        summary_thumbnail {
+            case no_images {
+                let images = vec![];
+                let want = None;
+            }
+
+            case first_image {
+                let images = vec![
+                    ImageRef { url: "/a.jpg".into(), caption: None },
+                    ImageRef { url: "/b.jpg".into(), caption: None },
+                ];
+                let want = Some("/a.jpg".to_string());
+            }
+
+            let recipe = Recipe { images, ..Default::default() };
+            let summary: Summary = (&Uuid::new_v4(), &Stored::new(recipe, Utc::now())).into();
+            assert_eq!(want, summary.thumbnail);
+        }
+    }
+
+    spec! {
+        display_ingredient {
+            case with_unit {
+                let ingredient = Ingredient { name: "Mehl".into(), quantity: rat!(3, 2), unit: "kg".into() };
+                let want = "1½ kg Mehl";
+            }
+
+            case without_unit {
+                let ingredient = Ingredient { name: "Eier".into(), quantity: rat!(2), unit: "".into() };
+                let want = "2 Eier";
+            }
+
+            assert_eq!(want, ingredient.to_string());
+        }
+    }
+
+    spec! {
+        parse_ingredient {
+            case with_unit {
+                let line = "1½ kg Mehl";
+                let want = Ok(Ingredient { name: "Mehl".into(), quantity: rat!(3, 2), unit: "kg".into() });
+            }
+
+            case without_unit {
+                let line = "2 Eier";
+                let want = Ok(Ingredient { name: "Eier".into(), quantity: rat!(2), unit: "".into() });
+            }
+
+            case mixed_number {
+                let line = "1 1/2 TL Backpulver";
+                let want = Ok(Ingredient { name: "Backpulver".into(), quantity: rat!(3, 2), unit: "TL".into() });
+            }
+
+            case long_name {
+                let line = "200 g passierte Tomaten";
+                let want = Ok(Ingredient { name: "passierte Tomaten".into(), quantity: rat!(200), unit: "g".into() });
+            }
+
+            case long_name_without_unit {
+                let line = "2 Eier Größe M";
+                let want = Ok(Ingredient { name: "Eier Größe M".into(), quantity: rat!(2), unit: "".into() });
+            }
+
+            case unknown_unit {
+                let line = "1 Bund Petersilie";
+                let want = Ok(Ingredient { name: "Bund Petersilie".into(), quantity: rat!(1), unit: "".into() });
+            }
+
+            case missing_quantity {
+                let line = "Salz";
+                let want = Err(IngredientParseError::MissingQuantity);
+            }
+
+            case missing_name {
+                let line = "3";
+                let want = Err(IngredientParseError::MissingName);
+            }
+
+            case empty {
+                let line = "";
+                let want = Err(IngredientParseError::MissingQuantity);
+            }
+
+            let got = line.parse::<Ingredient>();
+            assert_eq!(want, got);
+        }
+    }
+
+    spec! {
+        ingredient_round_trip {
+            case with_unit {
+                let ingredient = Ingredient { name: "passierte Tomaten".into(), quantity: rat!(800), unit: "g".into() };
+            }
+
+            case piece {
+                let ingredient = Ingredient { name: "Pasta".into(), quantity: rat!(5, 3), unit: "pc".into() };
+            }
+
+            case without_unit {
+                let ingredient = Ingredient { name: "Eier".into(), quantity: rat!(3), unit: "".into() };
+            }
+
+            case long_name_without_unit {
+                let ingredient = Ingredient { name: "Eier Größe M".into(), quantity: rat!(2), unit: "".into() };
+            }
+
+            case lower_case_name_without_unit {
+                let ingredient = Ingredient { name: "rote Zwiebeln".into(), quantity: rat!(3, 2), unit: "".into() };
+            }
+
+            let parsed: Ingredient = ingredient.to_string().parse().unwrap();
+            assert_eq!(ingredient, parsed);
+        }
+    }
 }
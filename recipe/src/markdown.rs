@@ -0,0 +1,554 @@
+use crate::{Ingredient, IngredientParseError, Recipe};
+use std::{
+    error,
+    fmt::{self, Write},
+};
+
+/// Number of servings assumed if the text does not mention it.
+const DEFAULT_SERVINGS: u8 = 1;
+
+impl Recipe {
+    /// Renders the recipe as Markdown.
+    ///
+    /// The document starts with the title as heading, followed by the
+    /// number of servings, the list of ingredients and the numbered
+    /// steps of the preparation. Each non-empty line of the
+    /// preparation is a step. Author and source are written to a
+    /// trailing attribution line. Sections without content are
+    /// omitted. The output only depends on the recipe, so it is
+    /// suitable for comparing against a snapshot.
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::new();
+        self.write_markdown(&mut markdown)
+            .expect("writing to a string cannot fail");
+        markdown
+    }
+
+    fn write_markdown(&self, out: &mut impl Write) -> fmt::Result {
+        writeln!(out, "# {}", self.title)?;
+        writeln!(out)?;
+        writeln!(out, "Portionen: {}", self.servings)?;
+        if let Some(description) = &self.yield_description {
+            writeln!(out, "Ergibt: {}", description)?;
+        }
+
+        if !self.ingredients.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "## Zutaten")?;
+            writeln!(out)?;
+            for ingredient in &self.ingredients {
+                writeln!(out, "- {}", ingredient)?;
+            }
+        }
+
+        let steps: Vec<&str> = self.steps().collect();
+        if !steps.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "## Zubereitung")?;
+            writeln!(out)?;
+            for (number, step) in steps.iter().enumerate() {
+                writeln!(out, "{}. {}", number + 1, step)?;
+            }
+        }
+
+        let attribution: Vec<String> = [("Autor", &self.author), ("Quelle", &self.source)]
+            .iter()
+            .filter_map(|(label, value)| {
+                value.as_ref().map(|value| format!("{}: {}", label, value))
+            })
+            .collect();
+        if !attribution.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "{}", attribution.join(" · "))?;
+        }
+
+        Ok(())
+    }
+
+    /// Parses a recipe from Markdown or plain text.
+    ///
+    /// Markdown is expected in the layout written by
+    /// [Recipe::to_markdown]: the title as heading, the number of
+    /// servings, a bullet list of ingredients below "## Zutaten" and
+    /// numbered or plain steps below "## Zubereitung". Other sections
+    /// are ignored.
+    ///
+    /// Text which does not start with a heading is read as plain
+    /// text. The first line is the title, lines starting with a
+    /// quantity are ingredients and all other lines are the
+    /// preparation.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use recipers::Recipe;
+    ///
+    /// let recipe = Recipe::from_markdown("Tee\n\n1 Teebeutel\nAufgießen.").unwrap();
+    /// assert_eq!("# Tee\n\nPortionen: 1\n\n## Zutaten\n\n- 1 Teebeutel\n\n## Zubereitung\n\n1. Aufgießen.\n", recipe.to_markdown());
+    /// ```
+    pub fn from_markdown(text: &str) -> Result<Recipe, RecipeParseError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let (_, first) = lines.next().ok_or(RecipeParseError::MissingTitle)?;
+
+        let mut recipe = Recipe {
+            servings: DEFAULT_SERVINGS,
+            ..Default::default()
+        };
+        let mut steps: Vec<&str> = Vec::new();
+
+        let heading = first
+            .strip_prefix('#')
+            .filter(|rest| !rest.starts_with('#'));
+
+        match heading {
+            Some(title) => {
+                recipe.title = title.trim().to_string();
+
+                let mut section = Section::Head;
+                for (number, line) in lines {
+                    if parse_attribution(&mut recipe, line) {
+                        continue;
+                    }
+
+                    if let Some(heading) = line.strip_prefix("## ") {
+                        section = Section::from_heading(heading);
+                        continue;
+                    }
+
+                    match section {
+                        Section::Head => {
+                            if let Some(servings) = parse_servings(number, line)? {
+                                recipe.servings = servings;
+                            } else if let Some(description) = parse_yield(line) {
+                                recipe.yield_description = Some(description);
+                            }
+                        }
+                        Section::Ingredients => {
+                            let ingredient = strip_bullet(line).parse().map_err(|source| {
+                                RecipeParseError::InvalidIngredient {
+                                    line: number,
+                                    source,
+                                }
+                            })?;
+                            recipe.ingredients.push(ingredient);
+                        }
+                        Section::Preparation => steps.push(strip_numbering(line)),
+                        Section::Other => {}
+                    }
+                }
+            }
+            None => {
+                recipe.title = first.to_string();
+
+                for (number, line) in lines {
+                    if parse_attribution(&mut recipe, line) {
+                        continue;
+                    }
+
+                    if let Some(servings) = parse_servings(number, line)? {
+                        recipe.servings = servings;
+                    } else if let Some(description) = parse_yield(line) {
+                        recipe.yield_description = Some(description);
+                    } else if let Some(ingredient) = parse_ingredient_line(line) {
+                        recipe.ingredients.push(ingredient);
+                    } else {
+                        steps.push(strip_numbering(line));
+                    }
+                }
+            }
+        }
+
+        if recipe.title.is_empty() {
+            return Err(RecipeParseError::MissingTitle);
+        }
+
+        recipe.preparation = steps.join("\n");
+        Ok(recipe)
+    }
+
+    /// Returns the non-empty lines of the preparation.
+    pub(crate) fn steps(&self) -> impl Iterator<Item = &str> {
+        self.preparation
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+    }
+}
+
+enum Section {
+    Head,
+    Ingredients,
+    Preparation,
+    Other,
+}
+
+impl Section {
+    fn from_heading(heading: &str) -> Section {
+        match heading.trim().to_lowercase().as_str() {
+            "zutaten" => Section::Ingredients,
+            "zubereitung" => Section::Preparation,
+            _ => Section::Other,
+        }
+    }
+}
+
+/// Parses a line like "Portionen: 4".
+///
+/// Returns None if the line does not mention the servings.
+fn parse_servings(number: usize, line: &str) -> Result<Option<u8>, RecipeParseError> {
+    match line.strip_prefix("Portionen:") {
+        Some(servings) => servings
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| RecipeParseError::InvalidServings { line: number }),
+        None => Ok(None),
+    }
+}
+
+/// Parses a line like "Ergibt: 1 Blech".
+fn parse_yield(line: &str) -> Option<String> {
+    line.strip_prefix("Ergibt:")
+        .map(|description| description.trim().to_string())
+}
+
+/// Parses an attribution line like "Autor: Oma Erna · Quelle:
+/// Das große Kochbuch" into the recipe.
+///
+/// Returns false if the line is not an attribution.
+fn parse_attribution(recipe: &mut Recipe, line: &str) -> bool {
+    if !(line.starts_with("Autor:") || line.starts_with("Quelle:")) {
+        return false;
+    }
+
+    for part in line.split(" · ") {
+        if let Some(author) = part.strip_prefix("Autor:") {
+            recipe.author = Some(author.trim().to_string());
+        } else if let Some(source) = part.strip_prefix("Quelle:") {
+            recipe.source = Some(source.trim().to_string());
+        }
+    }
+    true
+}
+
+/// Parses a line of plain text as ingredient if it looks like one.
+///
+/// Sentences ending with a period are never ingredients, even if
+/// they start with a number.
+fn parse_ingredient_line(line: &str) -> Option<Ingredient> {
+    if line.ends_with('.') {
+        return None;
+    }
+
+    strip_bullet(line).parse().ok()
+}
+
+fn strip_bullet(line: &str) -> &str {
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+        .unwrap_or(line)
+        .trim()
+}
+
+/// Removes the number of a step like "1." or "2)".
+fn strip_numbering(line: &str) -> &str {
+    let rest = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() < line.len() {
+        if let Some(step) = rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")) {
+            return step.trim();
+        }
+    }
+    line
+}
+
+/// The error returned if a text cannot be parsed as recipe.
+///
+/// Line numbers start at 1.
+#[derive(Debug, PartialEq)]
+pub enum RecipeParseError {
+    MissingTitle,
+    InvalidServings {
+        line: usize,
+    },
+    InvalidIngredient {
+        line: usize,
+        source: IngredientParseError,
+    },
+}
+
+impl fmt::Display for RecipeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecipeParseError::MissingTitle => write!(f, "title expected"),
+            RecipeParseError::InvalidServings { line } => {
+                write!(f, "line {}: invalid number of servings", line)
+            }
+            RecipeParseError::InvalidIngredient { line, source } => {
+                write!(f, "line {}: invalid ingredient: {}", line, source)
+            }
+        }
+    }
+}
+
+impl error::Error for RecipeParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RecipeParseError::InvalidIngredient { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RecipeParseError;
+    use crate::{rat, Ingredient, IngredientParseError, Rational, Recipe};
+    use spucky::spec;
+
+    #[test]
+    fn lasagne_to_markdown() {
+        let recipe: Recipe = serde_json::from_str(include_str!("fixture/lasagne.json")).unwrap();
+        let want = include_str!("fixture/lasagne.md");
+
+        assert_eq!(want, recipe.to_markdown());
+    }
+
+    spec! {
+        to_markdown {
+            case no_ingredients {
+                let recipe = Recipe {
+                    title: "Wasser".into(),
+                    preparation: "Kochen".into(),
+                    servings: 1,
+                    ..Default::default()
+                };
+                let want = "# Wasser\n\nPortionen: 1\n\n## Zubereitung\n\n1. Kochen\n";
+            }
+
+            case no_preparation {
+                let recipe = Recipe {
+                    title: "Brot".into(),
+                    servings: 1,
+                    ingredients: vec![Ingredient { name: "Brot".into(), quantity: rat!(1), unit: "".into() }],
+                    ..Default::default()
+                };
+                let want = "# Brot\n\nPortionen: 1\n\n## Zutaten\n\n- 1 Brot\n";
+            }
+
+            case vulgar_fractions {
+                let recipe = Recipe {
+                    title: "Pfannkuchen".into(),
+                    preparation: "Alles verrühren.\n\n  Ausbacken.  \n".into(),
+                    servings: 2,
+                    ingredients: vec![
+                        Ingredient { name: "Mehl".into(), quantity: rat!(1, 2), unit: "kg".into() },
+                        Ingredient { name: "Milch".into(), quantity: rat!(3, 4), unit: "l".into() },
+                        Ingredient { name: "Eier".into(), quantity: rat!(7, 3), unit: "".into() },
+                    ],
+                    ..Default::default()
+                };
+                let want = "# Pfannkuchen\n\nPortionen: 2\n\n## Zutaten\n\n- ½ kg Mehl\n- ¾ l Milch\n- 2⅓ Eier\n\n## Zubereitung\n\n1. Alles verrühren.\n2. Ausbacken.\n";
+            }
+
+            case attribution {
+                let recipe = Recipe {
+                    title: "Tee".into(),
+                    preparation: "Aufgießen".into(),
+                    servings: 1,
+                    author: Some("Oma Erna".into()),
+                    source: Some("https://example.com/tee".into()),
+                    ..Default::default()
+                };
+                let want = "# Tee\n\nPortionen: 1\n\n## Zubereitung\n\n1. Aufgießen\n\nAutor: Oma Erna · Quelle: https://example.com/tee\n";
+            }
+
+            case yield_description {
+                let recipe = Recipe {
+                    title: "Blechkuchen".into(),
+                    servings: 12,
+                    yield_description: Some("1 Blech".into()),
+                    ..Default::default()
+                };
+                let want = "# Blechkuchen\n\nPortionen: 12\nErgibt: 1 Blech\n";
+            }
+
+            case source_only {
+                let recipe = Recipe {
+                    title: "Tee".into(),
+                    servings: 1,
+                    source: Some("Das große Kochbuch, S. 12".into()),
+                    ..Default::default()
+                };
+                let want = "# Tee\n\nPortionen: 1\n\nQuelle: Das große Kochbuch, S. 12\n";
+            }
+
+            assert_eq!(want, recipe.to_markdown());
+        }
+    }
+
+    spec! {
+        markdown_round_trip {
+            case lasagne {
+                let recipe: Recipe = serde_json::from_str(include_str!("fixture/lasagne.json")).unwrap();
+            }
+
+            case without_ingredients {
+                let recipe = Recipe {
+                    title: "Wasser".into(),
+                    preparation: "Kochen".into(),
+                    servings: 1,
+                    ..Default::default()
+                };
+            }
+
+            case attribution {
+                let recipe = Recipe {
+                    title: "Tee".into(),
+                    preparation: "Aufgießen".into(),
+                    servings: 1,
+                    author: Some("Oma Erna".into()),
+                    source: Some("Das große Kochbuch, S. 12".into()),
+                    ..Default::default()
+                };
+            }
+
+            case yield_description {
+                let recipe = Recipe {
+                    title: "Marmelade".into(),
+                    preparation: "Einkochen".into(),
+                    servings: 8,
+                    yield_description: Some("2 Gläser".into()),
+                    ..Default::default()
+                };
+            }
+
+            case mixed_numbers {
+                let recipe = Recipe {
+                    title: "Pfannkuchen".into(),
+                    preparation: "Alles verrühren.\nAusbacken.".into(),
+                    servings: 2,
+                    ingredients: vec![
+                        Ingredient { name: "Mehl".into(), quantity: rat!(1, 2), unit: "kg".into() },
+                        Ingredient { name: "Eier".into(), quantity: rat!(7, 3), unit: "".into() },
+                        Ingredient { name: "Backpulver".into(), quantity: rat!(12, 11), unit: "TL".into() },
+                    ],
+                    ..Default::default()
+                };
+            }
+
+            let got = Recipe::from_markdown(&recipe.to_markdown()).unwrap();
+            assert_eq!(recipe, got);
+        }
+    }
+
+    #[test]
+    fn parse_pasted_markdown() {
+        let recipe = Recipe::from_markdown(include_str!("fixture/tomatensauce.md")).unwrap();
+
+        assert_eq!(recipe.title, "Tomatensauce");
+        assert_eq!(recipe.servings, 4);
+        assert_eq!(
+            recipe.ingredients,
+            vec![
+                Ingredient {
+                    name: "passierte Tomaten".into(),
+                    quantity: rat!(800),
+                    unit: "g".into()
+                },
+                Ingredient {
+                    name: "Zwiebel".into(),
+                    quantity: rat!(1),
+                    unit: "".into()
+                },
+                Ingredient {
+                    name: "Olivenöl".into(),
+                    quantity: rat!(2),
+                    unit: "EL".into()
+                },
+                Ingredient {
+                    name: "Salz".into(),
+                    quantity: rat!(1, 2),
+                    unit: "TL".into()
+                },
+            ]
+        );
+        assert_eq!(
+            recipe.preparation,
+            "Die Zwiebel fein würfeln und im Olivenöl glasig dünsten.\n\
+             Die Tomaten dazugeben und 20 Minuten köcheln lassen.\n\
+             Mit Salz abschmecken."
+        );
+    }
+
+    #[test]
+    fn parse_pasted_plain_text() {
+        let recipe = Recipe::from_markdown(include_str!("fixture/pfannkuchen.txt")).unwrap();
+
+        assert_eq!(recipe.title, "Pfannkuchen");
+        assert_eq!(recipe.servings, 1);
+        assert_eq!(
+            recipe.ingredients,
+            vec![
+                Ingredient {
+                    name: "Mehl".into(),
+                    quantity: rat!(250),
+                    unit: "g".into()
+                },
+                Ingredient {
+                    name: "Milch".into(),
+                    quantity: rat!(500),
+                    unit: "ml".into()
+                },
+                Ingredient {
+                    name: "Eier".into(),
+                    quantity: rat!(3),
+                    unit: "".into()
+                },
+                Ingredient {
+                    name: "Salz".into(),
+                    quantity: rat!(1),
+                    unit: "Prise".into()
+                },
+            ]
+        );
+        assert_eq!(
+            recipe.preparation,
+            "Mehl, Milch, Eier und Salz zu einem glatten Teig verrühren.\n\
+             Den Teig 30 Minuten quellen lassen.\n\
+             In einer heißen Pfanne nacheinander dünne Pfannkuchen ausbacken."
+        );
+    }
+
+    spec! {
+        from_markdown_error {
+            case empty {
+                let text = "  \n\n";
+                let want = RecipeParseError::MissingTitle;
+            }
+
+            case empty_title {
+                let text = "# \n\nPortionen: 2\n";
+                let want = RecipeParseError::MissingTitle;
+            }
+
+            case invalid_servings {
+                let text = "# Suppe\n\nPortionen: viele\n";
+                let want = RecipeParseError::InvalidServings { line: 3 };
+            }
+
+            case invalid_ingredient {
+                let text = "# Suppe\n\n## Zutaten\n\n- 1 l Wasser\n- Salz\n";
+                let want = RecipeParseError::InvalidIngredient { line: 6, source: IngredientParseError::MissingQuantity };
+            }
+
+            let got = Recipe::from_markdown(text).unwrap_err();
+            assert_eq!(want, got);
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use std::ops::{Add, Div, Mul, Sub};
 
-use serde::{de::Visitor, Deserialize, Serialize};
+use serde::{
+    de::{Unexpected, Visitor},
+    Deserialize, Serialize,
+};
 
 mod format;
 mod parse;
@@ -105,16 +108,56 @@ impl Rational {
         }
 
         let gcd = gcd(numerator, denominator);
-        let sign = (numerator * denominator).signum();
+        let sign = numerator.signum() * denominator.signum();
         Rational {
             numerator: sign * (numerator / gcd).abs(),
             denominator: (denominator / gcd).abs(),
         }
     }
 
+    /// Returns true if the number is greater than 0.
+    pub const fn is_positive(&self) -> bool {
+        self.numerator > 0
+    }
+
+    /// Multiplies two numbers like `*`, but returns `None` instead of
+    /// overflowing if the product does not fit into an `i64` fraction.
+    pub fn checked_mul(self, rhs: Rational) -> Option<Rational> {
+        // Cancelling first keeps the products as small as possible.
+        let left = gcd(self.numerator, rhs.denominator);
+        let right = gcd(rhs.numerator, self.denominator);
+        let numerator = (self.numerator / left).checked_mul(rhs.numerator / right)?;
+        let denominator = (self.denominator / right).checked_mul(rhs.denominator / left)?;
+        numerator.checked_abs()?;
+
+        Some(Rational::new(numerator, denominator))
+    }
+
+    /// Converts a float into the fraction of its shortest decimal
+    /// representation, like 0.1 into 1/10 instead of the binary
+    /// approximation.
+    ///
+    /// Returns `None` for infinite numbers, NaN and numbers with too
+    /// many digits for an `i64`. `i64::MIN` is rejected as well,
+    /// because its absolute value does not fit.
+    fn from_decimal(value: f64) -> Option<Rational> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        // Display never uses an exponent for floats.
+        let text = value.to_string();
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let denominator = 10i64.checked_pow(u32::try_from(fraction.len()).ok()?)?;
+        let numerator: i64 = format!("{}{}", whole, fraction).parse().ok()?;
+        numerator.checked_abs()?;
+
+        Some(Rational::new(numerator, denominator))
+    }
+
     fn normalize(self) -> Self {
         let gcd = gcd(self.numerator, self.denominator);
-        let sign = (self.numerator * self.denominator).signum();
+        let sign = self.numerator.signum() * self.denominator.signum();
         Rational {
             numerator: sign * (self.numerator / gcd).abs(),
             denominator: (self.denominator / gcd).abs(),
@@ -189,6 +232,33 @@ impl<'de> Visitor<'de> for RationalVisitor {
         formatter.write_str("rational number")
     }
 
+    fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match v.checked_abs() {
+            Some(_) => Ok(Rational::from(v)),
+            None => Err(E::invalid_value(Unexpected::Signed(v), &self)),
+        }
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        match i64::try_from(v) {
+            Ok(v) => Ok(Rational::from(v)),
+            Err(_) => Err(E::invalid_value(Unexpected::Unsigned(v), &self)),
+        }
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E>
+    where
+        E: serde::de::Error,
+    {
+        Rational::from_decimal(v).ok_or_else(|| E::invalid_value(Unexpected::Float(v), &self))
+    }
+
     fn visit_borrowed_str<E>(self, v: &'de str) -> Result<Self::Value, E>
     where
         E: serde::de::Error,
@@ -211,11 +281,15 @@ impl<'de> Visitor<'de> for RationalVisitor {
 }
 
 impl<'de> Deserialize<'de> for Rational {
+    /// Reads a rational number from a string like "1 1/2" or "1½",
+    /// or from a plain number like 2 or 1.5.
+    ///
+    /// Decimal numbers are taken as written, so 0.1 becomes 1/10.
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de>,
     {
-        deserializer.deserialize_str(RationalVisitor)
+        deserializer.deserialize_any(RationalVisitor)
     }
 }
 
@@ -325,6 +399,41 @@ mod test {
         }
     }
 
+    spec! {
+        rational_checked_mul {
+            case fraction {
+                let (a, b, want) = (rat!(1, 2), rat!(3, 4), Some(rat!(3, 8)));
+            }
+
+            case negative {
+                let (a, b, want) = (rat!(1, 3), rat!(-1, 5), Some(rat!(-1, 15)));
+            }
+
+            case zero {
+                let (a, b, want) = (rat!(0), rat!(i64::MAX), Some(rat!(0)));
+            }
+
+            case cancels {
+                let (a, b, want) = (rat!(i64::MAX, 2), rat!(2, 3), Some(rat!(i64::MAX, 3)));
+            }
+
+            case too_large {
+                let (a, b, want) = (rat!(i64::MAX), rat!(3, 2), None);
+            }
+
+            case too_small {
+                let (a, b, want) = (rat!(i64::MAX), rat!(-3, 2), None);
+            }
+
+            case denominator_too_large {
+                let (a, b, want) = (rat!(1, i64::MAX), rat!(1, 2), None);
+            }
+
+            assert_eq!(want, a.checked_mul(b));
+            assert_eq!(want, b.checked_mul(a));
+        }
+    }
+
     spec! {
         rational_div {
             case case1 {
@@ -416,6 +525,91 @@ mod test {
         }
     }
 
+    spec! {
+        deserialize_number_or_string {
+            case whole {
+                let inputs = [r#"2"#, r#"2.0"#, r#""2""#, r#""+2""#];
+                let want = rat!(2);
+            }
+
+            case mixed {
+                let inputs = [r#"1.5"#, r#""1 1/2""#, r#""1½""#, r#""3/2""#];
+                let want = rat!(3, 2);
+            }
+
+            case fraction {
+                let inputs = [r#"0.25"#, r#""1/4""#, r#""¼""#];
+                let want = rat!(1, 4);
+            }
+
+            case tenth {
+                let inputs = [r#"0.1"#, r#""1/10""#];
+                let want = rat!(1, 10);
+            }
+
+            case negative_whole {
+                let inputs = [r#"-3"#, r#""-3""#];
+                let want = rat!(-3);
+            }
+
+            case negative_fraction {
+                let inputs = [r#"-0.75"#, r#""-3/4""#];
+                let want = rat!(-3, 4);
+            }
+
+            case ten_fractional_digits {
+                let inputs = [r#"0.1234567891"#, r#""1234567891/10000000000""#];
+                let want = rat!(1234567891, 10000000000);
+            }
+
+            case shortest_third {
+                let inputs = [r#"0.3333333333333333"#];
+                let want = rat!(3333333333333333, 10000000000000000);
+            }
+
+            case negative_many_digits {
+                let inputs = [r#"-0.1234567891"#];
+                let want = rat!(-1234567891, 10000000000);
+            }
+
+            for input in inputs {
+                let got: Rational = serde_json::from_str(input).unwrap();
+                assert_eq!(want, got, "{}", input);
+            }
+        }
+    }
+
+    spec! {
+        deserialize_rejects {
+            case text {
+                let input = r#""viel""#;
+            }
+
+            case too_large {
+                let input = "18446744073709551615";
+            }
+
+            case too_many_digits {
+                let input = "1e300";
+            }
+
+            case smallest_integer {
+                let input = "-9223372036854775808";
+            }
+
+            case smallest_integer_as_float {
+                let input = "-9223372036854775808.0";
+            }
+
+            case boolean {
+                let input = "true";
+            }
+
+            let result = serde_json::from_str::<Rational>(input);
+            assert!(result.is_err(), "{:?}", result);
+        }
+    }
+
     #[test]
     fn from_int() {
         let a = Rational::from(42);
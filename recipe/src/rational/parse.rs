@@ -124,13 +124,13 @@ impl FromStr for Rational {
                 '0'..='9' => match state {
                     ParseState::Q0 => ParseState::Q2(MixedFraction {
                         sign: 1,
-                        number: to_digit_unwrap(c) as u64,
+                        number: to_digit_unwrap(c),
                         numerator: 0,
                         denominator: 1,
                     }),
                     ParseState::Q1(sign) => ParseState::Q2(MixedFraction {
                         sign: sign.sign,
-                        number: to_digit_unwrap(c) as u64,
+                        number: to_digit_unwrap(c),
                         numerator: 0,
                         denominator: 1,
                     }),
@@ -141,7 +141,7 @@ impl FromStr for Rational {
                         denominator: 1, // kann noch nicht gesetzt worden sein.
                     }),
                     ParseState::Q3(number) => ParseState::Q4(MixedFraction {
-                        denominator: to_digit_unwrap(c) as u64,
+                        denominator: to_digit_unwrap(c),
                         ..number
                     }),
                     ParseState::Q4(fraction) => ParseState::Q4(MixedFraction {
@@ -149,12 +149,12 @@ impl FromStr for Rational {
                         ..fraction
                     }),
                     ParseState::Q6(number) => ParseState::Q7(MixedFraction {
-                        numerator: to_digit_unwrap(c) as u64,
+                        numerator: to_digit_unwrap(c),
                         denominator: 0,
                         ..number
                     }),
                     ParseState::Q7(number) => ParseState::Q7(MixedFraction {
-                        numerator: number.numerator * 10 + to_digit_unwrap(c) as u64,
+                        numerator: number.numerator * 10 + to_digit_unwrap(c),
                         denominator: 0,
                         ..number
                     }),
@@ -334,7 +334,6 @@ lazy_static! {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::*;
     use spucky::spec;
 
     spec! {
@@ -1,17 +1,29 @@
+use crate::normalize_tag;
+use crate::Category;
+use crate::Difficulty;
 use crate::Recipe;
+use crate::Stored;
 use crate::Summary;
 use crate::TableOfContents;
-use axum::{http::StatusCode, response::IntoResponse};
+use async_trait::async_trait;
+use axum::{headers, http::StatusCode, response::IntoResponse};
 use std::{
-    cmp::min,
-    collections::HashMap,
+    cmp::{max, min, Ordering},
     error, fmt,
-    ops::{Bound, RangeBounds, Sub},
+    ops::{self, Bound, RangeBounds, Sub},
 };
+use tokio::sync::broadcast;
 
 use uuid::Uuid;
 
-#[derive(Debug, Copy, Clone)]
+mod id;
+mod instrumented;
+pub mod memory;
+
+pub use id::{IdGenerator, RandomIds, SequentialIds};
+pub use instrumented::InstrumentedRepository;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub enum Range {
     Empty,
     /// Das Intervall enthält sowohl a als auch b.
@@ -32,6 +44,34 @@ pub enum Range {
         end: usize,
     },
 
+    /// (start, end) = {x | start < x < end}
+    Open {
+        start: usize,
+        end: usize,
+    },
+
+    /// [start, end) = {x | start <= x < end}, like `start..end`
+    ClosedOpen {
+        start: usize,
+        end: usize,
+    },
+
+    /// (start, end] = {x | start < x <= end}
+    OpenClosed {
+        start: usize,
+        end: usize,
+    },
+
+    /// (start, +∞) = {x | x > start}
+    LeftOpen {
+        start: usize,
+    },
+
+    /// (-∞, end) = {x | x < end}, like `..end`
+    RightOpen {
+        end: usize,
+    },
+
     /// (-∞, +∞) = N
     Unbounded,
 }
@@ -39,7 +79,9 @@ pub enum Range {
 impl Range {
     /// Returns the range of a slice specified by self.
     ///
-    /// The range is adjusted to the length of the slice.
+    /// The range is adjusted to the length of the slice. Ranges
+    /// starting behind the last item or ending before their start
+    /// select an empty slice.
     ///
     /// # Example
     ///
@@ -48,43 +90,173 @@ impl Range {
     ///
     /// let numbers = [1, 2, 3, 4, 5];
     /// let range = Range::Closed{start: 2, end: 10};
-    /// assert_eq!([3, 4, 5], range.index(&numbers))
+    /// assert_eq!([3, 4, 5], range.index(&numbers));
+    ///
+    /// let range = Range::ClosedOpen{start: 1, end: 3};
+    /// assert_eq!([2, 3], range.index(&numbers));
+    ///
+    /// let range = Range::Open{start: 1, end: 3};
+    /// assert_eq!([3], range.index(&numbers));
     /// ```
+    #[allow(clippy::should_implement_trait)]
     pub fn index<T>(self, slice: &[T]) -> &[T] {
-        if slice.len() == 0 {
-            return slice;
+        match self.clip(slice.len()) {
+            Range::Closed { start, end } => &slice[start..=end],
+            _ => &slice[0..0],
         }
+    }
 
-        match self.clip(slice.len()) {
-            Range::Closed { start, end } => &slice[(start..=(min(end, slice.len() - 1)))],
-            Range::LeftClosed { start } => &slice[start..],
-            Range::RightClosed { end } => &slice[..=(min(end, slice.len() - 1))],
-            Range::Unbounded => slice,
-            Range::Empty => &slice[0..0],
+    /// Returns the number of items the range selects from a
+    /// collection of `total` items.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::repository::Range;
+    ///
+    /// let range = Range::Closed{start: 2, end: 10};
+    /// assert_eq!(3, range.len(5));
+    /// assert!(range.is_empty(2));
+    /// ```
+    pub fn len(&self, total: usize) -> usize {
+        match self.clip(total) {
+            Range::Closed { start, end } => end - start + 1,
+            _ => 0,
+        }
+    }
+
+    /// Returns true if the range selects nothing from a collection of
+    /// `total` items.
+    pub fn is_empty(&self, total: usize) -> bool {
+        self.len(total) == 0
+    }
+
+    /// Returns true if the range contains the index.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::repository::Range;
+    ///
+    /// let range = Range::ClosedOpen{start: 2, end: 4};
+    /// assert!(range.contains(3));
+    /// assert!(!range.contains(4));
+    /// ```
+    pub fn contains(&self, index: usize) -> bool {
+        self.limits().is_some_and(|(start, end)| {
+            start.unwrap_or(0) <= index && index <= end.unwrap_or(usize::MAX)
+        })
+    }
+
+    /// Returns the range of the indices contained in both ranges.
+    ///
+    /// [Range::Empty] and ranges ending before their start contain no
+    /// index, so their intersection with any range is empty. The
+    /// result has no excluded bounds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::repository::Range;
+    ///
+    /// let range = Range::LeftClosed{start: 5};
+    /// assert_eq!(
+    ///     Range::Closed{start: 5, end: 9},
+    ///     range.intersect(&Range::ClosedOpen{start: 0, end: 10})
+    /// );
+    /// assert_eq!(Range::Empty, range.intersect(&Range::RightClosed{end: 4}));
+    /// ```
+    pub fn intersect(&self, other: &Range) -> Range {
+        let ((left_start, left_end), (right_start, right_end)) =
+            match (self.limits(), other.limits()) {
+                (Some(left), Some(right)) => (left, right),
+                _ => return Range::Empty,
+            };
+
+        // An unbounded start is None and compares below every index.
+        let start = max(left_start, right_start);
+        let end = match (left_end, right_end) {
+            (Some(left), Some(right)) => Some(min(left, right)),
+            (left, None) => left,
+            (None, right) => right,
+        };
+
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end => Range::Closed { start, end },
+            (Some(_), Some(_)) => Range::Empty,
+            (Some(start), None) => Range::LeftClosed { start },
+            (None, Some(end)) => Range::RightClosed { end },
+            (None, None) => Range::Unbounded,
         }
     }
 
+    /// Restricts the range to the indices of a slice with `max_len`
+    /// items.
+    ///
+    /// The result is [Range::Closed] with valid indices of the slice
+    /// or [Range::Empty].
     fn clip(&self, max_len: usize) -> Range {
-        if max_len == 0 {
-            return Range::Empty;
+        match max_len.checked_sub(1) {
+            Some(last) => self.intersect(&Range::Closed {
+                start: 0,
+                end: last,
+            }),
+            None => Range::Empty,
         }
+    }
 
-        match self {
-            Range::Closed { start, end } => Range::Closed {
-                start: *start,
-                end: min(*end, max_len - 1),
+    /// Returns the first and the last index of the range, `None` for
+    /// an unbounded end.
+    ///
+    /// Returns `None` if the range contains no index.
+    fn limits(&self) -> Option<(Option<usize>, Option<usize>)> {
+        match self.closed() {
+            Range::Closed { start, end } if start <= end => Some((Some(start), Some(end))),
+            Range::LeftClosed { start } => Some((Some(start), None)),
+            Range::RightClosed { end } => Some((None, Some(end))),
+            Range::Unbounded => Some((None, None)),
+            _ => None,
+        }
+    }
+
+    /// Replaces excluded bounds by the included neighbours.
+    ///
+    /// The result is one of the variants without excluded bounds.
+    /// Intervals which cannot contain any index become [Range::Empty].
+    fn closed(&self) -> Range {
+        match *self {
+            Range::Open { start, end } => match end.checked_sub(1) {
+                Some(end) if start < end => Range::Closed {
+                    start: start + 1,
+                    end,
+                },
+                _ => Range::Empty,
             },
-            Range::RightClosed { end } => Range::RightClosed {
-                end: min(*end, max_len - 1),
+            Range::ClosedOpen { start, end } => match end.checked_sub(1) {
+                Some(end) if start <= end => Range::Closed { start, end },
+                _ => Range::Empty,
             },
-            _ => *self,
+            Range::OpenClosed { start, end } if start < end => Range::Closed {
+                start: start + 1,
+                end,
+            },
+            Range::OpenClosed { .. } => Range::Empty,
+            Range::LeftOpen { start } => match start.checked_add(1) {
+                Some(start) => Range::LeftClosed { start },
+                None => Range::Empty,
+            },
+            Range::RightOpen { end } => match end.checked_sub(1) {
+                Some(end) => Range::RightClosed { end },
+                None => Range::Empty,
+            },
+            range => range,
         }
     }
 }
 
 impl<T> From<&Vec<T>> for Range {
     fn from(value: &Vec<T>) -> Self {
-        if value.len() > 0 {
+        if !value.is_empty() {
             Range::Closed {
                 start: 0,
                 end: value.len() - 1,
@@ -95,14 +267,238 @@ impl<T> From<&Vec<T>> for Range {
     }
 }
 
+impl From<&(Bound<u64>, Bound<u64>)> for Range {
+    /// Converts a pair of bounds into a range.
+    ///
+    /// Every combination of bounds has its own variant, so excluded
+    /// bounds are kept. Positions beyond `usize::MAX` are reduced to
+    /// `usize::MAX`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::repository::Range;
+    /// use std::ops::Bound;
+    ///
+    /// let range = Range::from(&(Bound::Excluded(1), Bound::Unbounded));
+    /// assert_eq!(Range::LeftOpen { start: 1 }, range);
+    /// assert_eq!([3, 4], range.index(&[1, 2, 3, 4]));
+    /// ```
+    fn from(bounds: &(Bound<u64>, Bound<u64>)) -> Self {
+        let position = |value: u64| usize::try_from(value).unwrap_or(usize::MAX);
+        match (
+            BoundExt::map(bounds.0, position),
+            BoundExt::map(bounds.1, position),
+        ) {
+            (Bound::Included(start), Bound::Included(end)) => Range::Closed { start, end },
+            (Bound::Included(start), Bound::Excluded(end)) => Range::ClosedOpen { start, end },
+            (Bound::Included(start), Bound::Unbounded) => Range::LeftClosed { start },
+            (Bound::Excluded(start), Bound::Included(end)) => Range::OpenClosed { start, end },
+            (Bound::Excluded(start), Bound::Excluded(end)) => Range::Open { start, end },
+            (Bound::Excluded(start), Bound::Unbounded) => Range::LeftOpen { start },
+            (Bound::Unbounded, Bound::Included(end)) => Range::RightClosed { end },
+            (Bound::Unbounded, Bound::Excluded(end)) => Range::RightOpen { end },
+            (Bound::Unbounded, Bound::Unbounded) => Range::Unbounded,
+        }
+    }
+}
+
+impl TryFrom<&headers::Range> for Range {
+    type Error = RangeError;
+
+    /// Converts the HTTP Range header into a range of the table of
+    /// contents.
+    ///
+    /// Only a single range with a first position is supported, like
+    /// "bytes=0-9" or "bytes=10-". The header type itself skips specs
+    /// it cannot parse.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::headers;
+    /// use recipers::repository::Range;
+    ///
+    /// let header = headers::Range::bytes(10..20).unwrap();
+    /// assert_eq!(Ok(Range::Closed { start: 10, end: 19 }), Range::try_from(&header));
+    /// ```
+    fn try_from(header: &headers::Range) -> Result<Self, Self::Error> {
+        let mut ranges = header.iter();
+        let range = match (ranges.next(), ranges.next()) {
+            (None, _) => return Err(RangeError::Malformed),
+            (Some(_), Some(_)) => return Err(RangeError::MultipleRanges),
+            (Some(range), None) => range,
+        };
+
+        let position = |value: u64| usize::try_from(value).map_err(|_| RangeError::Unsatisfiable);
+        match range {
+            (Bound::Included(start), Bound::Included(end)) if start > end => {
+                Err(RangeError::Unsatisfiable)
+            }
+            (Bound::Included(start), Bound::Included(end)) => Ok(Range::Closed {
+                start: position(start)?,
+                end: position(end).unwrap_or(usize::MAX),
+            }),
+            (Bound::Included(start), Bound::Unbounded) => Ok(Range::LeftClosed {
+                start: position(start)?,
+            }),
+            (Bound::Unbounded, Bound::Included(_)) => Err(RangeError::Suffix),
+            _ => Err(RangeError::Malformed),
+        }
+    }
+}
+
+/// A page of the table of contents.
+///
+/// The page skips the first `offset` summaries and contains at most
+/// `limit` of the following ones, all of them if `limit` is `None`.
+/// Unlike [Range] a page cannot end before it starts, so every
+/// backend selects the summaries with the same [Page::index].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub offset: u64,
+    pub limit: Option<u64>,
+}
+
+impl Page {
+    /// Returns the page containing all summaries.
+    pub fn all() -> Page {
+        Page::default()
+    }
+
+    /// Returns the page of the first `n` summaries.
+    pub fn first(n: u64) -> Page {
+        Page {
+            offset: 0,
+            limit: Some(n),
+        }
+    }
+
+    /// Converts a pair of bounds into a page.
+    ///
+    /// Pairs ending before their start select an empty page.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::repository::Page;
+    /// use std::ops::Bound;
+    ///
+    /// let page = Page::from_bounds(&(Bound::Excluded(9), Bound::Included(19)));
+    /// assert_eq!(Page { offset: 10, limit: Some(10) }, page);
+    /// ```
+    pub fn from_bounds(bounds: &(Bound<u64>, Bound<u64>)) -> Page {
+        Page::from(Range::from(bounds))
+    }
+
+    /// Converts the HTTP Range header into a page.
+    ///
+    /// The header is restricted like [Range::try_from] describes.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::headers;
+    /// use recipers::repository::Page;
+    ///
+    /// let header = headers::Range::bytes(10..).unwrap();
+    /// assert_eq!(Ok(Page { offset: 10, limit: None }), Page::from_range_header(&header));
+    /// ```
+    pub fn from_range_header(header: &headers::Range) -> Result<Page, RangeError> {
+        Range::try_from(header).map(Page::from)
+    }
+
+    /// Returns true if the page contains no summary at all.
+    pub fn is_empty(&self) -> bool {
+        self.limit == Some(0)
+    }
+
+    /// Returns the items of the slice on this page.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::repository::Page;
+    ///
+    /// let numbers = [1, 2, 3, 4, 5];
+    /// let page = Page { offset: 3, limit: Some(10) };
+    /// assert_eq!([4, 5], page.index(&numbers));
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn index<'a, T>(&self, slice: &'a [T]) -> &'a [T] {
+        let fit =
+            |value: u64, len: usize| usize::try_from(value).map_or(len, |value| min(value, len));
+
+        let rest = &slice[fit(self.offset, slice.len())..];
+        match self.limit {
+            Some(limit) => &rest[..fit(limit, rest.len())],
+            None => rest,
+        }
+    }
+}
+
+impl From<Range> for Page {
+    /// Converts a range into the page of the same indices.
+    ///
+    /// Ranges containing no index become an empty page.
+    fn from(range: Range) -> Self {
+        match range.limits() {
+            Some((start, end)) => {
+                let offset = start.unwrap_or(0);
+                Page {
+                    offset: offset as u64,
+                    // An end at usize::MAX leaves the page unlimited.
+                    limit: end.and_then(|end| (end - offset).checked_add(1).map(|n| n as u64)),
+                }
+            }
+            None => Page::first(0),
+        }
+    }
+}
+
+/// The reason why an HTTP Range header does not select a [Range].
+#[derive(Debug, PartialEq)]
+pub enum RangeError {
+    /// The header contains no range, like "bytes=abc".
+    Malformed,
+    /// The header contains more than one range, like "bytes=0-4,10-14".
+    MultipleRanges,
+    /// The header selects the last positions, like "bytes=-5".
+    Suffix,
+    /// The range ends before it starts, like "bytes=9-5".
+    Unsatisfiable,
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeError::Malformed => write!(f, "malformed range"),
+            RangeError::MultipleRanges => write!(f, "multiple ranges are not supported"),
+            RangeError::Suffix => write!(f, "suffix ranges are not supported"),
+            RangeError::Unsatisfiable => write!(f, "range ends before it starts"),
+        }
+    }
+}
+
+impl error::Error for RangeError {}
+
+/// The position both bounds of [Range::Empty] refer to. The start
+/// includes it and the end excludes it, so the range contains nothing.
+static EMPTY_POSITION: usize = 0;
+
 impl RangeBounds<usize> for Range {
     fn start_bound(&self) -> Bound<&usize> {
         match self {
             Range::Unbounded => Bound::Unbounded,
             Range::RightClosed { .. } => Bound::Unbounded,
+            Range::RightOpen { .. } => Bound::Unbounded,
             Range::Closed { start, .. } => Bound::Included(start),
+            Range::ClosedOpen { start, .. } => Bound::Included(start),
             Range::LeftClosed { start } => Bound::Included(start),
-            Range::Empty => Bound::Included(&usize::MIN),
+            Range::Open { start, .. } => Bound::Excluded(start),
+            Range::OpenClosed { start, .. } => Bound::Excluded(start),
+            Range::LeftOpen { start } => Bound::Excluded(start),
+            Range::Empty => Bound::Included(&EMPTY_POSITION),
         }
     }
 
@@ -111,15 +507,19 @@ impl RangeBounds<usize> for Range {
             Range::Unbounded => Bound::Unbounded,
             Range::RightClosed { end } => Bound::Included(end),
             Range::Closed { start: _start, end } => Bound::Included(end),
+            Range::OpenClosed { start: _start, end } => Bound::Included(end),
+            Range::Open { start: _start, end } => Bound::Excluded(end),
+            Range::ClosedOpen { start: _start, end } => Bound::Excluded(end),
+            Range::RightOpen { end } => Bound::Excluded(end),
             Range::LeftClosed { start: _start } => Bound::Unbounded,
-            Range::Empty => Bound::Excluded(&usize::MIN),
+            Range::LeftOpen { start: _start } => Bound::Unbounded,
+            Range::Empty => Bound::Excluded(&EMPTY_POSITION),
         }
     }
 }
 
 trait BoundExt<T> {
     fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Bound<U>;
-    fn map_raw<U, F: FnOnce(Bound<T>) -> Bound<U>>(self, f: F) -> Bound<U>;
 }
 
 impl<T> BoundExt<T> for Bound<T>
@@ -133,294 +533,1281 @@ where
             Bound::Excluded(x) => Bound::Excluded(f(x)),
         }
     }
+}
 
-    fn map_raw<U, F: FnOnce(Bound<T>) -> Bound<U>>(self, f: F) -> Bound<U> {
-        f(self)
-    }
+/// How the text of a [SearchQuery] is compared with a title.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    /// The title starts with the text.
+    #[default]
+    Prefix,
+    /// The title contains the text anywhere.
+    Contains,
+    /// Every word of the text starts a word of the title, in any
+    /// order.
+    AllTokens,
+}
+
+/// The parts of a recipe a [SearchQuery] looks at.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SearchFields {
+    #[default]
+    Title,
+    /// The names of the ingredients.
+    Ingredients,
+    Preparation,
+    /// The title, the names of the ingredients and the preparation.
+    All,
 }
 
-/// An in-memory repository for recipes
-pub struct Repository {
-    entries: HashMap<Uuid, Recipe>,
+/// A search for recipes by their title or other texts.
+///
+/// Unless the query is case sensitive, upper and lower case are not
+/// distinguished, also for letters like "Ä". An empty text selects
+/// every recipe in all modes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    text: String,
+    mode: SearchMode,
+    fields: SearchFields,
+    case_sensitive: bool,
 }
 
-impl Repository {
-    /// Creates a new repository
-    pub fn new() -> Repository {
-        Repository {
-            entries: HashMap::new(),
+impl SearchQuery {
+    /// Creates a case insensitive query searching the titles of the
+    /// recipes.
+    pub fn new(text: &str, mode: SearchMode) -> SearchQuery {
+        SearchQuery {
+            text: text.to_lowercase(),
+            mode,
+            fields: SearchFields::Title,
+            case_sensitive: false,
         }
     }
 
-    /// Adds a recipe to the repository
-    pub fn insert(&mut self, r: &Recipe) -> Result<Uuid, RepositoryError> {
-        let id = Uuid::new_v4();
-        self.entries.insert(id, r.clone());
-        Ok(id)
+    /// Creates a query which distinguishes upper and lower case.
+    pub fn case_sensitive(text: &str, mode: SearchMode) -> SearchQuery {
+        SearchQuery {
+            text: text.to_string(),
+            case_sensitive: true,
+            ..SearchQuery::new(text, mode)
+        }
     }
 
-    pub fn list_ids(&self, range: &Range) -> Vec<Uuid> {
-        let keys: &Vec<Uuid> = &self.entries.keys().cloned().collect();
+    /// Creates a case insensitive query finding the text anywhere in
+    /// the title, the names of the ingredients or the preparation.
+    pub fn full_text(text: &str) -> SearchQuery {
+        SearchQuery::new(text, SearchMode::Contains).fields(SearchFields::All)
+    }
 
-        range.index(keys).into()
+    /// Searches the given parts of the recipes instead of the title.
+    ///
+    /// A recipe is selected if any of the texts matches, for example
+    /// the name of a single ingredient.
+    pub fn fields(mut self, fields: SearchFields) -> SearchQuery {
+        self.fields = fields;
+        self
     }
 
-    /// Creates a table of contents for the specified filter
-    /// criteria.
+    fn matches(&self, recipe: &Recipe) -> bool {
+        let title = || std::iter::once(recipe.title.as_str());
+        let ingredients = || recipe.ingredients.iter().map(|i| i.name.as_str());
+        let preparation = || std::iter::once(recipe.preparation.as_str());
+
+        match self.fields {
+            SearchFields::Title => title().any(|text| self.matches_text(text)),
+            SearchFields::Ingredients => ingredients().any(|text| self.matches_text(text)),
+            SearchFields::Preparation => preparation().any(|text| self.matches_text(text)),
+            SearchFields::All => title()
+                .chain(ingredients())
+                .chain(preparation())
+                .any(|text| self.matches_text(text)),
+        }
+    }
+
+    fn matches_text(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+
+    /// Returns the byte range of the text which matches the query, or
+    /// `None` if the text does not match.
     ///
-    /// The recipes are sorted by name. All recipes that start with
-    /// "search" are included in the table of contents. The table of
-    /// contents contains all the recipes within the given range.
-    pub fn list(&self, range: &Range, search: &str) -> Result<TableOfContents, RepositoryError> {
-        let mut summaries: Vec<Summary> = self
-            .entries
-            .iter()
-            .map(|entity| entity.into())
-            .filter(|s: &Summary| s.title.starts_with(search))
-            .collect();
-
-        summaries.sort();
-        let content: Vec<Summary> = range.index(&summaries).into();
-
-        Ok(TableOfContents {
-            total: self.entries.len(),
-            content,
-        })
+    /// With [SearchMode::AllTokens] the range reaches from the first
+    /// to the last matched word. An empty query matches the empty
+    /// range at the start of the text.
+    pub(crate) fn find(&self, text: &str) -> Option<ops::Range<usize>> {
+        let folded = Folded::new(text, self.case_sensitive);
+        let text = folded.text.as_str();
+        let found = match self.mode {
+            SearchMode::Prefix => text.starts_with(&self.text).then_some(0..self.text.len()),
+            SearchMode::Contains => text
+                .find(&self.text)
+                .map(|start| start..start + self.text.len()),
+            SearchMode::AllTokens => {
+                let mut found = 0..0;
+                for (n, token) in self.text.split_whitespace().enumerate() {
+                    let start = text
+                        .split_whitespace()
+                        .find(|word| word.starts_with(token))
+                        .map(|word| word.as_ptr() as usize - text.as_ptr() as usize)?;
+                    let end = start + token.len();
+                    found = match n {
+                        0 => start..end,
+                        _ => min(found.start, start)..max(found.end, end),
+                    };
+                }
+                Some(found)
+            }
+        };
+        found.map(|range| folded.original(range))
     }
+}
 
-    pub fn list2(
-        &self,
-        range: &(Bound<u64>, Bound<u64>),
-        search: &str,
-    ) -> Result<TableOfContents, RepositoryError> {
-        let mut summaries: Vec<Summary> = self
-            .entries
-            .iter()
-            .map(|entity| entity.into())
-            .filter(|s: &Summary| s.title.starts_with(search))
-            .collect();
+/// A text as it is compared with a query, remembering where its
+/// bytes came from.
+struct Folded {
+    text: String,
+    /// The range of the original character of every byte.
+    origins: Vec<ops::Range<usize>>,
+    len: usize,
+}
 
-        summaries.sort();
+impl Folded {
+    /// Converts the text to lower case unless the comparison is case
+    /// sensitive.
+    fn new(text: &str, case_sensitive: bool) -> Folded {
+        let mut folded = Folded {
+            text: String::with_capacity(text.len()),
+            origins: Vec::with_capacity(text.len()),
+            len: text.len(),
+        };
+        for (start, c) in text.char_indices() {
+            if case_sensitive {
+                folded.text.push(c);
+            } else {
+                folded.text.extend(c.to_lowercase());
+            }
+            folded
+                .origins
+                .resize(folded.text.len(), start..start + c.len_utf8());
+        }
+        folded
+    }
 
-        tracing::debug!("Got range {:?}", range);
+    /// Maps a range of the folded text to the original text.
+    fn original(&self, range: ops::Range<usize>) -> ops::Range<usize> {
+        if range.is_empty() {
+            let at = self.origins.get(range.start).map_or(self.len, |c| c.start);
+            return at..at;
+        }
+        self.origins[range.start].start..self.origins[range.end - 1].end
+    }
+}
 
-        let xrange = if summaries.len() == 0 {
-            (Bound::Unbounded, Bound::Unbounded)
-        } else {
-            (
-                BoundExt::map(range.0, |f| f as usize),
-                BoundExt::map(range.1, |f| f as usize).map_raw(|f| match f {
-                    Bound::Unbounded => Bound::Unbounded,
-                    Bound::Included(x) => Bound::Included(min(x, summaries.len() - 1)),
-                    Bound::Excluded(x) => Bound::Excluded(min(x, summaries.len())),
-                }),
-            )
+/// Converts the text to lower case character by character, like a
+/// case insensitive [SearchQuery] compares it.
+pub(crate) fn fold_case(text: &str) -> String {
+    text.chars().flat_map(char::to_lowercase).collect()
+}
+
+impl From<&str> for SearchQuery {
+    /// Searches for titles starting with the text.
+    fn from(text: &str) -> Self {
+        SearchQuery::new(text, SearchMode::Prefix)
+    }
+}
+
+/// Order of the summaries in the table of contents.
+///
+/// Titles are compared as written. Summaries which are equal in the
+/// sort key are ordered by id, so the order is the same for every
+/// request and pages of a range neither overlap nor leave gaps.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    #[default]
+    TitleAsc,
+    TitleDesc,
+    /// Most recently changed recipes first.
+    UpdatedDesc,
+}
+
+impl SortBy {
+    fn compare(&self, a: &Summary, b: &Summary) -> Ordering {
+        let key = match self {
+            SortBy::TitleAsc => a.title.cmp(&b.title),
+            SortBy::TitleDesc => b.title.cmp(&a.title),
+            SortBy::UpdatedDesc => b.updated_at.cmp(&a.updated_at),
         };
+        key.then_with(|| a.id.cmp(&b.id))
+    }
+}
 
-        tracing::debug!("Transposed to {:?}", xrange);
+/// Selection criteria and order for the table of contents.
+///
+/// A recipe is selected if it matches the search query, carries all
+/// of the tags of the filter and, where the filter sets them, has its
+/// difficulty and category. The default filter selects every recipe
+/// and sorts by title.
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    search: SearchQuery,
+    tags: Vec<String>,
+    difficulty: Option<Difficulty>,
+    category: Option<Category>,
+    sort: SortBy,
+}
 
-        //let content: Vec<Summary> =  range.index(&summaries).into();
-        // let content = summaries.index(xrange).into();
-        let content = summaries[xrange].into();
+impl Filter {
+    /// Creates a filter which selects all recipes.
+    pub fn new() -> Filter {
+        Filter::default()
+    }
 
-        Ok(TableOfContents {
-            total: self.entries.len(),
-            content,
-        })
+    /// Restricts the filter to recipes whose title matches `search`.
+    ///
+    /// A plain string selects the titles starting with it, see
+    /// [SearchQuery].
+    pub fn search(mut self, search: impl Into<SearchQuery>) -> Filter {
+        self.search = search.into();
+        self
     }
 
-    pub fn get(&self, id: &Uuid) -> Result<Option<&Recipe>, RepositoryError> {
-        Ok(self.entries.get(&id))
+    /// Restricts the filter to recipes tagged with `tag`.
+    ///
+    /// The tag is normalized like the tags of a recipe.
+    pub fn tag(mut self, tag: &str) -> Filter {
+        self.tags.push(normalize_tag(tag));
+        self
+    }
+
+    /// Restricts the filter to recipes of the given difficulty.
+    pub fn difficulty(mut self, difficulty: Difficulty) -> Filter {
+        self.difficulty = Some(difficulty);
+        self
+    }
+
+    /// Restricts the filter to recipes of the given category.
+    pub fn category(mut self, category: Category) -> Filter {
+        self.category = Some(category);
+        self
+    }
+
+    /// Sorts the selected recipes in the given order.
+    pub fn sort_by(mut self, sort: SortBy) -> Filter {
+        self.sort = sort;
+        self
     }
 
-    pub fn remove(&mut self, id: &Uuid) -> Result<(), RepositoryError> {
-        self.entries.remove(&id);
-        Ok(())
+    fn matches(&self, recipe: &Recipe) -> bool {
+        self.search.matches(recipe)
+            && self.tags.iter().all(|t| recipe.tags.contains(t))
+            && (self.difficulty.is_none() || recipe.difficulty == self.difficulty)
+            && (self.category.is_none() || recipe.category == self.category)
     }
+}
 
-    pub fn update(&mut self, id: &Uuid, recipe: Recipe) -> Result<UpdateResult, RepositoryError> {
-        match self.entries.insert(*id, recipe) {
-            Some(_) => Ok(UpdateResult::Changed),
-            None => Ok(UpdateResult::Created),
+/// Storage for recipes.
+///
+/// The HTTP and the gRPC interface access recipes only through this
+/// trait. [memory::Ephemeral] keeps the recipes in memory.
+/// [InstrumentedRepository] traces the calls to another repository.
+///
+/// The methods are async, so a backend may wait for I/O without
+/// blocking the threads of the runtime. Only [Repository::watch] is
+/// synchronous, subscribing never waits.
+#[async_trait]
+pub trait Repository: Send + Sync {
+    /// Adds a recipe to the repository
+    ///
+    /// The creation and modification time of the recipe are set to
+    /// the current time.
+    async fn insert(&mut self, recipe: &Recipe) -> Result<Uuid, RepositoryError>;
+
+    /// Adds several recipes to the repository.
+    ///
+    /// Every recipe gets a new id like with [insert]. With
+    /// `skip_duplicates` a recipe is skipped like with [insert_unique]
+    /// if the same recipe is stored, which includes the recipes added
+    /// before it. Returns the outcomes in the order of the recipes.
+    ///
+    /// The default implementation adds one recipe after the other and
+    /// stops at the first error. The recipes added before it are kept,
+    /// so with [RepositoryError::CapacityExceeded] the repository is
+    /// full and holds as many of the recipes as fitted. Implementations
+    /// may add all recipes or none instead and say so.
+    ///
+    /// [insert]: Repository::insert
+    /// [insert_unique]: Repository::insert_unique
+    async fn insert_all(
+        &mut self,
+        recipes: &[Recipe],
+        skip_duplicates: bool,
+    ) -> Result<Vec<InsertOutcome>, RepositoryError> {
+        let mut outcomes = Vec::with_capacity(recipes.len());
+        for recipe in recipes {
+            let outcome = if skip_duplicates {
+                self.insert_unique(recipe).await?
+            } else {
+                InsertOutcome::Inserted(self.insert(recipe).await?)
+            };
+            outcomes.push(outcome);
         }
+        Ok(outcomes)
     }
+
+    /// Adds a recipe with a known id to the repository
+    ///
+    /// Fails with [RepositoryError::DuplicateId] if the repository
+    /// already contains a recipe with this id.
+    async fn insert_with_id(&mut self, id: &Uuid, recipe: &Recipe) -> Result<(), RepositoryError>;
+
+    /// Adds a recipe unless the same recipe is stored already.
+    ///
+    /// Recipes are compared with [Recipe::semantically_equals], so a
+    /// recipe with the title of a stored one but another content is
+    /// added. A duplicate is not added, the outcome names the existing
+    /// recipe instead.
+    async fn insert_unique(&mut self, recipe: &Recipe) -> Result<InsertOutcome, RepositoryError>;
+
+    /// Creates a table of contents for the specified filter
+    /// criteria.
+    ///
+    /// The recipes are ordered by the [SortBy] of the filter, by
+    /// title unless the filter says otherwise. All recipes selected
+    /// by the filter are included in the table of contents. The table
+    /// of contents contains all the recipes on the given page.
+    ///
+    /// `total` counts all recipes of the repository, `filtered` only
+    /// those selected by the filter. The page refers to the latter.
+    /// The search of the filter ignores case unless it is case
+    /// sensitive, see [SearchQuery].
+    async fn list(&self, page: &Page, filter: &Filter) -> Result<TableOfContents, RepositoryError>;
+
+    /// Like [Repository::list] with the page given as pair of bounds.
+    #[deprecated(note = "use `list` with `Page::from_bounds`")]
+    async fn list2(
+        &self,
+        range: &(Bound<u64>, Bound<u64>),
+        filter: &Filter,
+    ) -> Result<TableOfContents, RepositoryError> {
+        self.list(&Page::from_bounds(range), filter).await
+    }
+
+    /// Returns a copy of the recipe with the given id.
+    async fn get(&self, id: &Uuid) -> Result<Option<Stored<Recipe>>, RepositoryError>;
+
+    /// Returns true if the repository contains a recipe with the id.
+    async fn exists(&self, id: &Uuid) -> Result<bool, RepositoryError>;
+
+    /// Returns the number of recipes in the repository.
+    async fn count(&self) -> Result<u64, RepositoryError>;
+
+    /// Returns all recipes with exactly the given title, ignoring
+    /// case.
+    ///
+    /// Titles are not unique, so there may be more than one match.
+    /// The matches are ordered by id.
+    async fn find_by_title(&self, title: &str) -> Result<Vec<(Uuid, Recipe)>, RepositoryError>;
+
+    /// Calls `f` once for every recipe of the repository.
+    ///
+    /// The order of the recipes is unspecified.
+    async fn for_each(
+        &self,
+        f: &mut (dyn for<'r> FnMut(&'r Uuid, &'r Recipe) + Send),
+    ) -> Result<(), RepositoryError>;
+
+    /// Returns the ids of all recipes ordered like [export_all].
+    ///
+    /// Fetching the recipes one by one with [get] visits the
+    /// repository without copying every recipe at once. Recipes
+    /// removed in the meantime are missing then.
+    ///
+    /// [export_all]: Repository::export_all
+    /// [get]: Repository::get
+    async fn ids(&self) -> Result<Vec<Uuid>, RepositoryError> {
+        let mut ids = Vec::new();
+        self.for_each(&mut |id, _| ids.push(*id)).await?;
+
+        ids.sort();
+        Ok(ids)
+    }
+
+    /// Returns every recipe of the repository together with its id.
+    ///
+    /// The recipes are ordered by id. Together with [import_all] this
+    /// copies a repository, for example into another backend.
+    ///
+    /// [import_all]: Repository::import_all
+    async fn export_all(&self) -> Result<Vec<(Uuid, Recipe)>, RepositoryError> {
+        let mut entries = Vec::new();
+        self.for_each(&mut |id, recipe| entries.push((*id, recipe.clone())))
+            .await?;
+
+        entries.sort_by_key(|(id, _)| *id);
+        Ok(entries)
+    }
+
+    /// Stores the recipes under their given ids.
+    ///
+    /// Unlike [insert] the ids are kept. A recipe whose id already
+    /// exists overwrites the existing one like [update] does. Returns
+    /// the number of recipes imported, overwritten ones included.
+    ///
+    /// [insert]: Repository::insert
+    /// [update]: Repository::update
+    async fn import_all(&mut self, entries: &[(Uuid, Recipe)]) -> Result<usize, RepositoryError>;
+
+    /// Removes the recipe with the given id if it exists.
+    ///
+    /// Reports [RemoveResult::NotFound] if there was no such recipe.
+    async fn remove(&mut self, id: &Uuid) -> Result<RemoveResult, RepositoryError>;
+
+    /// Removes all recipes with the given ids.
+    ///
+    /// Unknown ids are ignored. Returns the number of recipes
+    /// removed.
+    async fn remove_all(&mut self, ids: &[Uuid]) -> Result<u64, RepositoryError>;
+
+    /// Removes every recipe from the repository.
+    ///
+    /// Returns the number of recipes removed.
+    async fn clear(&mut self) -> Result<u64, RepositoryError>;
+
+    /// Replaces the recipe with the given id or creates it.
+    ///
+    /// A replaced recipe keeps its creation time, only the time of
+    /// the last change is refreshed.
+    async fn update(&mut self, id: &Uuid, recipe: &Recipe)
+        -> Result<UpdateResult, RepositoryError>;
+
+    /// Replaces the recipe only if it still has the expected revision.
+    ///
+    /// Fails with [RepositoryError::Conflict] if the recipe was
+    /// changed since the revision was read. The revision 0 stands for
+    /// a recipe which does not exist, so it creates the recipe.
+    async fn update_if_match(
+        &mut self,
+        id: &Uuid,
+        recipe: &Recipe,
+        expected_revision: u64,
+    ) -> Result<UpdateResult, RepositoryError>;
+
+    /// Subscribes to the changes of the repository.
+    ///
+    /// The receiver gets a [RepositoryEvent] for every recipe
+    /// inserted, updated or removed after the subscription. Failed
+    /// operations send no event. A receiver which falls too far
+    /// behind misses the oldest events and is told so by
+    /// [broadcast::error::RecvError::Lagged].
+    fn watch(&self) -> broadcast::Receiver<RepositoryEvent>;
+}
+
+/// A change of the repository, sent to the receivers of
+/// [Repository::watch] after the change succeeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepositoryEvent {
+    Inserted(Uuid),
+    Updated(Uuid),
+    Removed(Uuid),
 }
 
 #[derive(Debug)]
-pub enum RepositoryError {}
+pub enum RepositoryError {
+    /// A recipe with this id already exists.
+    DuplicateId(Uuid),
+    /// The recipe does not have the expected revision. The current
+    /// revision is 0 if the recipe does not exist.
+    Conflict { current_revision: u64 },
+    /// The repository holds as many recipes as it may, so no recipe
+    /// can be added. Operations adding several recipes may have added
+    /// some of them before, see [Repository::insert_all].
+    CapacityExceeded { capacity: usize },
+    /// The storage failed. The cause is kept for diagnosis.
+    Backend(Box<dyn error::Error + Send + Sync>),
+}
 
 impl IntoResponse for RepositoryError {
+    /// Converts the error into a response.
+    ///
+    /// Backend failures are logged with their cause but the client
+    /// only gets a generic message.
     fn into_response(self) -> axum::response::Response {
-        let body = "internal server error: code rot 7";
-        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+        match self {
+            RepositoryError::DuplicateId(_) => {
+                (StatusCode::CONFLICT, self.to_string()).into_response()
+            }
+            RepositoryError::Conflict { .. } => {
+                (StatusCode::PRECONDITION_FAILED, self.to_string()).into_response()
+            }
+            RepositoryError::CapacityExceeded { .. } => {
+                (StatusCode::INSUFFICIENT_STORAGE, self.to_string()).into_response()
+            }
+            RepositoryError::Backend(ref cause) => {
+                tracing::error!("repository failed: {}", cause);
+                (StatusCode::INTERNAL_SERVER_ERROR, "internal server error").into_response()
+            }
+        }
     }
 }
 
 impl fmt::Display for RepositoryError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Repository error")
+        match self {
+            RepositoryError::DuplicateId(id) => write!(f, "recipe {} already exists", id),
+            RepositoryError::Conflict { current_revision } => {
+                write!(
+                    f,
+                    "recipe has changed, current revision {}",
+                    current_revision
+                )
+            }
+            RepositoryError::CapacityExceeded { capacity } => {
+                write!(
+                    f,
+                    "repository is full, it holds at most {} recipes",
+                    capacity
+                )
+            }
+            RepositoryError::Backend(_) => write!(f, "repository backend failed"),
+        }
     }
 }
 
-impl error::Error for RepositoryError {}
+impl error::Error for RepositoryError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            RepositoryError::Backend(cause) => Some(cause.as_ref()),
+            _ => None,
+        }
+    }
+}
 
+/// Outcome of adding a recipe unless it is a duplicate.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum InsertOutcome {
+    /// The recipe was added with this id.
+    Inserted(Uuid),
+    /// The recipe with this id is the same, nothing was added.
+    Duplicate(Uuid),
+}
+
+impl InsertOutcome {
+    /// Returns the id of the new or of the existing recipe.
+    pub fn id(&self) -> Uuid {
+        match self {
+            InsertOutcome::Inserted(id) | InsertOutcome::Duplicate(id) => *id,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub enum UpdateResult {
     Changed,
     Created,
 }
 
+#[derive(Debug, PartialEq)]
+pub enum RemoveResult {
+    Removed,
+    NotFound,
+}
+
 #[cfg(test)]
 mod test {
     use std::ops::Bound;
 
-    use super::{Range, Repository, RepositoryError};
-    use crate::Recipe;
+    use super::{Page, Range, RangeError, RepositoryError};
+    use axum::headers::{self, Header};
+    use axum::http::HeaderValue;
     use spucky::spec;
 
-    lazy_static! {
-        static ref TESTDATA: Vec<Recipe> = vec![Recipe {
-            title: "Lasagne".to_string(),
-            preparation: "Du weist schon wie".to_string(),
-            servings: 2,
-            ingredients: vec![],
-        }];
+    spec! {
+        range_from_header {
+            case closed {
+                let value = "bytes=0-9";
+                let want = Ok(Range::Closed { start: 0, end: 9 });
+            }
+
+            case left_closed {
+                let value = "bytes=10-";
+                let want = Ok(Range::LeftClosed { start: 10 });
+            }
+
+            case single_position {
+                let value = "bytes=3-3";
+                let want = Ok(Range::Closed { start: 3, end: 3 });
+            }
+
+            case suffix {
+                let value = "bytes=-5";
+                let want = Err(RangeError::Suffix);
+            }
+
+            case multiple {
+                let value = "bytes=0-4,10-14";
+                let want = Err(RangeError::MultipleRanges);
+            }
+
+            case end_before_start {
+                let value = "bytes=9-5";
+                let want = Err(RangeError::Unsatisfiable);
+            }
+
+            case garbage {
+                let value = "bytes=abc";
+                let want = Err(RangeError::Malformed);
+            }
+
+            case no_positions {
+                let value = "bytes=-";
+                let want = Err(RangeError::Malformed);
+            }
+
+            let value = HeaderValue::from_static(value);
+            let header = headers::Range::decode(&mut std::iter::once(&value)).unwrap();
+            assert_eq!(want, Range::try_from(&header));
+        }
     }
 
     #[test]
-    fn test_insert() -> Result<(), Box<dyn std::error::Error>> {
-        let mut repo = Repository::new();
-
-        let recipe = Recipe {
-            title: "Lasagne".to_string(),
-            preparation: "Du weist schon wie".into(),
-            servings: 2,
-            ingredients: vec![],
-        };
+    fn backend_error_keeps_cause() {
+        use std::error::Error;
+
+        let cause = std::io::Error::other("disk full");
+        let error = RepositoryError::Backend(Box::new(cause));
+
+        assert_eq!("repository backend failed", error.to_string());
+        assert_eq!("disk full", error.source().unwrap().to_string());
+    }
+
+    spec! {
+        index_excluded_bounds {
+            case open {
+                let range = Range::Open { start: 1, end: 4 };
+                let want: &[i32] = &[3, 4];
+            }
+
+            case open_without_inner_index {
+                let range = Range::Open { start: 1, end: 2 };
+                let want: &[i32] = &[];
+            }
+
+            case closed_open {
+                let range = Range::ClosedOpen { start: 0, end: 2 };
+                let want: &[i32] = &[1, 2];
+            }
 
-        let id = repo.insert(&recipe)?;
+            case closed_open_empty {
+                let range = Range::ClosedOpen { start: 2, end: 2 };
+                let want: &[i32] = &[];
+            }
+
+            case closed_open_to_zero {
+                let range = Range::ClosedOpen { start: 0, end: 0 };
+                let want: &[i32] = &[];
+            }
+
+            case closed_open_behind_end {
+                let range = Range::ClosedOpen { start: 3, end: 10 };
+                let want: &[i32] = &[4, 5];
+            }
 
-        let copy = repo.get(&id)?;
+            case open_closed {
+                let range = Range::OpenClosed { start: 0, end: 2 };
+                let want: &[i32] = &[2, 3];
+            }
 
-        assert_eq!(&recipe, copy.unwrap());
+            case open_closed_empty {
+                let range = Range::OpenClosed { start: 2, end: 2 };
+                let want: &[i32] = &[];
+            }
 
-        Ok(())
+            case left_open {
+                let range = Range::LeftOpen { start: 2 };
+                let want: &[i32] = &[4, 5];
+            }
+
+            case left_open_at_max {
+                let range = Range::LeftOpen { start: usize::MAX };
+                let want: &[i32] = &[];
+            }
+
+            case right_open {
+                let range = Range::RightOpen { end: 2 };
+                let want: &[i32] = &[1, 2];
+            }
+
+            case right_open_at_zero {
+                let range = Range::RightOpen { end: 0 };
+                let want: &[i32] = &[];
+            }
+
+            let numbers = [1, 2, 3, 4, 5];
+            assert_eq!(want, range.index(&numbers));
+        }
     }
 
     spec! {
-        list_filled_repository {
+        index_start_behind_end {
+            case closed {
+                let range = Range::Closed { start: 50, end: 60 };
+            }
+
+            case closed_start_at_len {
+                let range = Range::Closed { start: 5, end: 9 };
+            }
+
+            case closed_inverted {
+                let range = Range::Closed { start: 2, end: 1 };
+            }
+
+            case closed_far_inverted {
+                let range = Range::Closed { start: 4, end: 1 };
+            }
 
-            case case1 {
+            case left_closed {
+                let range = Range::LeftClosed { start: 50 };
+            }
+
+            case left_closed_at_len {
+                let range = Range::LeftClosed { start: 5 };
+            }
+
+            case open {
+                let range = Range::Open { start: 50, end: 60 };
+            }
+
+            case closed_open {
+                let range = Range::ClosedOpen { start: 5, end: 60 };
+            }
+
+            case open_closed {
+                let range = Range::OpenClosed { start: 4, end: 60 };
+            }
+
+            case left_open {
+                let range = Range::LeftOpen { start: 4 };
+            }
+
+            let numbers = [1, 2, 3, 4, 5];
+            assert!(range.index(&numbers).is_empty());
+            assert!(range.is_empty(numbers.len()));
+            assert!(range.index::<i32>(&[]).is_empty());
+        }
+    }
+
+    spec! {
+        index_selects_nothing {
+            case empty_variant {
+                let range = Range::Empty;
+                let numbers = [1, 2, 3, 4, 5];
+            }
+
+            case empty_slice_unbounded {
                 let range = Range::Unbounded;
-                let want = 100;
+                let numbers: [i32; 0] = [];
             }
 
-            case case2  {
-                let range = Range::LeftClosed { start: 0 };
-                let want = 100;
+            case empty_slice_closed {
+                let range = Range::Closed { start: 0, end: 0 };
+                let numbers: [i32; 0] = [];
             }
 
-            case case3 {
-                let range = Range::RightClosed { end: 99 };
-                let want = 100;
+            case empty_slice_right_closed {
+                let range = Range::RightClosed { end: 10 };
+                let numbers: [i32; 0] = [];
             }
 
-            case case4 {
-                let range = Range::Closed { start: 0, end: 99 };
-                let want=  100;
+            case start_at_len {
+                let range = Range::Closed { start: 1, end: 1 };
+                let numbers = [1];
             }
 
-            case case5 {
-                let range = Range::Closed {start: 2, end: 1};
-                let want = 0;
+            case start_beyond_len {
+                let range = Range::Closed { start: 10, end: 20 };
+                let numbers = [1, 2, 3, 4, 5];
             }
 
-            case case6 {
-                let range = Range::Closed {start: 99, end: 100};
-                let want = 1;
+            case start_at_max {
+                let range = Range::LeftClosed { start: usize::MAX };
+                let numbers = [1, 2, 3, 4, 5];
             }
 
-            case case7 {
-                let range = Range::Closed {start: 99, end: 99};
-                let want = 1;
+            assert_eq!(&[] as &[i32], range.index(&numbers));
+            assert_eq!(0, range.len(numbers.len()));
+        }
+    }
+
+    #[test]
+    fn index_random_ranges() {
+        // xorshift, good enough to scatter the bounds and repeatable.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let mut position = || match next() % 4 {
+            0 => usize::MAX - (next() % 3) as usize,
+            _ => (next() % 12) as usize,
+        };
+
+        let numbers: Vec<usize> = (0..8).collect();
+        for _ in 0..10_000 {
+            let (start, end) = (position(), position());
+            let ranges = [
+                Range::Closed { start, end },
+                Range::Open { start, end },
+                Range::ClosedOpen { start, end },
+                Range::OpenClosed { start, end },
+                Range::LeftClosed { start },
+                Range::LeftOpen { start },
+                Range::RightClosed { end },
+                Range::RightOpen { end },
+            ];
+
+            for range in ranges {
+                for total in [0, 1, numbers.len()] {
+                    let selected = range.index(&numbers[..total]);
+                    assert_eq!(range.len(total), selected.len(), "{:?}", range);
+                    assert!(selected.iter().all(|index| range.contains(*index)));
+                }
             }
+        }
+    }
 
-            case case8 {
-                let range = Range::Closed {start: 0, end: 0};
-                let want = 1;
+    spec! {
+        range_len {
+            case empty {
+                let range = Range::Empty;
+                let want = [0, 0, 0];
             }
 
-            let mut repository = Repository::new();
-            fill_with_testdata(&mut repository);
+            case unbounded {
+                let range = Range::Unbounded;
+                let want = [0, 1, 5];
+            }
 
-            match repository.list(&range, "") {
-                Ok(toc) => assert_eq!(toc.content.len(), want),
-                Err(_) => panic!("unexpected error"),
+            case closed {
+                let range = Range::Closed { start: 1, end: 3 };
+                let want = [0, 0, 3];
             }
+
+            case closed_single {
+                let range = Range::Closed { start: 0, end: 0 };
+                let want = [0, 1, 1];
+            }
+
+            case closed_inverted {
+                let range = Range::Closed { start: 3, end: 1 };
+                let want = [0, 0, 0];
+            }
+
+            case closed_behind_end {
+                let range = Range::Closed { start: 4, end: 100 };
+                let want = [0, 0, 1];
+            }
+
+            case left_closed {
+                let range = Range::LeftClosed { start: 2 };
+                let want = [0, 0, 3];
+            }
+
+            case right_closed {
+                let range = Range::RightClosed { end: 2 };
+                let want = [0, 1, 3];
+            }
+
+            case open {
+                let range = Range::Open { start: 0, end: 3 };
+                let want = [0, 0, 2];
+            }
+
+            case closed_open {
+                let range = Range::ClosedOpen { start: 0, end: 3 };
+                let want = [0, 1, 3];
+            }
+
+            case open_closed {
+                let range = Range::OpenClosed { start: 0, end: 3 };
+                let want = [0, 0, 3];
+            }
+
+            case left_open {
+                let range = Range::LeftOpen { start: 0 };
+                let want = [0, 0, 4];
+            }
+
+            case right_open {
+                let range = Range::RightOpen { end: 3 };
+                let want = [0, 1, 3];
+            }
+
+            let got = [0, 1, 5].map(|total| range.len(total));
+            assert_eq!(want, got);
+            assert_eq!(want.map(|len| len == 0), [0, 1, 5].map(|total| range.is_empty(total)));
         }
+    }
 
+    /// Ranges of every variant, including ones ending before their
+    /// start and ones touching the ends of `usize`.
+    fn all_ranges() -> Vec<Range> {
+        let mut ranges = vec![Range::Empty, Range::Unbounded];
+        let positions = [0, 1, 3, 6, usize::MAX];
+        for start in positions {
+            ranges.push(Range::LeftClosed { start });
+            ranges.push(Range::LeftOpen { start });
+            ranges.push(Range::RightClosed { end: start });
+            ranges.push(Range::RightOpen { end: start });
+            for end in positions {
+                ranges.push(Range::Closed { start, end });
+                ranges.push(Range::Open { start, end });
+                ranges.push(Range::ClosedOpen { start, end });
+                ranges.push(Range::OpenClosed { start, end });
+            }
+        }
+        ranges
+    }
+
+    #[test]
+    fn range_contains_like_range_bounds() {
+        use std::ops::RangeBounds;
+
+        for range in all_ranges() {
+            for index in (0..10).chain([usize::MAX - 1, usize::MAX]) {
+                assert_eq!(
+                    RangeBounds::contains(&range, &index),
+                    range.contains(index),
+                    "{:?} contains {}",
+                    range,
+                    index
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn range_intersect_all_variants() {
+        for left in all_ranges() {
+            for right in all_ranges() {
+                let both = left.intersect(&right);
+                assert_eq!(both, right.intersect(&left), "{:?} ∩ {:?}", left, right);
+                for index in (0..10).chain([usize::MAX - 1, usize::MAX]) {
+                    assert_eq!(
+                        left.contains(index) && right.contains(index),
+                        both.contains(index),
+                        "{:?} ∩ {:?} contains {}",
+                        left,
+                        right,
+                        index
+                    );
+                }
+            }
+        }
     }
 
     spec! {
-        list_empty_repository {
-            case case1 {
-                let range = Range::Closed {start: 0, end: 0};
-                let want = 0;
+        range_intersect {
+            case empty_absorbs {
+                let left = Range::Empty;
+                let right = Range::Unbounded;
+                let want = Range::Empty;
+            }
+
+            case unbounded_is_neutral {
+                let left = Range::Unbounded;
+                let right = Range::Closed { start: 2, end: 5 };
+                let want = Range::Closed { start: 2, end: 5 };
+            }
+
+            case overlapping {
+                let left = Range::Closed { start: 2, end: 5 };
+                let right = Range::Closed { start: 4, end: 9 };
+                let want = Range::Closed { start: 4, end: 5 };
+            }
+
+            case disjoint {
+                let left = Range::Closed { start: 2, end: 5 };
+                let right = Range::Closed { start: 6, end: 9 };
+                let want = Range::Empty;
+            }
+
+            case touching {
+                let left = Range::RightClosed { end: 5 };
+                let right = Range::LeftClosed { start: 5 };
+                let want = Range::Closed { start: 5, end: 5 };
+            }
+
+            case half_open {
+                let left = Range::LeftOpen { start: 2 };
+                let right = Range::RightOpen { end: 5 };
+                let want = Range::Closed { start: 3, end: 4 };
+            }
+
+            case same_side {
+                let left = Range::LeftClosed { start: 2 };
+                let right = Range::LeftOpen { start: 2 };
+                let want = Range::LeftClosed { start: 3 };
             }
 
-            let repository = Repository::new();
-            match repository.list(&range, "") {
-                Ok(toc) => assert_eq!(toc.content.len(), want),
-                Err(_) => panic!("unexpected error",)
+            case inverted {
+                let left = Range::Closed { start: 5, end: 2 };
+                let right = Range::Unbounded;
+                let want = Range::Empty;
             }
+
+            case open_without_index {
+                let left = Range::Open { start: 2, end: 3 };
+                let right = Range::Unbounded;
+                let want = Range::Empty;
+            }
+
+            assert_eq!(want, left.intersect(&right));
+        }
+    }
+
+    spec! {
+        page_index {
+            case all {
+                let page = Page::all();
+                let want: &[i32] = &[1, 2, 3, 4, 5];
+            }
+
+            case first {
+                let page = Page::first(2);
+                let want: &[i32] = &[1, 2];
+            }
+
+            case nothing {
+                let page = Page::first(0);
+                let want: &[i32] = &[];
+            }
+
+            case middle {
+                let page = Page { offset: 1, limit: Some(3) };
+                let want: &[i32] = &[2, 3, 4];
+            }
+
+            case limit_behind_end {
+                let page = Page { offset: 3, limit: Some(10) };
+                let want: &[i32] = &[4, 5];
+            }
+
+            case offset_at_end {
+                let page = Page { offset: 5, limit: None };
+                let want: &[i32] = &[];
+            }
+
+            case offset_at_max {
+                let page = Page { offset: u64::MAX, limit: Some(u64::MAX) };
+                let want: &[i32] = &[];
+            }
+
+            let numbers = [1, 2, 3, 4, 5];
+            assert_eq!(want, page.index(&numbers));
+        }
+    }
+
+    spec! {
+        page_from_bounds {
+            case unbounded {
+                let bounds = (Bound::Unbounded, Bound::Unbounded);
+                let want = Page::all();
+            }
+
+            case closed {
+                let bounds = (Bound::Included(10), Bound::Included(19));
+                let want = Page { offset: 10, limit: Some(10) };
+            }
+
+            case excluded {
+                let bounds = (Bound::Excluded(9), Bound::Excluded(20));
+                let want = Page { offset: 10, limit: Some(10) };
+            }
+
+            case left_closed {
+                let bounds = (Bound::Included(10), Bound::Unbounded);
+                let want = Page { offset: 10, limit: None };
+            }
+
+            case right_open {
+                let bounds = (Bound::Unbounded, Bound::Excluded(10));
+                let want = Page::first(10);
+            }
+
+            case inverted {
+                let bounds = (Bound::Included(10), Bound::Included(9));
+                let want = Page::first(0);
+            }
+
+            case included_end_at_max {
+                let bounds = (Bound::Unbounded, Bound::Included(u64::MAX));
+                let want = Page::all();
+            }
+
+            case excluded_start_at_max {
+                let bounds = (Bound::Excluded(u64::MAX), Bound::Unbounded);
+                let want = Page::first(0);
+            }
+
+            assert_eq!(want, Page::from_bounds(&bounds));
         }
     }
 
     #[test]
-    fn list_some_keys() -> Result<(), RepositoryError> {
-        let mut repository = Repository::new();
-        fill_with_testdata(&mut repository);
+    fn page_selects_like_range() {
+        for range in all_ranges() {
+            let page = Page::from(range);
+            for total in 0..8 {
+                let numbers: Vec<usize> = (0..total).collect();
+                assert_eq!(
+                    range.index(&numbers),
+                    page.index(&numbers),
+                    "{:?} as {:?}",
+                    range,
+                    page
+                );
+            }
+        }
+    }
 
-        struct Testcase {
-            range: Range,
-            want: usize,
+    #[test]
+    fn range_len_counts_contained_indices() {
+        for range in all_ranges() {
+            for total in 0..8 {
+                let want = (0..total).filter(|index| range.contains(*index)).count();
+                assert_eq!(want, range.len(total), "{:?} of {}", range, total);
+                assert_eq!(want, range.index(&vec![(); total]).len());
+            }
         }
+    }
 
-        let td3: Vec<Testcase> = vec![
-            Testcase {
-                range: Range::Unbounded,
-                want: 100,
-            },
-            Testcase {
-                range: Range::LeftClosed { start: 0 },
-                want: 100,
-            },
-            Testcase {
-                range: Range::RightClosed { end: 99 },
-                want: 100,
-            },
-            Testcase {
-                range: Range::Closed { start: 0, end: 99 },
-                want: 100,
-            },
-        ];
+    spec! {
+        index_exclusive_start {
+            case left_open_empty_slice {
+                let range = Range::LeftOpen { start: 0 };
+                let numbers: &[i32] = &[];
+                let want: &[i32] = &[];
+            }
+
+            case left_open_single_item {
+                let range = Range::LeftOpen { start: 0 };
+                let numbers: &[i32] = &[1];
+                let want: &[i32] = &[];
+            }
+
+            case left_open_from_bounds {
+                let range = Range::from(&(Bound::Excluded(0), Bound::Unbounded));
+                let numbers: &[i32] = &[1];
+                let want: &[i32] = &[];
+            }
+
+            case left_open_many_items {
+                let range = Range::LeftOpen { start: 0 };
+                let numbers: &[i32] = &[1, 2, 3, 4, 5];
+                let want: &[i32] = &[2, 3, 4, 5];
+            }
+
+            case open_empty_slice {
+                let range = Range::Open { start: 0, end: 2 };
+                let numbers: &[i32] = &[];
+                let want: &[i32] = &[];
+            }
+
+            case open_single_item {
+                let range = Range::Open { start: 0, end: 2 };
+                let numbers: &[i32] = &[1];
+                let want: &[i32] = &[];
+            }
+
+            case open_many_items {
+                let range = Range::Open { start: 0, end: 2 };
+                let numbers: &[i32] = &[1, 2, 3, 4, 5];
+                let want: &[i32] = &[2];
+            }
+
+            case open_many_items_behind_end {
+                let range = Range::Open { start: 2, end: 10 };
+                let numbers: &[i32] = &[1, 2, 3, 4, 5];
+                let want: &[i32] = &[4, 5];
+            }
 
-        for testcase in &td3 {
-            let keys = repository.list_ids(&testcase.range);
-            assert_eq!(keys.len(), testcase.want)
+            assert_eq!(want, range.index(numbers));
+            assert_eq!(want.len(), range.len(numbers.len()));
+            let contained = (0..numbers.len()).filter(|index| range.contains(*index));
+            assert_eq!(want.len(), contained.count());
         }
-        Ok(())
     }
 
-    fn fill_with_testdata(repository: &mut Repository) {
-        for ele in 0..100 {
-            let recipe = Recipe {
-                title: format!("Recipe {}", ele),
-                preparation: format!("Preparation of recipe {}", ele),
-                servings: (ele % 3) + 1,
-                ingredients: vec![],
-            };
-            _ = repository.insert(&recipe);
+    #[test]
+    fn range_from_all_bound_combinations() {
+        use std::ops::RangeBounds;
+
+        let bounds = |value| {
+            [
+                Bound::Included(value),
+                Bound::Excluded(value),
+                Bound::Unbounded,
+            ]
+        };
+        let numbers: Vec<usize> = (0..10).collect();
+
+        let mut variants = Vec::new();
+        for start in bounds(2) {
+            for end in bounds(6) {
+                let range = Range::from(&(start, end));
+                variants.push(range);
+
+                // The range selects the same items as the pair of bounds.
+                let want = &numbers[(start.map(|v| v as usize), end.map(|v| v as usize))];
+                assert_eq!(want, range.index(&numbers), "{:?}", (start, end));
+                assert_eq!(
+                    (start.map(|v| v as usize), end.map(|v| v as usize)),
+                    (range.start_bound().cloned(), range.end_bound().cloned())
+                );
+            }
         }
+
+        assert_eq!(
+            vec![
+                Range::Closed { start: 2, end: 6 },
+                Range::ClosedOpen { start: 2, end: 6 },
+                Range::LeftClosed { start: 2 },
+                Range::OpenClosed { start: 2, end: 6 },
+                Range::Open { start: 2, end: 6 },
+                Range::LeftOpen { start: 2 },
+                Range::RightClosed { end: 6 },
+                Range::RightOpen { end: 6 },
+                Range::Unbounded,
+            ],
+            variants
+        );
+
+        assert_eq!(
+            Range::LeftOpen { start: usize::MAX },
+            Range::from(&(Bound::Excluded(u64::MAX), Bound::Unbounded))
+        );
+    }
+
+    #[test]
+    fn range_bounds_of_excluded_variants() {
+        use std::ops::RangeBounds;
+
+        let range = Range::ClosedOpen { start: 10, end: 20 };
+        assert_eq!(
+            (Bound::Included(&10), Bound::Excluded(&20)),
+            (range.start_bound(), range.end_bound())
+        );
+        assert!(RangeBounds::contains(&range, &19));
+        assert!(!RangeBounds::contains(&range, &20));
+
+        let range = Range::LeftOpen { start: 10 };
+        assert_eq!(
+            (Bound::Excluded(&10), Bound::Unbounded),
+            (range.start_bound(), range.end_bound())
+        );
     }
 
     #[test]
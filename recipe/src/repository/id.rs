@@ -0,0 +1,92 @@
+//! Generators of the ids of new recipes.
+
+use uuid::Uuid;
+
+/// Chooses the id of each recipe inserted without one.
+pub trait IdGenerator: Send + Sync {
+    /// Returns the id for the next recipe.
+    fn next_id(&mut self) -> Uuid;
+}
+
+/// Generates random version 4 ids. Used unless a repository is given
+/// another generator.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn next_id(&mut self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Generates the ids 1, 2, 3 and so on, for tests which compare ids.
+///
+/// # Example
+///
+/// ```rust
+/// use recipers::repository::{IdGenerator, SequentialIds};
+///
+/// let mut ids = SequentialIds::new();
+/// assert_eq!("00000000-0000-0000-0000-000000000001", ids.next_id().to_string());
+/// ```
+#[derive(Debug, Clone)]
+pub struct SequentialIds {
+    next: u128,
+}
+
+impl SequentialIds {
+    /// Starts the sequence at 1.
+    pub fn new() -> SequentialIds {
+        SequentialIds::starting_at(1)
+    }
+
+    /// Starts the sequence at the given number.
+    pub fn starting_at(first: u128) -> SequentialIds {
+        SequentialIds { next: first }
+    }
+}
+
+impl Default for SequentialIds {
+    fn default() -> Self {
+        SequentialIds::new()
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id(&mut self) -> Uuid {
+        let id = Uuid::from_u128(self.next);
+        self.next += 1;
+        id
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IdGenerator, RandomIds, SequentialIds};
+    use spucky::spec;
+    use uuid::Uuid;
+
+    spec! {
+        sequential_ids {
+            case from_one {
+                let mut generator = SequentialIds::new();
+                let want = [1, 2, 3];
+            }
+
+            case starting_at {
+                let mut generator = SequentialIds::starting_at(41);
+                let want = [41, 42, 43];
+            }
+
+            let got: Vec<Uuid> = (0..3).map(|_| generator.next_id()).collect();
+            let want: Vec<Uuid> = want.into_iter().map(Uuid::from_u128).collect();
+            assert_eq!(want, got);
+        }
+    }
+
+    #[test]
+    fn random_ids_differ() {
+        let mut generator = RandomIds;
+        assert_ne!(generator.next_id(), generator.next_id());
+    }
+}
@@ -0,0 +1,407 @@
+//! Traces and measures the calls to a repository.
+
+use super::{
+    Filter, InsertOutcome, Page, RemoveResult, Repository, RepositoryError, RepositoryEvent,
+    UpdateResult,
+};
+use crate::{Recipe, Stored, TableOfContents};
+use async_trait::async_trait;
+use std::future::Future;
+use tokio::sync::broadcast;
+use tracing::{field, Instrument};
+use uuid::Uuid;
+
+/// Wraps a repository and observes every call to it.
+///
+/// Each call runs in a `repository` span recording the operation, the
+/// id of the recipe if there is one, and the result: `ok` or the kind
+/// of the [RepositoryError]. With the `metrics` feature the calls are
+/// also counted in `repository_operations_total` by operation and
+/// result, and timed in `repository_operation_duration_seconds`.
+///
+/// # Example
+///
+/// ```rust
+/// use recipers::repository::{memory, InstrumentedRepository, Repository};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let repository = InstrumentedRepository::new(memory::Repository::new());
+/// assert_eq!(0, repository.count().await.unwrap());
+/// # }
+/// ```
+pub struct InstrumentedRepository<R> {
+    inner: R,
+}
+
+impl<R: Repository> InstrumentedRepository<R> {
+    pub fn new(inner: R) -> InstrumentedRepository<R> {
+        InstrumentedRepository { inner }
+    }
+
+    /// Returns the wrapped repository.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+/// Runs a call to the wrapped repository in a span and records its
+/// outcome.
+async fn observe<T>(
+    operation: &'static str,
+    id: Option<&Uuid>,
+    call: impl Future<Output = Result<T, RepositoryError>>,
+) -> Result<T, RepositoryError> {
+    let span = tracing::info_span!(
+        "repository",
+        operation,
+        id = field::Empty,
+        result = field::Empty
+    );
+    if let Some(id) = id {
+        span.record("id", field::display(id));
+    }
+
+    #[cfg(feature = "metrics")]
+    let start = std::time::Instant::now();
+    let result = call.instrument(span.clone()).await;
+    let outcome = match &result {
+        Ok(_) => "ok",
+        Err(error) => kind(error),
+    };
+    span.record("result", outcome);
+
+    #[cfg(feature = "metrics")]
+    {
+        let seconds = start.elapsed().as_secs_f64();
+        metrics::increment_counter!(
+            "repository_operations_total",
+            "operation" => operation,
+            "result" => outcome
+        );
+        metrics::histogram!(
+            "repository_operation_duration_seconds",
+            seconds,
+            "operation" => operation
+        );
+    }
+
+    result
+}
+
+/// Names the variant of the error in spans and metrics.
+fn kind(error: &RepositoryError) -> &'static str {
+    match error {
+        RepositoryError::DuplicateId(_) => "duplicate_id",
+        RepositoryError::Conflict { .. } => "conflict",
+        RepositoryError::CapacityExceeded { .. } => "capacity_exceeded",
+        RepositoryError::Backend(_) => "backend",
+    }
+}
+
+#[async_trait]
+impl<R: Repository> Repository for InstrumentedRepository<R> {
+    async fn insert(&mut self, recipe: &Recipe) -> Result<Uuid, RepositoryError> {
+        observe("insert", None, self.inner.insert(recipe)).await
+    }
+
+    async fn insert_all(
+        &mut self,
+        recipes: &[Recipe],
+        skip_duplicates: bool,
+    ) -> Result<Vec<InsertOutcome>, RepositoryError> {
+        observe(
+            "insert_all",
+            None,
+            self.inner.insert_all(recipes, skip_duplicates),
+        )
+        .await
+    }
+
+    async fn insert_with_id(&mut self, id: &Uuid, recipe: &Recipe) -> Result<(), RepositoryError> {
+        observe(
+            "insert_with_id",
+            Some(id),
+            self.inner.insert_with_id(id, recipe),
+        )
+        .await
+    }
+
+    async fn insert_unique(&mut self, recipe: &Recipe) -> Result<InsertOutcome, RepositoryError> {
+        observe("insert_unique", None, self.inner.insert_unique(recipe)).await
+    }
+
+    async fn list(&self, page: &Page, filter: &Filter) -> Result<TableOfContents, RepositoryError> {
+        observe("list", None, self.inner.list(page, filter)).await
+    }
+
+    async fn get(&self, id: &Uuid) -> Result<Option<Stored<Recipe>>, RepositoryError> {
+        observe("get", Some(id), self.inner.get(id)).await
+    }
+
+    async fn exists(&self, id: &Uuid) -> Result<bool, RepositoryError> {
+        observe("exists", Some(id), self.inner.exists(id)).await
+    }
+
+    async fn count(&self) -> Result<u64, RepositoryError> {
+        observe("count", None, self.inner.count()).await
+    }
+
+    async fn find_by_title(&self, title: &str) -> Result<Vec<(Uuid, Recipe)>, RepositoryError> {
+        observe("find_by_title", None, self.inner.find_by_title(title)).await
+    }
+
+    async fn for_each(
+        &self,
+        f: &mut (dyn for<'r> FnMut(&'r Uuid, &'r Recipe) + Send),
+    ) -> Result<(), RepositoryError> {
+        observe("for_each", None, self.inner.for_each(f)).await
+    }
+
+    async fn import_all(&mut self, entries: &[(Uuid, Recipe)]) -> Result<usize, RepositoryError> {
+        observe("import_all", None, self.inner.import_all(entries)).await
+    }
+
+    async fn remove(&mut self, id: &Uuid) -> Result<RemoveResult, RepositoryError> {
+        observe("remove", Some(id), self.inner.remove(id)).await
+    }
+
+    async fn remove_all(&mut self, ids: &[Uuid]) -> Result<u64, RepositoryError> {
+        observe("remove_all", None, self.inner.remove_all(ids)).await
+    }
+
+    async fn clear(&mut self) -> Result<u64, RepositoryError> {
+        observe("clear", None, self.inner.clear()).await
+    }
+
+    async fn update(
+        &mut self,
+        id: &Uuid,
+        recipe: &Recipe,
+    ) -> Result<UpdateResult, RepositoryError> {
+        observe("update", Some(id), self.inner.update(id, recipe)).await
+    }
+
+    async fn update_if_match(
+        &mut self,
+        id: &Uuid,
+        recipe: &Recipe,
+        expected_revision: u64,
+    ) -> Result<UpdateResult, RepositoryError> {
+        observe(
+            "update_if_match",
+            Some(id),
+            self.inner.update_if_match(id, recipe, expected_revision),
+        )
+        .await
+    }
+
+    fn watch(&self) -> broadcast::Receiver<RepositoryEvent> {
+        self.inner.watch()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::InstrumentedRepository;
+    use crate::repository::{memory::Ephemeral, Repository, RepositoryError};
+    use crate::Recipe;
+    use spucky::spec;
+    use std::{
+        collections::BTreeMap,
+        fmt,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        subscriber::DefaultGuard,
+        Subscriber,
+    };
+    use tracing_subscriber::{layer::Context, layer::SubscriberExt, Layer};
+    use uuid::Uuid;
+
+    type Fields = BTreeMap<&'static str, String>;
+
+    /// Collects the fields of the repository spans.
+    #[derive(Clone, Default)]
+    struct Spans(Arc<Mutex<Vec<(Id, Fields)>>>);
+
+    struct Collect<'a>(&'a mut Fields);
+
+    impl Visit for Collect<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name(), value.to_owned());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.insert(field.name(), format!("{:?}", value));
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for Spans {
+        fn on_new_span(&self, attributes: &Attributes<'_>, id: &Id, _: Context<'_, S>) {
+            if attributes.metadata().name() == "repository" {
+                let mut fields = Fields::new();
+                attributes.record(&mut Collect(&mut fields));
+                self.0.lock().unwrap().push((id.clone(), fields));
+            }
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, _: Context<'_, S>) {
+            let mut spans = self.0.lock().unwrap();
+            if let Some((_, fields)) = spans.iter_mut().rev().find(|(span, _)| span == id) {
+                values.record(&mut Collect(fields));
+            }
+        }
+    }
+
+    /// An instrumented repository holding one recipe, which records
+    /// the spans of the calls made after its creation.
+    struct Traced {
+        repository: InstrumentedRepository<Ephemeral>,
+        id: Uuid,
+        spans: Spans,
+        _subscriber: DefaultGuard,
+    }
+
+    impl Traced {
+        async fn new() -> Traced {
+            let mut repository = InstrumentedRepository::new(Ephemeral::new());
+            let id = repository.inner.insert(&recipe()).await.unwrap();
+
+            // The tests run on a single thread, so the subscriber sees
+            // every call made until it is dropped.
+            let spans = Spans::default();
+            let subscriber = tracing_subscriber::registry().with(spans.clone());
+            Traced {
+                repository,
+                id,
+                spans,
+                _subscriber: tracing::subscriber::set_default(subscriber),
+            }
+        }
+
+        /// Returns the fields of the last span.
+        fn last(&self) -> Fields {
+            let spans = self.spans.0.lock().unwrap();
+            spans.last().map(|(_, fields)| fields.clone()).unwrap()
+        }
+    }
+
+    fn recipe() -> Recipe {
+        Recipe {
+            title: "Lasagne".into(),
+            servings: 2,
+            ..Default::default()
+        }
+    }
+
+    spec! {
+        async span_per_call {
+            case insert {
+                let mut traced = Traced::new().await;
+                let _ = traced.repository.insert(&recipe()).await;
+                let want = [("operation", "insert"), ("result", "ok")];
+            }
+
+            case get {
+                let traced = Traced::new().await;
+                let _ = traced.repository.get(&traced.id).await;
+                let want = [("operation", "get"), ("result", "ok")];
+            }
+
+            case count {
+                let traced = Traced::new().await;
+                let _ = traced.repository.count().await;
+                let want = [("operation", "count"), ("result", "ok")];
+            }
+
+            case duplicate_id {
+                let mut traced = Traced::new().await;
+                let id = traced.id;
+                let _ = traced.repository.insert_with_id(&id, &recipe()).await;
+                let want = [("operation", "insert_with_id"), ("result", "duplicate_id")];
+            }
+
+            case conflict {
+                let mut traced = Traced::new().await;
+                let id = traced.id;
+                let _ = traced.repository.update_if_match(&id, &recipe(), 7).await;
+                let want = [("operation", "update_if_match"), ("result", "conflict")];
+            }
+
+            let fields = traced.last();
+            for (name, value) in want {
+                assert_eq!(Some(value), fields.get(name).map(String::as_str), "{}", name);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn span_records_id() {
+        let mut traced = Traced::new().await;
+        let id = traced.id;
+        let _ = traced.repository.remove(&id).await;
+        assert_eq!(Some(&id.to_string()), traced.last().get("id"));
+
+        let _ = traced.repository.count().await;
+        assert_eq!(None, traced.last().get("id"));
+    }
+
+    #[tokio::test]
+    async fn results_pass_through() -> Result<(), RepositoryError> {
+        let mut repository = InstrumentedRepository::new(Ephemeral::new());
+        let id = repository.insert(&recipe()).await?;
+
+        assert_eq!(&recipe(), repository.get(&id).await?.unwrap().value());
+        assert!(matches!(
+            repository.insert_with_id(&id, &recipe()).await,
+            Err(RepositoryError::DuplicateId(duplicate)) if duplicate == id
+        ));
+        assert_eq!(1, repository.into_inner().count().await?);
+        Ok(())
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn metrics_per_call() -> Result<(), RepositoryError> {
+        use metrics_util::debugging::{DebugValue, DebuggingRecorder, Snapshotter};
+        use metrics_util::MetricKind;
+
+        // Every test runs in its own thread and sees only its metrics.
+        let _ = DebuggingRecorder::per_thread().install();
+
+        let mut repository = InstrumentedRepository::new(Ephemeral::new());
+        let id = repository.insert(&recipe()).await?;
+        repository.get(&id).await?;
+        repository.get(&id).await?;
+        assert!(repository.insert_with_id(&id, &recipe()).await.is_err());
+
+        let snapshot = Snapshotter::current_thread_snapshot().unwrap().into_vec();
+        let counter = |operation: &str, result: &str| {
+            snapshot.iter().find_map(|(key, _, _, value)| {
+                let labels: Vec<_> = key.key().labels().map(|l| (l.key(), l.value())).collect();
+                let found = key.kind() == MetricKind::Counter
+                    && key.key().name() == "repository_operations_total"
+                    && labels.contains(&("operation", operation))
+                    && labels.contains(&("result", result));
+                found.then_some(value)
+            })
+        };
+        assert_eq!(Some(&DebugValue::Counter(1)), counter("insert", "ok"));
+        assert_eq!(Some(&DebugValue::Counter(2)), counter("get", "ok"));
+        assert_eq!(
+            Some(&DebugValue::Counter(1)),
+            counter("insert_with_id", "duplicate_id")
+        );
+        assert_eq!(None, counter("get", "backend"));
+
+        let timed = snapshot.iter().any(|(key, _, _, value)| {
+            key.key().name() == "repository_operation_duration_seconds"
+                && matches!(value, DebugValue::Histogram(durations) if durations.len() == 2)
+        });
+        assert!(timed);
+        Ok(())
+    }
+}
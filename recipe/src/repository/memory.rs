@@ -0,0 +1,2687 @@
+//! Keeps recipes in memory.
+//!
+//! The recipes are lost when the server stops, unless the repository
+//! keeps a snapshot in a file.
+
+use super::{
+    fold_case, Filter, IdGenerator, InsertOutcome, Page, RandomIds, Range, RemoveResult,
+    RepositoryError, RepositoryEvent, SearchFields, SearchMode, SortBy, UpdateResult,
+};
+use crate::{Recipe, Stored, Summary, TableOfContents};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+};
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+    sync::broadcast,
+};
+use uuid::Uuid;
+
+/// The repository used by the server.
+pub type Repository = Ephemeral;
+
+/// Number of events kept for receivers which fall behind.
+const EVENT_CAPACITY: usize = 256;
+
+/// An in-memory repository for recipes
+///
+/// A repository created by [Ephemeral::with_persistence] keeps a
+/// snapshot of its recipes in a JSON file, so they survive a restart.
+/// One created by [Ephemeral::with_capacity_limit] holds a bounded
+/// number of recipes.
+pub struct Ephemeral {
+    entries: HashMap<Uuid, Stored<Recipe>>,
+    /// The ids of the recipes by title, each list sorted. Iterating
+    /// the index yields the order of [SortBy::TitleAsc].
+    titles: BTreeMap<String, Vec<Uuid>>,
+    /// The titles of [Ephemeral::titles] by their [folded] form, to
+    /// look them up ignoring case.
+    folded: BTreeMap<String, BTreeSet<String>>,
+    events: broadcast::Sender<RepositoryEvent>,
+    /// The file keeping the snapshot, if the recipes are persisted.
+    snapshot: Option<PathBuf>,
+    ids: Box<dyn IdGenerator>,
+    /// The maximum number of recipes, if the repository is bounded.
+    capacity: Option<usize>,
+    /// The recipes as they were before the current change, to undo
+    /// it if it fails.
+    journal: Vec<(Uuid, Option<Stored<Recipe>>)>,
+    /// The events of the current change, sent once it succeeded.
+    pending: Vec<RepositoryEvent>,
+}
+
+/// Returns the title in lower case without surrounding whitespace,
+/// so the titles starting with a prefix in any case are next to each
+/// other in the index.
+fn folded(title: &str) -> String {
+    fold_case(title.trim())
+}
+
+impl Default for Ephemeral {
+    fn default() -> Self {
+        Ephemeral::new()
+    }
+}
+
+impl Ephemeral {
+    /// Creates a new repository
+    pub fn new() -> Ephemeral {
+        Ephemeral::with_id_generator(RandomIds)
+    }
+
+    /// Creates a new repository which takes the ids of inserted
+    /// recipes from the generator.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::repository::{memory::Ephemeral, Repository, SequentialIds};
+    /// use recipers::Recipe;
+    /// use uuid::Uuid;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut repository = Ephemeral::with_id_generator(SequentialIds::new());
+    /// let id = repository.insert(&Recipe::default()).await.unwrap();
+    /// assert_eq!(Uuid::from_u128(1), id);
+    /// # }
+    /// ```
+    pub fn with_id_generator(ids: impl IdGenerator + 'static) -> Ephemeral {
+        Ephemeral {
+            entries: HashMap::new(),
+            titles: BTreeMap::new(),
+            folded: BTreeMap::new(),
+            events: broadcast::channel(EVENT_CAPACITY).0,
+            snapshot: None,
+            ids: Box::new(ids),
+            capacity: None,
+            journal: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Creates a repository which holds at most `max_entries` recipes.
+    ///
+    /// Adding a recipe to a full repository fails with
+    /// [RepositoryError::CapacityExceeded], no recipe is evicted to
+    /// make room. Replacing a stored recipe is always possible, and
+    /// removing recipes frees their places.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::repository::{memory::Ephemeral, Repository, RepositoryError};
+    /// use recipers::Recipe;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let mut repository = Ephemeral::with_capacity_limit(1);
+    /// let id = repository.insert(&Recipe::default()).await.unwrap();
+    /// assert!(matches!(
+    ///     repository.insert(&Recipe::default()).await,
+    ///     Err(RepositoryError::CapacityExceeded { capacity: 1 })
+    /// ));
+    /// assert!(repository.update(&id, &Recipe::default()).await.is_ok());
+    /// # }
+    /// ```
+    pub fn with_capacity_limit(max_entries: usize) -> Ephemeral {
+        let mut repository = Ephemeral::new();
+        repository.capacity = Some(max_entries);
+        repository
+    }
+
+    /// Creates a repository which keeps its recipes in a JSON file.
+    ///
+    /// The recipes of an existing file are loaded, keeping their ids
+    /// and metadata. A missing file starts an empty repository. A
+    /// file which cannot be read or is no snapshot fails with
+    /// [RepositoryError::Backend] and is left as it is.
+    ///
+    /// The file is rewritten after every change. It is replaced
+    /// atomically, so a crash while writing leaves the previous
+    /// snapshot intact. A change whose snapshot cannot be written
+    /// fails with [RepositoryError::Backend] and is undone.
+    pub async fn with_persistence(path: PathBuf) -> Result<Ephemeral, RepositoryError> {
+        let mut repository = Ephemeral::new();
+        match read_snapshot(&path).await {
+            Ok(entries) => {
+                for (id, stored) in entries {
+                    repository.store(id, stored);
+                }
+                // Loading is no change which could be undone.
+                repository.journal.clear();
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => (),
+            Err(error) => return Err(RepositoryError::Backend(Box::new(error))),
+        }
+
+        repository.snapshot = Some(path);
+        Ok(repository)
+    }
+
+    /// Writes the snapshot file of a persisted repository.
+    ///
+    /// Every change is written anyway, so this is only needed to
+    /// restore a snapshot file which was removed.
+    pub async fn flush(&self) -> Result<(), RepositoryError> {
+        self.persist()
+            .await
+            .map_err(|error| RepositoryError::Backend(Box::new(error)))
+    }
+
+    /// Writes the snapshot file, if the recipes are persisted.
+    async fn persist(&self) -> io::Result<()> {
+        match &self.snapshot {
+            Some(path) => write_snapshot(path, &self.entries).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Completes a change with its result.
+    ///
+    /// A successful change is written to the snapshot and its events
+    /// are sent. A failed change, and one whose snapshot cannot be
+    /// written, is undone without sending any event, so the recipes
+    /// in memory and in the file never differ.
+    async fn commit<T>(
+        &mut self,
+        result: Result<T, RepositoryError>,
+    ) -> Result<T, RepositoryError> {
+        let result = match result {
+            Ok(value) if !self.journal.is_empty() => self
+                .persist()
+                .await
+                .map(|()| value)
+                .map_err(|error| RepositoryError::Backend(Box::new(error))),
+            result => result,
+        };
+
+        match result {
+            Ok(_) => {
+                self.journal.clear();
+                for event in self.pending.drain(..) {
+                    // Without receivers nobody is interested in the event.
+                    let _ = self.events.send(event);
+                }
+            }
+            Err(_) => self.rollback(),
+        }
+        result
+    }
+
+    /// Restores the recipes changed by the current change and drops
+    /// its events.
+    fn rollback(&mut self) {
+        self.pending.clear();
+        while let Some((id, previous)) = self.journal.pop() {
+            if let Some(current) = self.entries.remove(&id) {
+                self.unindex(&id, &current.title);
+            }
+            if let Some(previous) = previous {
+                self.index(id, &previous.title);
+                self.entries.insert(id, previous);
+            }
+        }
+    }
+
+    /// Adds a recipe with a new id without committing the change.
+    fn add(&mut self, recipe: &Recipe) -> Result<Uuid, RepositoryError> {
+        self.reserve(1)?;
+        let id = self.ids.next_id();
+        if self.entries.contains_key(&id) {
+            return Err(RepositoryError::DuplicateId(id));
+        }
+
+        self.store(id, Stored::new(recipe.clone(), Utc::now()));
+        self.emit(RepositoryEvent::Inserted(id));
+        Ok(id)
+    }
+
+    /// Adds a recipe like [Ephemeral::add] unless the same recipe is
+    /// stored already, see [Recipe::semantically_equals].
+    fn add_unique(&mut self, recipe: &Recipe) -> Result<InsertOutcome, RepositoryError> {
+        let existing = self
+            .titled(&recipe.title)
+            .find(|id| self.entries[*id].semantically_equals(recipe))
+            .copied();
+        match existing {
+            Some(id) => Ok(InsertOutcome::Duplicate(id)),
+            None => self.add(recipe).map(InsertOutcome::Inserted),
+        }
+    }
+
+    /// Fails unless `additional` new recipes fit into the repository.
+    fn reserve(&self, additional: usize) -> Result<(), RepositoryError> {
+        match self.capacity {
+            Some(capacity) if self.entries.len() + additional > capacity => {
+                Err(RepositoryError::CapacityExceeded { capacity })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Adds a new recipe and its title to the index.
+    fn store(&mut self, id: Uuid, stored: Stored<Recipe>) {
+        self.journal.push((id, None));
+        self.index(id, &stored.title);
+        self.entries.insert(id, stored);
+    }
+
+    /// Replaces the recipe with the id or creates it.
+    fn put(&mut self, id: &Uuid, recipe: &Recipe) -> UpdateResult {
+        let now = Utc::now();
+        match self.entries.get_mut(id) {
+            Some(stored) => {
+                self.journal.push((*id, Some(stored.clone())));
+                let previous = stored.title.clone();
+                stored.replace(recipe.clone(), now);
+                self.unindex(id, &previous);
+                self.index(*id, &recipe.title);
+                self.emit(RepositoryEvent::Updated(*id));
+                UpdateResult::Changed
+            }
+            None => {
+                self.store(*id, Stored::new(recipe.clone(), now));
+                self.emit(RepositoryEvent::Inserted(*id));
+                UpdateResult::Created
+            }
+        }
+    }
+
+    /// Removes the recipe with the id if it exists.
+    fn delete(&mut self, id: &Uuid) -> RemoveResult {
+        match self.entries.remove(id) {
+            Some(stored) => {
+                self.unindex(id, &stored.title);
+                self.journal.push((*id, Some(stored)));
+                self.emit(RepositoryEvent::Removed(*id));
+                RemoveResult::Removed
+            }
+            None => RemoveResult::NotFound,
+        }
+    }
+
+    fn index(&mut self, id: Uuid, title: &str) {
+        let ids = self.titles.entry(title.to_string()).or_default();
+        if let Err(position) = ids.binary_search(&id) {
+            ids.insert(position, id);
+        }
+        self.folded
+            .entry(folded(title))
+            .or_default()
+            .insert(title.to_string());
+    }
+
+    fn unindex(&mut self, id: &Uuid, title: &str) {
+        let Some(ids) = self.titles.get_mut(title) else {
+            return;
+        };
+        ids.retain(|other| other != id);
+        if !ids.is_empty() {
+            return;
+        }
+
+        self.titles.remove(title);
+        let key = folded(title);
+        if let Some(titles) = self.folded.get_mut(&key) {
+            titles.remove(title);
+            if titles.is_empty() {
+                self.folded.remove(&key);
+            }
+        }
+    }
+
+    /// Returns the ids of the recipes whose titles start with
+    /// `prefix`, grouped by title in the order of the index.
+    ///
+    /// Unless the search is case sensitive, the groups are those of
+    /// the titles whose [folded] form starts with `prefix`, which may
+    /// be more than actually match.
+    fn titles_starting_with(&self, prefix: &str, case_sensitive: bool) -> Vec<&Vec<Uuid>> {
+        if case_sensitive {
+            return self
+                .titles
+                .range(prefix.to_string()..)
+                .take_while(|(title, _)| title.starts_with(prefix))
+                .map(|(_, ids)| ids)
+                .collect();
+        }
+
+        let prefix = fold_case(prefix);
+        let mut titles: Vec<&String> = self
+            .folded
+            .range(prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .flat_map(|(_, titles)| titles)
+            .collect();
+        titles.sort();
+        titles
+            .into_iter()
+            .map(|title| &self.titles[title])
+            .collect()
+    }
+
+    /// Returns the ids of the recipes whose titles are equal to
+    /// `title` in their [folded] form.
+    fn titled(&self, title: &str) -> impl Iterator<Item = &Uuid> + '_ {
+        self.folded
+            .get(&folded(title))
+            .into_iter()
+            .flatten()
+            .flat_map(|title| &self.titles[title])
+    }
+
+    /// Sends the event to the current receivers once the change is
+    /// committed.
+    fn emit(&mut self, event: RepositoryEvent) {
+        self.pending.push(event);
+    }
+
+    pub fn list_ids(&self, range: &Range) -> Vec<Uuid> {
+        let keys: &Vec<Uuid> = &self.entries.keys().cloned().collect();
+
+        range.index(keys).into()
+    }
+
+    /// Returns the number of recipes selected by the filter and the
+    /// summaries of those on the page.
+    ///
+    /// Sorting by title walks the title index, so only the summaries
+    /// on the page are created. A prefix search of the titles visits
+    /// only the titles starting with the prefix.
+    fn page(&self, page: &Page, filter: &Filter) -> (usize, Vec<Summary>) {
+        let groups: Vec<&Vec<Uuid>> = match filter.sort {
+            SortBy::UpdatedDesc => {
+                let summaries = self.summaries(filter);
+                return (summaries.len(), page.index(&summaries).into());
+            }
+            _ if Self::is_title_prefix(filter) => {
+                self.titles_starting_with(&filter.search.text, filter.search.case_sensitive)
+            }
+            _ => self.titles.values().collect(),
+        };
+
+        let ordered: Box<dyn Iterator<Item = &Vec<Uuid>>> = match filter.sort {
+            SortBy::TitleDesc => Box::new(groups.into_iter().rev()),
+            _ => Box::new(groups.into_iter()),
+        };
+
+        let selected: Vec<(&Uuid, &Stored<Recipe>)> = ordered
+            .flatten()
+            .filter_map(|id| self.entries.get_key_value(id))
+            .filter(|(_, stored)| filter.matches(stored))
+            .collect();
+
+        let content = page.index(&selected).iter().map(|&entry| entry.into());
+        (selected.len(), content.collect())
+    }
+
+    /// Returns true if the filter searches for titles starting with
+    /// a text which can be looked up in the title index.
+    ///
+    /// Ignoring case, the index knows the titles only without
+    /// surrounding whitespace, so a text with surrounding whitespace
+    /// cannot be looked up.
+    fn is_title_prefix(filter: &Filter) -> bool {
+        let search = &filter.search;
+        search.mode == SearchMode::Prefix
+            && search.fields == SearchFields::Title
+            && (search.case_sensitive || search.text.trim() == search.text)
+    }
+
+    /// Returns the sorted summaries of all recipes selected by the
+    /// filter.
+    fn summaries(&self, filter: &Filter) -> Vec<Summary> {
+        let mut summaries: Vec<Summary> = self
+            .entries
+            .iter()
+            .filter(|(_, recipe)| filter.matches(recipe))
+            .map(|entity| entity.into())
+            .collect();
+
+        summaries.sort_by(|a, b| filter.sort.compare(a, b));
+        summaries
+    }
+}
+
+async fn read_snapshot(path: &Path) -> io::Result<HashMap<Uuid, Stored<Recipe>>> {
+    let json = fs::read(path).await?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// Writes the recipes to a temporary file next to the snapshot and
+/// renames it, which replaces the snapshot in one step.
+async fn write_snapshot(path: &Path, entries: &HashMap<Uuid, Stored<Recipe>>) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = PathBuf::from(temporary);
+
+    // Sorted by id, so unchanged recipes keep their place in the file.
+    let sorted: BTreeMap<&Uuid, &Stored<Recipe>> = entries.iter().collect();
+    let json = serde_json::to_vec(&sorted)?;
+    let mut file = File::create(&temporary).await?;
+    file.write_all(&json).await?;
+    file.sync_all().await?;
+
+    fs::rename(&temporary, path).await
+}
+
+#[async_trait]
+impl super::Repository for Ephemeral {
+    /// Fails with [RepositoryError::DuplicateId] if the generator
+    /// returns the id of a stored recipe.
+    async fn insert(&mut self, r: &Recipe) -> Result<Uuid, RepositoryError> {
+        let result = self.add(r);
+        self.commit(result).await
+    }
+
+    /// Adds all recipes or none. If they do not fit into the capacity
+    /// or the snapshot cannot be written, no recipe is added. Writes
+    /// the snapshot once after all recipes are added.
+    async fn insert_all(
+        &mut self,
+        recipes: &[Recipe],
+        skip_duplicates: bool,
+    ) -> Result<Vec<InsertOutcome>, RepositoryError> {
+        let outcomes = recipes
+            .iter()
+            .map(|recipe| {
+                if skip_duplicates {
+                    self.add_unique(recipe)
+                } else {
+                    self.add(recipe).map(InsertOutcome::Inserted)
+                }
+            })
+            .collect();
+        self.commit(outcomes).await
+    }
+
+    async fn insert_with_id(&mut self, id: &Uuid, r: &Recipe) -> Result<(), RepositoryError> {
+        if self.entries.contains_key(id) {
+            return Err(RepositoryError::DuplicateId(*id));
+        }
+        self.reserve(1)?;
+
+        self.store(*id, Stored::new(r.clone(), Utc::now()));
+        self.emit(RepositoryEvent::Inserted(*id));
+        self.commit(Ok(())).await
+    }
+
+    async fn insert_unique(&mut self, recipe: &Recipe) -> Result<InsertOutcome, RepositoryError> {
+        let outcome = self.add_unique(recipe);
+        self.commit(outcome).await
+    }
+
+    async fn list(&self, page: &Page, filter: &Filter) -> Result<TableOfContents, RepositoryError> {
+        let (filtered, content) = self.page(page, filter);
+
+        Ok(TableOfContents {
+            total: self.entries.len(),
+            filtered,
+            content,
+        })
+    }
+
+    async fn get(&self, id: &Uuid) -> Result<Option<Stored<Recipe>>, RepositoryError> {
+        Ok(self.entries.get(id).cloned())
+    }
+
+    async fn exists(&self, id: &Uuid) -> Result<bool, RepositoryError> {
+        Ok(self.entries.contains_key(id))
+    }
+
+    async fn count(&self) -> Result<u64, RepositoryError> {
+        Ok(self.entries.len() as u64)
+    }
+
+    async fn find_by_title(&self, title: &str) -> Result<Vec<(Uuid, Recipe)>, RepositoryError> {
+        let mut found: Vec<(Uuid, Recipe)> = self
+            .titled(title)
+            .filter_map(|id| self.entries.get_key_value(id))
+            .filter(|(_, stored)| fold_case(&stored.title) == fold_case(title))
+            .map(|(id, stored)| (*id, stored.value().clone()))
+            .collect();
+
+        found.sort_by_key(|(id, _)| *id);
+        Ok(found)
+    }
+
+    async fn for_each(
+        &self,
+        f: &mut (dyn for<'r> FnMut(&'r Uuid, &'r Recipe) + Send),
+    ) -> Result<(), RepositoryError> {
+        for (id, stored) in &self.entries {
+            f(id, stored.value());
+        }
+        Ok(())
+    }
+
+    /// Fails without importing any recipe if the new ones do not fit
+    /// into a bounded repository.
+    async fn import_all(&mut self, entries: &[(Uuid, Recipe)]) -> Result<usize, RepositoryError> {
+        let new: HashSet<&Uuid> = entries
+            .iter()
+            .map(|(id, _)| id)
+            .filter(|id| !self.entries.contains_key(id))
+            .collect();
+        self.reserve(new.len())?;
+
+        for (id, recipe) in entries {
+            self.put(id, recipe);
+        }
+        self.commit(Ok(entries.len())).await
+    }
+
+    async fn remove(&mut self, id: &Uuid) -> Result<RemoveResult, RepositoryError> {
+        let result = self.delete(id);
+        self.commit(Ok(result)).await
+    }
+
+    async fn remove_all(&mut self, ids: &[Uuid]) -> Result<u64, RepositoryError> {
+        let removed = ids
+            .iter()
+            .filter(|id| self.delete(id) == RemoveResult::Removed)
+            .count();
+        self.commit(Ok(removed as u64)).await
+    }
+
+    async fn clear(&mut self) -> Result<u64, RepositoryError> {
+        self.titles.clear();
+        let removed: Vec<(Uuid, Stored<Recipe>)> = self.entries.drain().collect();
+        let count = removed.len() as u64;
+        for (id, stored) in removed {
+            self.journal.push((id, Some(stored)));
+            self.emit(RepositoryEvent::Removed(id));
+        }
+        self.commit(Ok(count)).await
+    }
+
+    async fn update(
+        &mut self,
+        id: &Uuid,
+        recipe: &Recipe,
+    ) -> Result<UpdateResult, RepositoryError> {
+        if !self.entries.contains_key(id) {
+            self.reserve(1)?;
+        }
+        let result = self.put(id, recipe);
+        self.commit(Ok(result)).await
+    }
+
+    async fn update_if_match(
+        &mut self,
+        id: &Uuid,
+        recipe: &Recipe,
+        expected_revision: u64,
+    ) -> Result<UpdateResult, RepositoryError> {
+        let current_revision = self.entries.get(id).map_or(0, Stored::revision);
+        if current_revision != expected_revision {
+            return Err(RepositoryError::Conflict { current_revision });
+        }
+
+        self.update(id, recipe).await
+    }
+
+    fn watch(&self) -> broadcast::Receiver<RepositoryEvent> {
+        self.events.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, ops::Bound, path::PathBuf};
+
+    use super::Ephemeral;
+    use crate::repository::{
+        Filter, InsertOutcome, Page, Range, RemoveResult, Repository, RepositoryError,
+        RepositoryEvent, SearchFields, SearchMode, SearchQuery, SequentialIds, SortBy,
+        UpdateResult,
+    };
+    use crate::testing::sample_recipes;
+    use crate::Difficulty;
+    use crate::{Category, Ingredient, Recipe};
+    use spucky::spec;
+    use tokio::sync::broadcast;
+    use uuid::Uuid;
+
+    lazy_static! {
+        static ref TESTDATA: Vec<Recipe> = vec![Recipe {
+            title: "Lasagne".to_string(),
+            preparation: "Du weist schon wie".to_string(),
+            servings: 2,
+            ingredients: vec![],
+            ..Default::default()
+        }];
+    }
+
+    #[tokio::test]
+    async fn test_insert() -> Result<(), Box<dyn std::error::Error>> {
+        let mut repo = Ephemeral::new();
+
+        let recipe = Recipe {
+            title: "Lasagne".to_string(),
+            preparation: "Du weist schon wie".into(),
+            servings: 2,
+            ingredients: vec![],
+            ..Default::default()
+        };
+
+        let id = repo.insert(&recipe).await?;
+
+        let copy = repo.get(&id).await?.unwrap();
+
+        assert_eq!(&recipe, copy.value());
+        assert_eq!(copy.created_at(), copy.updated_at());
+
+        Ok(())
+    }
+
+    spec! {
+        async insert_with_id {
+            type Output = Result<(), RepositoryError>;
+
+            case new_id {
+                let existing = false;
+            }
+
+            case duplicate_id {
+                let existing = true;
+            }
+
+            let mut repository = Ephemeral::new();
+            let id = uuid::Uuid::new_v4();
+            if existing {
+                repository.insert_with_id(&id, &TESTDATA[0]).await?;
+            }
+
+            let recipe = Recipe { servings: 4, ..TESTDATA[0].clone() };
+            match repository.insert_with_id(&id, &recipe).await {
+                Ok(()) => assert!(!existing),
+                Err(RepositoryError::DuplicateId(duplicate)) => assert!(existing && duplicate == id),
+                Err(error) => panic!("unexpected error {}", error),
+            }
+
+            let stored = repository.get(&id).await?.unwrap();
+            assert_eq!(existing, stored.value() != &recipe);
+            Ok(())
+        }
+    }
+
+    spec! {
+        async insert_unique {
+            type Output = Result<(), RepositoryError>;
+
+            case new_title {
+                let title = "Käsekuchen";
+                let preparation = "Du weist schon wie";
+                let duplicate = false;
+            }
+
+            case exact_copy {
+                let title = "Lasagne";
+                let preparation = "Du weist schon wie";
+                let duplicate = true;
+            }
+
+            case same_title_other_content {
+                let title = "Lasagne";
+                let preparation = "Schichten und backen.";
+                let duplicate = false;
+            }
+
+            case ignores_case {
+                let title = "LASAGNE";
+                let preparation = "Du weist schon wie";
+                let duplicate = true;
+            }
+
+            case longer_title {
+                let title = "Lasagne al forno";
+                let preparation = "Du weist schon wie";
+                let duplicate = false;
+            }
+
+            let mut repository = Ephemeral::new();
+            let existing = repository.insert(&TESTDATA[0]).await?;
+
+            let recipe = Recipe {
+                title: title.into(),
+                preparation: preparation.into(),
+                ..TESTDATA[0].clone()
+            };
+            match repository.insert_unique(&recipe).await? {
+                InsertOutcome::Inserted(id) => {
+                    assert!(!duplicate);
+                    assert_eq!(&recipe, repository.get(&id).await?.unwrap().value());
+                }
+                InsertOutcome::Duplicate(id) => {
+                    assert!(duplicate);
+                    assert_eq!(existing, id);
+                    assert_eq!(&TESTDATA[0], repository.get(&id).await?.unwrap().value());
+                }
+            }
+
+            assert_eq!(if duplicate { 1 } else { 2 }, repository.count().await?);
+            Ok(())
+        }
+    }
+
+    spec! {
+        async insert_unique_compares_content {
+            type Output = Result<(), RepositoryError>;
+
+            case reordered_ingredients {
+                let title = "Lasagne";
+                let ingredients = ["500 g Nudeln", "1 kg Tomaten"];
+                let duplicate = true;
+            }
+
+            case same_title_other_ingredients {
+                let title = "Lasagne";
+                let ingredients = ["500 g Nudeln", "2 kg Tomaten"];
+                let duplicate = false;
+            }
+
+            case other_title_same_ingredients {
+                let title = "Pasta al forno";
+                let ingredients = ["1 kg Tomaten", "500 g Nudeln"];
+                let duplicate = false;
+            }
+
+            case title_case_and_whitespace {
+                let title = " lasagne ";
+                let ingredients = ["1 kg Tomaten", "500 g Nudeln"];
+                let duplicate = true;
+            }
+
+            let ingredient = |line: &str| line.parse::<Ingredient>().unwrap();
+            let stored = Recipe {
+                ingredients: vec![ingredient("1 kg Tomaten"), ingredient("500 g Nudeln")],
+                ..TESTDATA[0].clone()
+            };
+            let mut repository = Ephemeral::new();
+            let existing = repository.insert(&stored).await?;
+
+            let recipe = Recipe {
+                title: title.into(),
+                ingredients: ingredients.map(ingredient).to_vec(),
+                ..stored
+            };
+            let outcome = repository.insert_unique(&recipe).await?;
+            assert_eq!(duplicate, outcome == InsertOutcome::Duplicate(existing));
+            assert_eq!(if duplicate { 1 } else { 2 }, repository.count().await?);
+            Ok(())
+        }
+    }
+
+    spec! {
+        async insert_all_skip_duplicates {
+            type Output = Result<(), RepositoryError>;
+
+            case keep_duplicates {
+                let skip_duplicates = false;
+                let want = (5, [true, true, true, true]);
+            }
+
+            case skip_duplicates {
+                let skip_duplicates = true;
+                let want = (3, [false, true, false, true]);
+            }
+
+            let mut repository = Ephemeral::new();
+            let existing = repository.insert(&TESTDATA[0]).await?;
+            let recipes = [
+                TESTDATA[0].clone(),
+                Recipe { title: "Käsekuchen".into(), ..TESTDATA[0].clone() },
+                Recipe { title: "käsekuchen".into(), ..TESTDATA[0].clone() },
+                Recipe { preparation: "Schichten und backen.".into(), ..TESTDATA[0].clone() },
+            ];
+
+            let outcomes = repository.insert_all(&recipes, skip_duplicates).await?;
+
+            let (count, inserted) = want;
+            assert_eq!(count, repository.count().await?);
+            for ((outcome, recipe), inserted) in outcomes.iter().zip(&recipes).zip(inserted) {
+                match outcome {
+                    InsertOutcome::Inserted(id) => {
+                        assert!(inserted);
+                        assert_eq!(recipe, repository.get(id).await?.unwrap().value());
+                    }
+                    InsertOutcome::Duplicate(_) => assert!(!inserted),
+                }
+            }
+            if skip_duplicates {
+                assert_eq!(InsertOutcome::Duplicate(existing), outcomes[0]);
+                assert_eq!(InsertOutcome::Duplicate(outcomes[1].id()), outcomes[2]);
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn update_preserves_created_at() -> Result<(), Box<dyn std::error::Error>> {
+        let mut repo = Ephemeral::new();
+        let id = repo.insert(&TESTDATA[0]).await?;
+        let inserted = repo.get(&id).await?.unwrap();
+
+        let recipe = Recipe {
+            servings: 4,
+            ..TESTDATA[0].clone()
+        };
+        repo.update(&id, &recipe).await?;
+
+        let updated = repo.get(&id).await?.unwrap();
+        assert_eq!(&recipe, updated.value());
+        assert_eq!(inserted.created_at(), updated.created_at());
+        assert!(updated.updated_at() >= inserted.updated_at());
+
+        Ok(())
+    }
+
+    spec! {
+        async update_recipe {
+            type Output = Result<(), RepositoryError>;
+
+            case missing {
+                let before = 0;
+                let want = UpdateResult::Created;
+            }
+
+            case existing {
+                let before = 1;
+                let want = UpdateResult::Changed;
+            }
+
+            case changed_twice {
+                let before = 2;
+                let want = UpdateResult::Changed;
+            }
+
+            let mut repository = Ephemeral::new();
+            let id = uuid::Uuid::new_v4();
+            for servings in 0..before {
+                repository.update(&id, &Recipe { servings, ..TESTDATA[0].clone() }).await?;
+            }
+
+            let recipe = Recipe { servings: 8, ..TESTDATA[0].clone() };
+            assert_eq!(want, repository.update(&id, &recipe).await?);
+            assert_eq!(&recipe, repository.get(&id).await?.unwrap().value());
+            assert_eq!(1, repository.count().await?);
+            Ok(())
+        }
+    }
+
+    spec! {
+        async remove_all {
+            type Output = Result<(), RepositoryError>;
+
+            case all {
+                let count = 100;
+                let unknown = 0;
+                let want = 100;
+            }
+
+            case some {
+                let count = 10;
+                let unknown = 0;
+                let want = 10;
+            }
+
+            case none {
+                let count = 0;
+                let unknown = 0;
+                let want = 0;
+            }
+
+            case unknown_ids {
+                let count = 5;
+                let unknown = 3;
+                let want = 5;
+            }
+
+            let mut repository = Ephemeral::new();
+            fill_with_testdata(&mut repository).await;
+
+            let mut ids = repository.list_ids(&Range::Closed { start: 0, end: count });
+            ids.truncate(count);
+            ids.extend((0..unknown).map(|_| uuid::Uuid::new_v4()));
+
+            let removed = repository.remove_all(&ids).await?;
+            assert_eq!(want, removed);
+            assert_eq!(100 - want as usize, repository.list_ids(&Range::Unbounded).len());
+            Ok(())
+        }
+    }
+
+    spec! {
+        async clear {
+            type Output = Result<(), RepositoryError>;
+
+            case empty {
+                let filled = false;
+                let want = 0;
+            }
+
+            case filled {
+                let filled = true;
+                let want = 100;
+            }
+
+            let mut repository = Ephemeral::new();
+            if filled {
+                fill_with_testdata(&mut repository).await;
+            }
+
+            assert_eq!(want, repository.clear().await?);
+            assert_eq!(0, repository.count().await?);
+            assert_eq!(0, repository.clear().await?);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn update_creates_missing_recipe() -> Result<(), Box<dyn std::error::Error>> {
+        let mut repo = Ephemeral::new();
+        let id = uuid::Uuid::new_v4();
+        repo.update(&id, &TESTDATA[0]).await?;
+
+        let created = repo.get(&id).await?.unwrap();
+        assert_eq!(created.created_at(), created.updated_at());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn revision_counts_changes() -> Result<(), RepositoryError> {
+        let mut repo = Ephemeral::new();
+        let id = repo.insert(&TESTDATA[0]).await?;
+        assert_eq!(1, repo.get(&id).await?.unwrap().revision());
+
+        repo.update(&id, &TESTDATA[0]).await?;
+        assert_eq!(2, repo.get(&id).await?.unwrap().revision());
+
+        let toc = repo.list(&Page::all(), &Filter::new()).await?;
+        assert_eq!(2, toc.content()[0].revision());
+
+        Ok(())
+    }
+
+    spec! {
+        async update_if_match {
+            type Output = Result<(), RepositoryError>;
+
+            case matching_revision {
+                let expected = 1;
+                let want = Ok(UpdateResult::Changed);
+                let revision = 2;
+            }
+
+            case stale_revision {
+                let expected = 0;
+                let want = Err(1);
+                let revision = 1;
+            }
+
+            case future_revision {
+                let expected = 2;
+                let want = Err(1);
+                let revision = 1;
+            }
+
+            let mut repo = Ephemeral::new();
+            let id = repo.insert(&TESTDATA[0]).await?;
+            let recipe = Recipe { servings: 4, ..TESTDATA[0].clone() };
+
+            let got = match repo.update_if_match(&id, &recipe, expected).await {
+                Ok(result) => Ok(result),
+                Err(RepositoryError::Conflict { current_revision }) => Err(current_revision),
+                Err(error) => return Err(error),
+            };
+            assert_eq!(want, got);
+            assert_eq!(revision, repo.get(&id).await?.unwrap().revision());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn update_if_match_missing_recipe() -> Result<(), RepositoryError> {
+        let mut repo = Ephemeral::new();
+        let id = uuid::Uuid::new_v4();
+
+        assert!(matches!(
+            repo.update_if_match(&id, &TESTDATA[0], 1).await,
+            Err(RepositoryError::Conflict {
+                current_revision: 0
+            })
+        ));
+        assert_eq!(
+            UpdateResult::Created,
+            repo.update_if_match(&id, &TESTDATA[0], 0).await?
+        );
+        assert_eq!(1, repo.get(&id).await?.unwrap().revision());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_if_match_detects_lost_update() -> Result<(), RepositoryError> {
+        let mut repo = Ephemeral::new();
+        let id = repo.insert(&TESTDATA[0]).await?;
+
+        // Both clients read the same revision before either writes.
+        let first = repo.get(&id).await?.unwrap().revision();
+        let second = repo.get(&id).await?.unwrap().revision();
+
+        let recipe = Recipe {
+            servings: 4,
+            ..TESTDATA[0].clone()
+        };
+        assert_eq!(
+            UpdateResult::Changed,
+            repo.update_if_match(&id, &recipe, first).await?
+        );
+
+        let recipe = Recipe {
+            servings: 8,
+            ..TESTDATA[0].clone()
+        };
+        assert!(matches!(
+            repo.update_if_match(&id, &recipe, second).await,
+            Err(RepositoryError::Conflict {
+                current_revision: 2
+            })
+        ));
+        assert_eq!(4, repo.get(&id).await?.unwrap().servings);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn update_if_match_concurrent_clients() -> Result<(), RepositoryError> {
+        use std::sync::Arc;
+        use tokio::sync::{Barrier, RwLock};
+
+        const CLIENTS: usize = 8;
+        let repo = Arc::new(RwLock::new(Ephemeral::new()));
+        let id = repo.write().await.insert(&TESTDATA[0]).await?;
+        let barrier = Arc::new(Barrier::new(CLIENTS));
+
+        let clients: Vec<_> = (0..CLIENTS)
+            .map(|servings| {
+                let repo = Arc::clone(&repo);
+                let barrier = Arc::clone(&barrier);
+                tokio::spawn(async move {
+                    let stored = repo.read().await.get(&id).await.unwrap();
+                    let revision = stored.unwrap().revision();
+                    barrier.wait().await;
+
+                    let recipe = Recipe {
+                        servings: servings as u8,
+                        ..TESTDATA[0].clone()
+                    };
+                    let result = repo
+                        .write()
+                        .await
+                        .update_if_match(&id, &recipe, revision)
+                        .await;
+                    match result {
+                        Ok(UpdateResult::Changed) => true,
+                        Err(RepositoryError::Conflict { current_revision }) => {
+                            assert!(current_revision > revision);
+                            false
+                        }
+                        other => panic!("unexpected result {:?}", other),
+                    }
+                })
+            })
+            .collect();
+
+        let mut changed = 0;
+        for client in clients {
+            if client.await.unwrap() {
+                changed += 1;
+            }
+        }
+
+        // Every client read revision 1, so only one of them may win.
+        assert_eq!(1, changed);
+        assert_eq!(2, repo.read().await.get(&id).await?.unwrap().revision());
+
+        Ok(())
+    }
+
+    spec! {
+        async list_filled_repository {
+
+            case case1 {
+                let range = Range::Unbounded;
+                let want = 100;
+            }
+
+            case case2  {
+                let range = Range::LeftClosed { start: 0 };
+                let want = 100;
+            }
+
+            case case3 {
+                let range = Range::RightClosed { end: 99 };
+                let want = 100;
+            }
+
+            case case4 {
+                let range = Range::Closed { start: 0, end: 99 };
+                let want=  100;
+            }
+
+            case case5 {
+                let range = Range::Closed {start: 2, end: 1};
+                let want = 0;
+            }
+
+            case case6 {
+                let range = Range::Closed {start: 99, end: 100};
+                let want = 1;
+            }
+
+            case case7 {
+                let range = Range::Closed {start: 99, end: 99};
+                let want = 1;
+            }
+
+            case case8 {
+                let range = Range::Closed {start: 0, end: 0};
+                let want = 1;
+            }
+
+            case start_behind_end {
+                let range = Range::Closed {start: 500, end: 600};
+                let want = 0;
+            }
+
+            case left_closed_behind_end {
+                let range = Range::LeftClosed {start: 100};
+                let want = 0;
+            }
+
+            let mut repository = Ephemeral::new();
+            fill_with_testdata(&mut repository).await;
+
+            match repository.list(&range.into(), &Filter::new()).await {
+                Ok(toc) => assert_eq!(toc.content.len(), want),
+                Err(_) => panic!("unexpected error"),
+            }
+        }
+
+    }
+
+    spec! {
+        async list_empty_repository {
+            case case1 {
+                let range = Range::Closed {start: 0, end: 0};
+                let want = 0;
+            }
+
+            let repository = Ephemeral::new();
+            match repository.list(&range.into(), &Filter::new()).await {
+                Ok(toc) => assert_eq!(toc.content.len(), want),
+                Err(_) => panic!("unexpected error",)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn list_some_keys() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::new();
+        fill_with_testdata(&mut repository).await;
+
+        struct Testcase {
+            range: Range,
+            want: usize,
+        }
+
+        let td3: Vec<Testcase> = vec![
+            Testcase {
+                range: Range::Unbounded,
+                want: 100,
+            },
+            Testcase {
+                range: Range::LeftClosed { start: 0 },
+                want: 100,
+            },
+            Testcase {
+                range: Range::RightClosed { end: 99 },
+                want: 100,
+            },
+            Testcase {
+                range: Range::Closed { start: 0, end: 99 },
+                want: 100,
+            },
+        ];
+
+        for testcase in &td3 {
+            let keys = repository.list_ids(&testcase.range);
+            assert_eq!(keys.len(), testcase.want)
+        }
+        Ok(())
+    }
+
+    async fn fill_with_testdata(repository: &mut Ephemeral) {
+        for recipe in sample_recipes(100) {
+            _ = repository.insert(&recipe).await;
+        }
+    }
+
+    spec! {
+        async list_from_bounds_filled_repository {
+            case unbounded {
+                let range = (Bound::Unbounded, Bound::Unbounded);
+                let want = 100;
+            }
+
+            case start_behind_end {
+                let range = (Bound::Included(500), Bound::Included(600));
+                let want = 0;
+            }
+
+            case start_at_len {
+                let range = (Bound::Included(100), Bound::Unbounded);
+                let want = 0;
+            }
+
+            case inverted {
+                let range = (Bound::Included(2), Bound::Included(1));
+                let want = 0;
+            }
+
+            case end_behind_len {
+                let range = (Bound::Included(90), Bound::Included(200));
+                let want = 10;
+            }
+
+            case excluded_end_zero {
+                let range = (Bound::Unbounded, Bound::Excluded(0));
+                let want = 0;
+            }
+
+            case excluded_start_zero {
+                let range = (Bound::Excluded(0), Bound::Unbounded);
+                let want = 99;
+            }
+
+            case excluded_zero_both {
+                let range = (Bound::Excluded(0), Bound::Excluded(0));
+                let want = 0;
+            }
+
+            case included_start_excluded_end_zero {
+                let range = (Bound::Included(0), Bound::Excluded(0));
+                let want = 0;
+            }
+
+            case excluded_without_inner_index {
+                let range = (Bound::Excluded(5), Bound::Excluded(6));
+                let want = 0;
+            }
+
+            case inverted_excluded {
+                let range = (Bound::Excluded(10), Bound::Included(3));
+                let want = 0;
+            }
+
+            case excluded_end_behind_len {
+                let range = (Bound::Excluded(97), Bound::Excluded(500));
+                let want = 2;
+            }
+
+            case excluded_start_at_max {
+                let range = (Bound::Excluded(u64::MAX), Bound::Unbounded);
+                let want = 0;
+            }
+
+            case included_at_max {
+                let range = (Bound::Included(u64::MAX), Bound::Included(u64::MAX));
+                let want = 0;
+            }
+
+            let mut repository = Ephemeral::new();
+            fill_with_testdata(&mut repository).await;
+
+            let toc = repository.list(&Page::from_bounds(&range), &Filter::new()).await.unwrap();
+            assert_eq!(toc.content.len(), want);
+        }
+    }
+
+    #[tokio::test]
+    async fn title_index_after_many_changes() -> Result<(), RepositoryError> {
+        // xorshift, good enough to scatter the changes and repeatable.
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        let mut title = || {
+            let prefix = ["Apfel", "apfel", "Birne", "Brot", "Zwiebel"][(next() % 5) as usize];
+            format!("{}{}", prefix, next() % 300)
+        };
+
+        let mut repository = Ephemeral::new();
+        let mut ids = Vec::new();
+        for _ in 0..10_000 {
+            let recipe = Recipe {
+                title: title(),
+                ..TESTDATA[0].clone()
+            };
+            ids.push(repository.insert(&recipe).await?);
+        }
+
+        for (n, id) in ids.iter().enumerate() {
+            match n % 5 {
+                0 => {
+                    repository.remove(id).await?;
+                }
+                1 | 2 => {
+                    let recipe = Recipe {
+                        title: title(),
+                        ..TESTDATA[0].clone()
+                    };
+                    repository.update(id, &recipe).await?;
+                }
+                _ => (),
+            }
+        }
+        assert_eq!(
+            repository.entries.len(),
+            repository.titles.values().map(Vec::len).sum::<usize>()
+        );
+
+        let filters = [
+            Filter::new(),
+            Filter::new().sort_by(SortBy::TitleDesc),
+            Filter::new().search("apfel1"),
+            Filter::new().search("APFEL1").sort_by(SortBy::TitleDesc),
+            Filter::new().search(SearchQuery::case_sensitive("Apfel1", SearchMode::Prefix)),
+            Filter::new()
+                .search(SearchQuery::case_sensitive("Brot", SearchMode::Prefix))
+                .sort_by(SortBy::TitleDesc),
+        ];
+        let pages = [
+            Page::all(),
+            Page::first(10),
+            Page {
+                offset: 3_995,
+                limit: Some(10),
+            },
+            Page {
+                offset: 200,
+                limit: Some(50),
+            },
+        ];
+
+        for filter in &filters {
+            let want = repository.summaries(filter);
+            for page in &pages {
+                let toc = repository.list(page, filter).await?;
+                assert_eq!(want.len(), toc.filtered(), "{:?}", filter);
+                assert_eq!(page.index(&want), toc.content(), "{:?} {:?}", filter, page);
+            }
+        }
+
+        for title in ["apfel12", "BROT7", "Zwiebel299"] {
+            let mut want: Vec<Uuid> = repository
+                .entries
+                .iter()
+                .filter(|(_, stored)| stored.title.to_lowercase() == title.to_lowercase())
+                .map(|(id, _)| *id)
+                .collect();
+            want.sort();
+            let found: Vec<Uuid> = repository
+                .find_by_title(title)
+                .await?
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            assert_eq!(want, found, "{}", title);
+        }
+        Ok(())
+    }
+
+    /// Returns the events received so far.
+    fn received(events: &mut broadcast::Receiver<RepositoryEvent>) -> Vec<RepositoryEvent> {
+        std::iter::from_fn(|| events.try_recv().ok()).collect()
+    }
+
+    #[tokio::test]
+    async fn watch_reports_changes() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::new();
+        let before = repository.insert(&TESTDATA[0]).await?;
+        let mut events = repository.watch();
+
+        let id = repository.insert(&TESTDATA[0]).await?;
+        let known = uuid::Uuid::new_v4();
+        repository.insert_with_id(&known, &TESTDATA[0]).await?;
+        repository.update(&id, &TESTDATA[0]).await?;
+        let created = uuid::Uuid::new_v4();
+        repository.update(&created, &TESTDATA[0]).await?;
+        repository.update_if_match(&id, &TESTDATA[0], 2).await?;
+        repository.remove(&known).await?;
+        repository.remove_all(&[before, created]).await?;
+
+        assert_eq!(
+            vec![
+                RepositoryEvent::Inserted(id),
+                RepositoryEvent::Inserted(known),
+                RepositoryEvent::Updated(id),
+                RepositoryEvent::Inserted(created),
+                RepositoryEvent::Updated(id),
+                RepositoryEvent::Removed(known),
+                RepositoryEvent::Removed(before),
+                RepositoryEvent::Removed(created),
+            ],
+            received(&mut events)
+        );
+
+        assert_eq!(1, repository.clear().await?);
+        assert_eq!(vec![RepositoryEvent::Removed(id)], received(&mut events));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watch_ignores_failed_operations() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::new();
+        let id = repository.insert(&TESTDATA[0]).await?;
+        let mut events = repository.watch();
+
+        assert!(repository.insert_with_id(&id, &TESTDATA[0]).await.is_err());
+        assert_eq!(
+            InsertOutcome::Duplicate(id),
+            repository.insert_unique(&TESTDATA[0]).await?
+        );
+        assert!(repository
+            .update_if_match(&id, &TESTDATA[0], 7)
+            .await
+            .is_err());
+        let unknown = uuid::Uuid::new_v4();
+        assert_eq!(RemoveResult::NotFound, repository.remove(&unknown).await?);
+        assert_eq!(0, repository.remove_all(&[unknown]).await?);
+
+        assert_eq!(Vec::<RepositoryEvent>::new(), received(&mut events));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn watch_insert_all() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::new();
+        let mut first = repository.watch();
+        let mut second = repository.watch();
+
+        let outcomes = repository
+            .insert_all(&[TESTDATA[0].clone(), TESTDATA[0].clone()], false)
+            .await?;
+
+        let want: Vec<_> = outcomes
+            .iter()
+            .map(|outcome| RepositoryEvent::Inserted(outcome.id()))
+            .collect();
+        assert_eq!(want, received(&mut first));
+        assert_eq!(want, received(&mut second));
+        Ok(())
+    }
+
+    /// Returns the path of a snapshot file which does not exist yet.
+    fn snapshot_path() -> PathBuf {
+        env::temp_dir().join(format!("recipers-{}.json", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn persistence_survives_reopening() -> Result<(), RepositoryError> {
+        let path = snapshot_path();
+        let id = Uuid::new_v4();
+
+        let mut repository = Ephemeral::with_persistence(path.clone()).await?;
+        repository.insert_with_id(&id, &TESTDATA[0]).await?;
+        let other = repository.insert(&TESTDATA[0]).await?;
+        repository
+            .update(
+                &id,
+                &Recipe {
+                    title: "Pfannkuchen".to_string(),
+                    ..TESTDATA[0].clone()
+                },
+            )
+            .await?;
+        let want = [repository.get(&id).await?, repository.get(&other).await?];
+        drop(repository);
+
+        let repository = Ephemeral::with_persistence(path.clone()).await?;
+        assert_eq!(2, repository.count().await?);
+        assert_eq!(
+            want,
+            [repository.get(&id).await?, repository.get(&other).await?]
+        );
+        assert_eq!(vec!["Lasagne", "Pfannkuchen"], titles(&repository).await?);
+
+        drop(repository);
+        fs::remove_file(path).unwrap();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn persistence_writes_every_change() -> Result<(), RepositoryError> {
+        let path = snapshot_path();
+        let mut repository = Ephemeral::with_persistence(path.clone()).await?;
+        assert!(!path.exists());
+
+        let id = repository.insert(&TESTDATA[0]).await?;
+        assert_eq!(
+            1,
+            Ephemeral::with_persistence(path.clone())
+                .await?
+                .count()
+                .await?
+        );
+
+        repository.remove(&id).await?;
+        assert_eq!(
+            0,
+            Ephemeral::with_persistence(path.clone())
+                .await?
+                .count()
+                .await?
+        );
+
+        drop(repository);
+        fs::remove_file(path).unwrap();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn persistence_keeps_corrupt_file() -> Result<(), RepositoryError> {
+        let path = snapshot_path();
+        fs::write(&path, "{not json").unwrap();
+
+        let result = Ephemeral::with_persistence(path.clone()).await;
+        assert!(matches!(result, Err(RepositoryError::Backend(_))));
+        assert_eq!("{not json", fs::read_to_string(&path).unwrap());
+
+        // Dropping a repository does not write the snapshot, so it
+        // leaves a file alone which became corrupt after opening.
+        fs::remove_file(&path).unwrap();
+        let mut repository = Ephemeral::with_persistence(path.clone()).await?;
+        repository.insert(&TESTDATA[0]).await?;
+        fs::write(&path, "{not json").unwrap();
+        drop(repository);
+        assert_eq!("{not json", fs::read_to_string(&path).unwrap());
+
+        fs::remove_file(path).unwrap();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn persistence_failure_undoes_change() -> Result<(), RepositoryError> {
+        let directory = snapshot_path();
+        fs::create_dir(&directory).unwrap();
+        let mut repository = Ephemeral::with_persistence(directory.join("recipes.json")).await?;
+        let id = repository.insert(&TESTDATA[0]).await?;
+        let want = repository.get(&id).await?;
+        let mut events = repository.watch();
+        let other = Recipe {
+            title: "Pfannkuchen".to_string(),
+            ..TESTDATA[0].clone()
+        };
+
+        // Without the directory of the snapshot every write fails.
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert!(matches!(
+            repository.insert(&other).await,
+            Err(RepositoryError::Backend(_))
+        ));
+        assert!(matches!(
+            repository.update(&id, &other).await,
+            Err(RepositoryError::Backend(_))
+        ));
+        assert!(matches!(
+            repository.remove(&id).await,
+            Err(RepositoryError::Backend(_))
+        ));
+        assert!(matches!(
+            repository.clear().await,
+            Err(RepositoryError::Backend(_))
+        ));
+
+        assert_eq!(1, repository.count().await?);
+        assert_eq!(want, repository.get(&id).await?);
+        assert_eq!(vec!["Lasagne"], titles(&repository).await?);
+        assert_eq!(Vec::<RepositoryEvent>::new(), received(&mut events));
+        assert!(repository.flush().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn persistence_insert_all() -> Result<(), RepositoryError> {
+        let path = snapshot_path();
+        let mut repository = Ephemeral::with_persistence(path.clone()).await?;
+
+        repository.insert_all(&sample_recipes(10), false).await?;
+        assert_eq!(
+            10,
+            Ephemeral::with_persistence(path.clone())
+                .await?
+                .count()
+                .await?
+        );
+
+        drop(repository);
+        fs::remove_file(path).unwrap();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn insert_takes_ids_from_generator() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::with_id_generator(SequentialIds::new());
+
+        let first = repository.insert(&TESTDATA[0]).await?;
+        let second = repository.insert(&TESTDATA[0]).await?;
+
+        assert_eq!(Uuid::from_u128(1), first);
+        assert_eq!(Uuid::from_u128(2), second);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn insert_rejects_colliding_ids() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::with_id_generator(SequentialIds::new());
+        let taken = Uuid::from_u128(2);
+        repository.insert_with_id(&taken, &TESTDATA[0]).await?;
+
+        let first = repository.insert(&TESTDATA[0]).await?;
+        let result = repository.insert(&TESTDATA[0]).await;
+        assert!(
+            matches!(result, Err(RepositoryError::DuplicateId(id)) if id == taken),
+            "{:?}",
+            result
+        );
+
+        let result = repository.insert_with_id(&first, &TESTDATA[0]).await;
+        assert!(
+            matches!(result, Err(RepositoryError::DuplicateId(id)) if id == first),
+            "{:?}",
+            result
+        );
+        assert_eq!(2, repository.count().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn capacity_limit() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::with_capacity_limit(3);
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            ids.push(repository.insert(&TESTDATA[0]).await?);
+        }
+
+        let result = repository.insert(&TESTDATA[0]).await;
+        assert!(
+            matches!(
+                result,
+                Err(RepositoryError::CapacityExceeded { capacity: 3 })
+            ),
+            "{:?}",
+            result
+        );
+        assert_eq!(
+            UpdateResult::Changed,
+            repository.update(&ids[0], &TESTDATA[0]).await?
+        );
+        assert_eq!(3, repository.count().await?);
+
+        repository.remove(&ids[1]).await?;
+        repository.insert(&TESTDATA[0]).await?;
+        assert_eq!(3, repository.count().await?);
+
+        assert_eq!(3, repository.clear().await?);
+        repository
+            .insert_all(&[TESTDATA[0].clone(), TESTDATA[0].clone()], false)
+            .await?;
+        assert_eq!(2, repository.count().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn capacity_limit_insert_all_adds_none() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::with_capacity_limit(3);
+        let mut events = repository.watch();
+        repository.insert(&TESTDATA[0]).await?;
+        events.try_recv().unwrap();
+
+        let recipes = vec![TESTDATA[0].clone(); 3];
+        let result = repository.insert_all(&recipes, false).await;
+        assert!(
+            matches!(
+                result,
+                Err(RepositoryError::CapacityExceeded { capacity: 3 })
+            ),
+            "{:?}",
+            result
+        );
+        assert_eq!(1, repository.count().await?);
+        assert!(events.try_recv().is_err());
+
+        repository.insert_all(&recipes[1..], false).await?;
+        assert_eq!(3, repository.count().await?);
+        Ok(())
+    }
+
+    /// Returns a repository which holds as many recipes as it may.
+    async fn full_repository() -> Ephemeral {
+        let mut repository = Ephemeral::with_capacity_limit(1);
+        repository.insert(&TESTDATA[0]).await.unwrap();
+        repository
+    }
+
+    spec! {
+        async capacity_limit_rejects_new_recipes {
+            case insert {
+                let mut repository = full_repository().await;
+                let result = repository.insert(&TESTDATA[0]).await.map(|_| ());
+            }
+
+            case insert_with_id {
+                let mut repository = full_repository().await;
+                let result = repository.insert_with_id(&Uuid::new_v4(), &TESTDATA[0]).await;
+            }
+
+            case insert_all {
+                let mut repository = full_repository().await;
+                let result = repository
+                    .insert_all(&[TESTDATA[0].clone()], false)
+                    .await
+                    .map(|_| ());
+            }
+
+            case update_new_id {
+                let mut repository = full_repository().await;
+                let result = repository.update(&Uuid::new_v4(), &TESTDATA[0]).await.map(|_| ());
+            }
+
+            case import_all {
+                let mut repository = full_repository().await;
+                let existing = repository.list(&Page::all(), &Filter::new()).await.unwrap().content()[0].id;
+                let entries = [(existing, TESTDATA[0].clone()), (Uuid::new_v4(), TESTDATA[0].clone())];
+                let result = repository.import_all(&entries).await.map(|_| ());
+            }
+
+            assert!(
+                matches!(result, Err(RepositoryError::CapacityExceeded { capacity: 1 })),
+                "{:?}",
+                result
+            );
+            assert_eq!(1, repository.count().await.unwrap());
+        }
+    }
+
+    async fn titles(repository: &Ephemeral) -> Result<Vec<String>, RepositoryError> {
+        let toc = repository.list(&Page::all(), &Filter::new()).await?;
+        Ok(toc.content().iter().map(|s| s.title.clone()).collect())
+    }
+
+    #[tokio::test]
+    #[allow(deprecated)]
+    async fn list2_agrees_with_page() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::new();
+        fill_with_testdata(&mut repository).await;
+        let bounds = [
+            Bound::Unbounded,
+            Bound::Included(0),
+            Bound::Excluded(0),
+            Bound::Included(42),
+            Bound::Excluded(99),
+            Bound::Included(100),
+        ];
+
+        for start in bounds {
+            for end in bounds {
+                let range = (start, end);
+                let page = Page::from_bounds(&range);
+                let want = repository.list(&page, &Filter::new()).await?;
+                let got = repository.list2(&range, &Filter::new()).await?;
+                assert_eq!(want.content(), got.content(), "{:?}", range);
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_from_bounds_empty_repository() -> Result<(), RepositoryError> {
+        let repository = Ephemeral::new();
+        let bounds = [
+            Bound::Unbounded,
+            Bound::Included(0),
+            Bound::Excluded(0),
+            Bound::Included(5),
+            Bound::Excluded(u64::MAX),
+        ];
+
+        for start in bounds {
+            for end in bounds {
+                let toc = repository
+                    .list(&Page::from_bounds(&(start, end)), &Filter::new())
+                    .await?;
+                assert!(toc.content.is_empty(), "{:?}", (start, end));
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_contains_times() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::new();
+        let recipe = Recipe {
+            title: "Lasagne".to_string(),
+            prep_time_minutes: Some(30),
+            cook_time_minutes: Some(45),
+            ..Default::default()
+        };
+        repository.insert(&recipe).await?;
+
+        let toc = repository.list(&Page::all(), &Filter::new()).await?;
+        assert_eq!(toc.content[0].prep_time_minutes, Some(30));
+        assert_eq!(toc.content[0].cook_time_minutes, Some(45));
+
+        Ok(())
+    }
+
+    spec! {
+        async list_by_tags {
+            case no_tags {
+                let filter = Filter::new();
+                let want = 100;
+            }
+
+            case one_tag {
+                let filter = Filter::new().tag("vegetarisch");
+                let want = 67;
+            }
+
+            case all_tags {
+                let filter = Filter::new().tag("vegetarisch").tag("schnell");
+                let want = 34;
+            }
+
+            case tag_is_normalized {
+                let filter = Filter::new().tag(" Schnell ");
+                let want = 34;
+            }
+
+            case unknown_tag {
+                let filter = Filter::new().tag("vegan");
+                let want = 0;
+            }
+
+            case tag_and_search {
+                let filter = Filter::new().search("Recipe 1").tag("schnell");
+                let want = 3;
+            }
+
+            case search_only {
+                let filter = Filter::new().search("Recipe 1");
+                let want = 11;
+            }
+
+            let mut repository = Ephemeral::new();
+            fill_with_testdata(&mut repository).await;
+
+            let toc = repository.list(&Page::all(), &filter).await.unwrap();
+            assert_eq!(toc.content.len(), want);
+        }
+    }
+
+    spec! {
+        async list_counts {
+            type Output = Result<(), RepositoryError>;
+
+            case everything {
+                let filter = Filter::new();
+                let range = Range::Unbounded;
+                let want = (100, 100, 100);
+            }
+
+            case search {
+                let filter = Filter::new().search("Recipe 1");
+                let range = Range::Unbounded;
+                let want = (100, 11, 11);
+            }
+
+            case search_and_range {
+                let filter = Filter::new().search("Recipe 1");
+                let range = Range::Closed { start: 0, end: 4 };
+                let want = (100, 11, 5);
+            }
+
+            case nothing_found {
+                let filter = Filter::new().search("Suppe");
+                let range = Range::Unbounded;
+                let want = (100, 0, 0);
+            }
+
+            let mut repository = Ephemeral::new();
+            fill_with_testdata(&mut repository).await;
+
+            let toc = repository.list(&range.into(), &filter).await?;
+            assert_eq!(want, (toc.total(), toc.filtered(), toc.content().len()));
+            Ok(())
+        }
+    }
+
+    spec! {
+        async list_by_search {
+            type Output = Result<(), RepositoryError>;
+
+            case same_case {
+                let search = "Käse";
+                let want = vec!["Käsekuchen", "Käsespätzle"];
+            }
+
+            case lower_case {
+                let search = "lasagne";
+                let want = vec!["Lasagne"];
+            }
+
+            case upper_case {
+                let search = "KÄSES";
+                let want = vec!["Käsespätzle"];
+            }
+
+            case umlaut {
+                let search = "käse";
+                let want = vec!["Käsekuchen", "Käsespätzle"];
+            }
+
+            case empty {
+                let search = "";
+                let want = vec!["Käsekuchen", "Käsespätzle", "Lasagne"];
+            }
+
+            let mut repository = Ephemeral::new();
+            for title in ["Lasagne", "Käsespätzle", "Käsekuchen"] {
+                repository.insert(&Recipe { title: title.into(), ..Default::default() }).await?;
+            }
+
+            let toc = repository.list(&Page::all(), &Filter::new().search(search)).await?;
+            let titles: Vec<&str> = toc.content().iter().map(|s| s.title.as_str()).collect();
+            assert_eq!(want, titles);
+            Ok(())
+        }
+    }
+
+    spec! {
+        async list_by_search_mode {
+            type Output = Result<(), RepositoryError>;
+
+            case prefix {
+                let query = SearchQuery::new("spaghetti", SearchMode::Prefix);
+                let want = vec!["Spaghetti Bolognese", "Spaghetti Carbonara"];
+            }
+
+            case prefix_misses_inner_word {
+                let query = SearchQuery::new("bolognese", SearchMode::Prefix);
+                let want: Vec<&str> = vec![];
+            }
+
+            case contains {
+                let query = SearchQuery::new("bolognese", SearchMode::Contains);
+                let want = vec!["Lasagne Bolognese", "Spaghetti Bolognese"];
+            }
+
+            case contains_part_of_word {
+                let query = SearchQuery::new("ara", SearchMode::Contains);
+                let want = vec!["Spaghetti Carbonara"];
+            }
+
+            case all_tokens {
+                let query = SearchQuery::new("bolognese spaghetti", SearchMode::AllTokens);
+                let want = vec!["Spaghetti Bolognese"];
+            }
+
+            case all_tokens_word_prefix {
+                let query = SearchQuery::new("Bolo LAS", SearchMode::AllTokens);
+                let want = vec!["Lasagne Bolognese"];
+            }
+
+            case all_tokens_missing_word {
+                let query = SearchQuery::new("spaghetti pesto", SearchMode::AllTokens);
+                let want: Vec<&str> = vec![];
+            }
+
+            case all_tokens_empty {
+                let query = SearchQuery::new("  ", SearchMode::AllTokens);
+                let want = vec!["Lasagne Bolognese", "Spaghetti Bolognese", "Spaghetti Carbonara"];
+            }
+
+            let mut repository = Ephemeral::new();
+            for title in ["Spaghetti Bolognese", "Lasagne Bolognese", "Spaghetti Carbonara"] {
+                repository.insert(&Recipe { title: title.into(), ..Default::default() }).await?;
+            }
+
+            let toc = repository.list(&Page::all(), &Filter::new().search(query)).await?;
+            let titles: Vec<&str> = toc.content().iter().map(|s| s.title.as_str()).collect();
+            assert_eq!(want, titles);
+            Ok(())
+        }
+    }
+
+    spec! {
+        async list_by_search_case {
+            type Output = Result<(), RepositoryError>;
+
+            case insensitive_lower {
+                let query = SearchQuery::new("lasagne", SearchMode::Prefix);
+                let want = vec!["LASAGNE al forno", "Lasagne"];
+            }
+
+            case insensitive_mixed {
+                let query = SearchQuery::new("LaSaGnE", SearchMode::Prefix);
+                let want = vec!["LASAGNE al forno", "Lasagne"];
+            }
+
+            case insensitive_leading_space {
+                let query = SearchQuery::new(" lasagne", SearchMode::Prefix);
+                let want = vec![" Lasagne verde"];
+            }
+
+            case sensitive {
+                let query = SearchQuery::case_sensitive("Lasagne", SearchMode::Prefix);
+                let want = vec!["Lasagne"];
+            }
+
+            case sensitive_lower {
+                let query = SearchQuery::case_sensitive("lasagne", SearchMode::Contains);
+                let want: Vec<&str> = vec![];
+            }
+
+            case sensitive_umlaut {
+                let query = SearchQuery::case_sensitive("Käse", SearchMode::Contains);
+                let want = vec!["Käsekuchen"];
+            }
+
+            let mut repository = Ephemeral::new();
+            for title in ["Lasagne", "LASAGNE al forno", " Lasagne verde", "Käsekuchen", "KÄSEKUCHEN"] {
+                repository.insert(&Recipe { title: title.into(), ..Default::default() }).await?;
+            }
+
+            let toc = repository.list(&Page::all(), &Filter::new().search(query)).await?;
+            let titles: Vec<&str> = toc.content().iter().map(|s| s.title.as_str()).collect();
+            assert_eq!(want, titles);
+            Ok(())
+        }
+    }
+
+    spec! {
+        async list_by_full_text {
+            type Output = Result<(), RepositoryError>;
+
+            case only_in_preparation {
+                let text = "Backofen";
+                let want = vec!["Auflauf"];
+            }
+
+            case inside_a_word {
+                let text = "MEHL";
+                let want = vec!["Auflauf", "Pfannkuchen"];
+            }
+
+            case in_title {
+                let text = "kuchen";
+                let want = vec!["Pfannkuchen"];
+            }
+
+            case nowhere {
+                let text = "Zimt";
+                let want: Vec<&str> = vec![];
+            }
+
+            let recipes = serde_json::json!([
+                {"title": "Auflauf", "servings": 2, "ingredients": [
+                    {"name": "Weizenmehl", "quantity": "50", "unit": "g"},
+                ], "preparation": "Im Backofen 40 Minuten garen."},
+                {"title": "Pfannkuchen", "servings": 4, "ingredients": [
+                    {"name": "Mehl", "quantity": "250", "unit": "g"},
+                ], "preparation": "In der Pfanne ausbacken."},
+            ]);
+
+            let mut repository = Ephemeral::new();
+            for recipe in serde_json::from_value::<Vec<Recipe>>(recipes).unwrap() {
+                repository.insert(&recipe).await?;
+            }
+
+            let filter = Filter::new().search(SearchQuery::full_text(text));
+            let toc = repository.list(&Page::all(), &filter).await?;
+            let titles: Vec<&str> = toc.content().iter().map(|s| s.title.as_str()).collect();
+            assert_eq!(want, titles);
+            Ok(())
+        }
+    }
+
+    spec! {
+        async list_by_search_fields {
+            type Output = Result<(), RepositoryError>;
+
+            case title_only {
+                let fields = SearchFields::Title;
+                let want = vec!["Auberginen-Auflauf"];
+            }
+
+            case ingredients {
+                let fields = SearchFields::Ingredients;
+                let want = vec!["Moussaka", "Ratatouille"];
+            }
+
+            case preparation {
+                let fields = SearchFields::Preparation;
+                let want = vec!["Gemüsepfanne"];
+            }
+
+            case all {
+                let fields = SearchFields::All;
+                let want = vec!["Auberginen-Auflauf", "Gemüsepfanne", "Moussaka", "Ratatouille"];
+            }
+
+            let recipes = serde_json::json!([
+                {"title": "Auberginen-Auflauf", "servings": 2, "ingredients": []},
+                {"title": "Moussaka", "servings": 4, "ingredients": [
+                    {"name": "Aubergine", "quantity": "2", "unit": ""},
+                ]},
+                {"title": "Ratatouille", "servings": 4, "ingredients": [
+                    {"name": "Zucchini", "quantity": "1", "unit": ""},
+                    {"name": "aubergine", "quantity": "1", "unit": ""},
+                ]},
+                {"title": "Gemüsepfanne", "servings": 2, "ingredients": [],
+                    "preparation": "Zucchini und Aubergine würfeln."},
+                {"title": "Lasagne", "servings": 4, "ingredients": []},
+            ]);
+
+            let mut repository = Ephemeral::new();
+            for recipe in serde_json::from_value::<Vec<Recipe>>(recipes).unwrap() {
+                repository.insert(&recipe).await?;
+            }
+
+            let query = SearchQuery::new("aubergine", SearchMode::Contains).fields(fields);
+            let toc = repository.list(&Page::all(), &Filter::new().search(query)).await?;
+            let titles: Vec<&str> = toc.content().iter().map(|s| s.title.as_str()).collect();
+            assert_eq!(want, titles);
+            Ok(())
+        }
+    }
+
+    spec! {
+        async list_sorted {
+            type Output = Result<(), RepositoryError>;
+
+            case title_asc {
+                let sort = SortBy::TitleAsc;
+                let want = vec!["Apfelkuchen", "Lasagne", "Lasagne", "Zwiebelkuchen"];
+            }
+
+            case title_desc {
+                let sort = SortBy::TitleDesc;
+                let want = vec!["Zwiebelkuchen", "Lasagne", "Lasagne", "Apfelkuchen"];
+            }
+
+            case updated_desc {
+                let sort = SortBy::UpdatedDesc;
+                let want = vec!["Apfelkuchen", "Lasagne", "Zwiebelkuchen", "Lasagne"];
+            }
+
+            let mut repository = Ephemeral::new();
+            for title in ["Lasagne", "Zwiebelkuchen", "Lasagne", "Apfelkuchen"] {
+                repository.insert(&Recipe { title: title.into(), ..Default::default() }).await?;
+                std::thread::sleep(std::time::Duration::from_millis(2));
+            }
+
+            let filter = Filter::new().sort_by(sort);
+            let toc = repository.list(&Page::all(), &filter).await?;
+            let titles: Vec<&str> = toc.content().iter().map(|s| s.title.as_str()).collect();
+            assert_eq!(want, titles);
+
+            let ids: Vec<_> = toc.content().iter().map(|s| s.id).collect();
+            let again = repository.list(&Page::all(), &filter).await?;
+            assert_eq!(ids, again.content().iter().map(|s| s.id).collect::<Vec<_>>());
+            Ok(())
+        }
+    }
+
+    spec! {
+        async page_through_sorted {
+            type Output = Result<(), RepositoryError>;
+
+            case title_asc {
+                let sort = SortBy::TitleAsc;
+            }
+
+            case title_desc {
+                let sort = SortBy::TitleDesc;
+            }
+
+            case updated_desc {
+                let sort = SortBy::UpdatedDesc;
+            }
+
+            let mut repository = Ephemeral::new();
+            fill_with_testdata(&mut repository).await;
+            fill_with_testdata(&mut repository).await;
+            let filter = Filter::new().sort_by(sort);
+
+            let mut paged = Vec::new();
+            for start in (0..200).step_by(7) {
+                let toc = repository.list(&Page { offset: start, limit: Some(7) }, &filter).await?;
+                paged.extend(toc.content().iter().map(|s| s.id));
+            }
+
+            let all: Vec<_> = repository
+                .list(&Page::all(), &filter).await?
+                .content()
+                .iter()
+                .map(|s| s.id)
+                .collect();
+            assert_eq!(200, paged.len());
+            assert_eq!(all, paged);
+            Ok(())
+        }
+    }
+
+    spec! {
+        async find_by_title {
+            type Output = Result<(), RepositoryError>;
+
+            case none {
+                let title = "Moussaka";
+                let want = 0;
+            }
+
+            case one {
+                let title = "Käsekuchen";
+                let want = 1;
+            }
+
+            case several {
+                let title = "Lasagne";
+                let want = 2;
+            }
+
+            case ignores_case {
+                let title = "KÄSEKUCHEN";
+                let want = 1;
+            }
+
+            case exact_only {
+                let title = "Lasagne al";
+                let want = 0;
+            }
+
+            let mut repository = Ephemeral::new();
+            for title in ["Lasagne", "lasagne", "Lasagne al forno", "Käsekuchen"] {
+                repository.insert(&Recipe { title: title.into(), ..Default::default() }).await?;
+            }
+
+            let found = repository.find_by_title(title).await?;
+            assert_eq!(want, found.len());
+            for (id, recipe) in &found {
+                assert_eq!(title.to_lowercase(), recipe.title.to_lowercase());
+                assert_eq!(recipe, repository.get(id).await?.unwrap().value());
+            }
+            assert!(found.windows(2).all(|pair| pair[0].0 < pair[1].0));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn for_each_visits_every_recipe_once() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::new();
+        let mut inserted = Vec::new();
+        for servings in 1..=20 {
+            let recipe = Recipe {
+                servings,
+                ..TESTDATA[0].clone()
+            };
+            inserted.push(repository.insert(&recipe).await?);
+        }
+
+        let mut visited = Vec::new();
+        repository
+            .for_each(&mut |id, recipe| visited.push((*id, recipe.clone())))
+            .await?;
+        for (id, recipe) in &visited {
+            assert_eq!(recipe, repository.get(id).await?.unwrap().value());
+        }
+        let mut visited: Vec<Uuid> = visited.into_iter().map(|(id, _)| id).collect();
+
+        visited.sort();
+        inserted.sort();
+        assert_eq!(inserted, visited);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn export_import_round_trip() -> Result<(), RepositoryError> {
+        let mut source = Ephemeral::new();
+        fill_with_testdata(&mut source).await;
+        let exported = source.export_all().await?;
+        assert_eq!(100, exported.len());
+        assert!(exported.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        let mut target = Ephemeral::new();
+        assert_eq!(100, target.import_all(&exported).await?);
+
+        assert_eq!(exported, target.export_all().await?);
+        for (id, recipe) in &exported {
+            assert_eq!(recipe, target.get(id).await?.unwrap().value());
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn import_overwrites_existing() -> Result<(), RepositoryError> {
+        let mut repository = Ephemeral::new();
+        let id = repository.insert(&TESTDATA[0]).await?;
+        let created_at = repository.get(&id).await?.unwrap().created_at();
+
+        let recipe = Recipe {
+            servings: 4,
+            ..TESTDATA[0].clone()
+        };
+        let entries = [
+            (id, recipe.clone()),
+            (uuid::Uuid::new_v4(), TESTDATA[0].clone()),
+        ];
+        assert_eq!(2, repository.import_all(&entries).await?);
+
+        assert_eq!(2, repository.count().await?);
+        let stored = repository.get(&id).await?.unwrap();
+        assert_eq!(&recipe, stored.value());
+        assert_eq!(created_at, stored.created_at());
+        Ok(())
+    }
+
+    spec! {
+        async exists_and_count {
+            type Output = Result<(), RepositoryError>;
+
+            case empty {
+                let filled = false;
+            }
+
+            case filled {
+                let filled = true;
+            }
+
+            let mut repository = Ephemeral::new();
+            if filled {
+                fill_with_testdata(&mut repository).await;
+            }
+
+            assert_eq!(if filled { 100 } else { 0 }, repository.count().await?);
+            assert!(!repository.exists(&uuid::Uuid::new_v4()).await?);
+            for id in repository.list_ids(&Range::Unbounded) {
+                assert!(repository.exists(&id).await?);
+            }
+            Ok(())
+        }
+    }
+
+    spec! {
+        async list_by_difficulty {
+            case easy {
+                let filter = Filter::new().difficulty(Difficulty::Easy);
+                let want = 25;
+            }
+
+            case hard {
+                let filter = Filter::new().difficulty(Difficulty::Hard);
+                let want = 25;
+            }
+
+            case difficulty_and_tag {
+                let filter = Filter::new().difficulty(Difficulty::Easy).tag("vegetarisch");
+                let want = 17;
+            }
+
+            let mut repository = Ephemeral::new();
+            fill_with_testdata(&mut repository).await;
+
+            let toc = repository.list(&Page::all(), &filter).await.unwrap();
+            assert_eq!(toc.content.len(), want);
+        }
+    }
+
+    spec! {
+        async list_by_category {
+            case main {
+                let filter = Filter::new().category(Category::Main);
+                let want = vec!["Gulasch", "Lasagne"];
+            }
+
+            case other {
+                let filter = Filter::new().category(Category::from("Brotzeit"));
+                let want = vec!["Brotzeit"];
+            }
+
+            case category_and_search {
+                let filter = Filter::new().category(Category::Main).search("Las");
+                let want = vec!["Lasagne"];
+            }
+
+            case none_of_the_category {
+                let filter = Filter::new().category(Category::Drink);
+                let want: Vec<&str> = vec![];
+            }
+
+            let recipes: Vec<Recipe> = serde_json::from_value(serde_json::json!([
+                {"title": "Tomatensuppe", "servings": 2, "ingredients": [], "category": "starter"},
+                {"title": "Lasagne", "servings": 4, "ingredients": [], "category": "main"},
+                {"title": "Gulasch", "servings": 4, "ingredients": [], "category": "Main"},
+                {"title": "Tiramisu", "servings": 6, "ingredients": [], "category": "dessert"},
+                {"title": "Brotzeit", "servings": 2, "ingredients": [], "category": "brotzeit"},
+                {"title": "Tee", "servings": 1, "ingredients": []},
+            ]))
+            .unwrap();
+            let mut repository = Ephemeral::new();
+            for recipe in &recipes {
+                repository.insert(recipe).await.unwrap();
+            }
+
+            let toc = repository.list(&Page::all(), &filter).await.unwrap();
+            let got: Vec<&str> = toc.content.iter().map(|s| s.title.as_str()).collect();
+            assert_eq!(want, got);
+        }
+    }
+
+    spec! {
+        async list_by_combined_filter {
+            type Output = Result<(), RepositoryError>;
+
+            case tag_and_difficulty {
+                let filter = Filter::new().tag("schnell").difficulty(Difficulty::Easy);
+                let range = Range::Unbounded;
+                let want = (9, vec![0, 12, 24, 36, 48, 60, 72, 84, 96]);
+            }
+
+            case tag_and_difficulty_descending {
+                let filter = Filter::new()
+                    .tag("schnell")
+                    .difficulty(Difficulty::Easy)
+                    .sort_by(SortBy::TitleDesc);
+                let range = Range::Unbounded;
+                let want = (9, vec![96, 84, 72, 60, 48, 36, 24, 12, 0]);
+            }
+
+            case tag_and_difficulty_paged {
+                let filter = Filter::new().tag("schnell").difficulty(Difficulty::Easy);
+                let range = Range::Closed { start: 2, end: 4 };
+                let want = (9, vec![24, 36, 48]);
+            }
+
+            case search_tag_and_difficulty {
+                let filter = Filter::new()
+                    .search("Recipe 1")
+                    .tag("schnell")
+                    .difficulty(Difficulty::Easy);
+                let range = Range::Unbounded;
+                let want = (1, vec![12]);
+            }
+
+            case search_and_difficulty {
+                let filter = Filter::new().search("recipe 1").difficulty(Difficulty::Hard);
+                let range = Range::Unbounded;
+                let want = (3, vec![10, 14, 18]);
+            }
+
+            case search_and_tag_descending {
+                let filter = Filter::new()
+                    .search("Recipe 1")
+                    .tag("vegetarisch")
+                    .sort_by(SortBy::TitleDesc);
+                let range = Range::Unbounded;
+                let want = (8, vec![19, 18, 16, 15, 13, 12, 10, 1]);
+            }
+
+            case category_and_tag {
+                let filter = Filter::new().category(Category::Main).tag("schnell");
+                let range = Range::Unbounded;
+                let want = (7, vec![0, 15, 30, 45, 60, 75, 90]);
+            }
+
+            case category_and_difficulty_descending {
+                let filter = Filter::new()
+                    .category(Category::Dessert)
+                    .difficulty(Difficulty::Medium)
+                    .sort_by(SortBy::TitleDesc);
+                let range = Range::Unbounded;
+                let want = (5, vec![81, 61, 41, 21, 1]);
+            }
+
+            case all_criteria {
+                let filter = Filter::new()
+                    .search("recipe 6")
+                    .tag("vegetarisch")
+                    .difficulty(Difficulty::Easy)
+                    .category(Category::Main);
+                let range = Range::Unbounded;
+                let want = (1, vec![60]);
+            }
+
+            case nothing_selected {
+                let filter = Filter::new()
+                    .search("Recipe 2")
+                    .tag("schnell")
+                    .difficulty(Difficulty::Hard);
+                let range = Range::Unbounded;
+                let want = (0, Vec::<u32>::new());
+            }
+
+            let mut repository = Ephemeral::new();
+            fill_with_testdata(&mut repository).await;
+
+            let toc = repository.list(&range.into(), &filter).await?;
+            let titles: Vec<_> = toc.content().iter().map(|s| s.title.as_str()).collect();
+            let (filtered, numbers) = want;
+            let want: Vec<_> = numbers.iter().map(|n| format!("Recipe {}", n)).collect();
+            assert_eq!(100, toc.total());
+            assert_eq!(filtered, toc.filtered());
+            assert_eq!(want, titles);
+            Ok(())
+        }
+    }
+
+    /// Uses the repository only through the trait, like the handlers
+    /// of the server do.
+    async fn exercise(repository: &mut dyn Repository) -> Result<(), RepositoryError> {
+        let mut events = repository.watch();
+        let id = repository.insert(&TESTDATA[0]).await?;
+        assert_eq!(Ok(RepositoryEvent::Inserted(id)), events.try_recv());
+        let known = uuid::Uuid::new_v4();
+        repository.insert_with_id(&known, &TESTDATA[0]).await?;
+        assert!(matches!(
+            repository.insert_unique(&TESTDATA[0]).await?,
+            InsertOutcome::Duplicate(_)
+        ));
+        assert!(matches!(
+            repository.insert_with_id(&known, &TESTDATA[0]).await,
+            Err(RepositoryError::DuplicateId(_))
+        ));
+
+        let recipe = Recipe {
+            servings: 4,
+            ..TESTDATA[0].clone()
+        };
+        assert_eq!(
+            UpdateResult::Changed,
+            repository.update(&id, &recipe).await?
+        );
+        assert_eq!(&recipe, repository.get(&id).await?.unwrap().value());
+        assert_eq!(
+            UpdateResult::Changed,
+            repository.update_if_match(&id, &recipe, 2).await?
+        );
+        assert!(matches!(
+            repository.update_if_match(&id, &recipe, 2).await,
+            Err(RepositoryError::Conflict {
+                current_revision: 3
+            })
+        ));
+        assert_eq!(2, repository.find_by_title(&TESTDATA[0].title).await?.len());
+        let mut visited = 0;
+        repository.for_each(&mut |_, _| visited += 1).await?;
+        assert_eq!(2, visited);
+        let exported = repository.export_all().await?;
+        assert_eq!(2, exported.len());
+        assert_eq!(
+            exported.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            repository.ids().await?
+        );
+        assert_eq!(2, repository.import_all(&exported).await?);
+        assert_eq!(exported, repository.export_all().await?);
+        assert!(repository.exists(&id).await?);
+        assert_eq!(2, repository.count().await?);
+
+        let toc = repository.list(&Page::all(), &Filter::new()).await?;
+        assert_eq!(2, toc.content.len());
+        let toc = repository
+            .list(
+                &Page {
+                    offset: 1,
+                    limit: None,
+                },
+                &Filter::new(),
+            )
+            .await?;
+        assert_eq!(1, toc.content.len());
+
+        assert_eq!(RemoveResult::Removed, repository.remove(&id).await?);
+        assert_eq!(None, repository.get(&id).await?);
+        assert_eq!(RemoveResult::NotFound, repository.remove(&id).await?);
+        assert_eq!(1, repository.remove_all(&[id, known]).await?);
+        let outcomes = repository
+            .insert_all(&[TESTDATA[0].clone(), recipe.clone()], false)
+            .await?;
+        assert_eq!(2, outcomes.len());
+        assert_eq!(
+            &recipe,
+            repository.get(&outcomes[1].id()).await?.unwrap().value()
+        );
+        assert_eq!(2, repository.clear().await?);
+        assert_eq!(0, repository.count().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ephemeral_implements_trait() -> Result<(), RepositoryError> {
+        exercise(&mut Ephemeral::new()).await
+    }
+}
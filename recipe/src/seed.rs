@@ -0,0 +1,158 @@
+//! Fills a repository with recipes for demos and the first run.
+
+use crate::repository::{InsertOutcome, Repository, RepositoryError};
+use crate::Recipe;
+use std::{error, fmt};
+use uuid::Uuid;
+
+/// JSON array of the recipes bundled with the cookbook.
+pub const RECIPES: &str = include_str!("fixture/recipes.json");
+
+/// Inserts the recipes of a JSON array into the repository.
+///
+/// The whole array is parsed before the first recipe is inserted, so
+/// malformed JSON leaves the repository untouched. If the repository
+/// fails, [Repository::insert_all] tells which recipes are kept.
+///
+/// Returns the ids of the new recipes in the order of the array.
+///
+/// # Example
+///
+/// ```rust
+/// use recipers::repository::{memory, Repository};
+/// use recipers::seed::{seed_from_json, RECIPES};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut repository = memory::Repository::new();
+/// let ids = seed_from_json(&mut repository, RECIPES).await.unwrap();
+/// assert_eq!(ids.len() as u64, repository.count().await.unwrap());
+/// # }
+/// ```
+pub async fn seed_from_json(
+    repository: &mut impl Repository,
+    json: &str,
+) -> Result<Vec<Uuid>, SeedError> {
+    let recipes: Vec<Recipe> = serde_json::from_str(json)?;
+    let outcomes = repository.insert_all(&recipes, false).await?;
+    Ok(outcomes.iter().map(InsertOutcome::id).collect())
+}
+
+#[derive(Debug)]
+pub enum SeedError {
+    /// The recipes are not a JSON array of recipes.
+    Json(serde_json::Error),
+    /// The repository rejected a recipe.
+    Repository(RepositoryError),
+}
+
+impl From<serde_json::Error> for SeedError {
+    fn from(error: serde_json::Error) -> Self {
+        SeedError::Json(error)
+    }
+}
+
+impl From<RepositoryError> for SeedError {
+    fn from(error: RepositoryError) -> Self {
+        SeedError::Repository(error)
+    }
+}
+
+impl fmt::Display for SeedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeedError::Json(_) => write!(f, "invalid recipes"),
+            SeedError::Repository(_) => write!(f, "seeding the repository failed"),
+        }
+    }
+}
+
+impl error::Error for SeedError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SeedError::Json(cause) => Some(cause),
+            SeedError::Repository(cause) => Some(cause),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{seed_from_json, SeedError, RECIPES};
+    use crate::repository::{memory, Filter, Page, Repository};
+    use spucky::spec;
+
+    spec! {
+        async seed_memory_repository {
+            type Output = Result<(), SeedError>;
+
+            case bundled {
+                let json = RECIPES;
+                let want = 3;
+            }
+
+            case empty {
+                let json = "[]";
+                let want = 0;
+            }
+
+            case duplicates {
+                let json = r#"[
+                    {"title": "Tee", "servings": 1, "ingredients": []},
+                    {"title": "Tee", "servings": 1, "ingredients": []}
+                ]"#;
+                let want = 2;
+            }
+
+            let mut repository = memory::Repository::new();
+            let ids = seed_from_json(&mut repository, json).await?;
+
+            assert_eq!(want, ids.len());
+            assert_eq!(want as u64, repository.count().await?);
+            for id in &ids {
+                assert!(repository.exists(id).await?);
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn seed_bundled_titles() -> Result<(), SeedError> {
+        let mut repository = memory::Repository::new();
+        seed_from_json(&mut repository, RECIPES).await?;
+
+        let toc = repository.list(&Page::all(), &Filter::new()).await?;
+        let titles: Vec<_> = toc
+            .content()
+            .iter()
+            .map(|summary| summary.title.as_str())
+            .collect();
+        assert_eq!(vec!["Lasagne", "Pfannkuchen", "Tomatensauce"], titles);
+        Ok(())
+    }
+
+    spec! {
+        async seed_rejects_invalid_json {
+            case malformed {
+                let json = "[{";
+            }
+
+            case not_an_array {
+                let json = r#"{"title": "Tee", "servings": 1, "ingredients": []}"#;
+            }
+
+            case invalid_recipe {
+                let json = r#"[
+                    {"title": "Tee", "servings": 1, "ingredients": []},
+                    {"title": "Kaffee"}
+                ]"#;
+            }
+
+            let mut repository = memory::Repository::new();
+            let result = seed_from_json(&mut repository, json).await;
+
+            assert!(matches!(result, Err(SeedError::Json(_))), "{:?}", result);
+            assert_eq!(0, repository.count().await.unwrap());
+        }
+    }
+}
@@ -1,64 +1,30 @@
-use std::{
-    ops::Bound,
-    sync::{Arc, RwLock},
-};
+use std::{borrow::Cow, convert::Infallible, sync::Arc};
 
 use axum::{
-    extract::{Json, Path, Query, State, TypedHeader},
-    headers::Range,
-    http::{header, StatusCode},
-    response::IntoResponse,
-    routing, Router,
+    async_trait,
+    body::{Bytes, HttpBody, StreamBody},
+    extract::{FromRequest, Json, Path, Query, State, TypedHeader},
+    headers::{IfNoneMatch, Range},
+    http::{header, HeaderMap, Request, StatusCode},
+    response::{IntoResponse, Response},
+    routing, BoxError, Router,
 };
 use recipers::{
-    repository::{Repository, UpdateResult},
-    Recipe,
+    grpc::{self, RecipeServiceServer},
+    repository::{
+        memory, Filter, InsertOutcome, InstrumentedRepository, Page, RangeError, RemoveResult,
+        Repository, RepositoryError, SearchFields, SearchMode, SearchQuery, UpdateResult,
+    },
+    seed, Difficulty, ImageRef, Ingredient, Recipe, Summary, TableOfContents, ValidationError,
 };
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utoipa::{IntoParams, OpenApi};
 use uuid::Uuid;
 
-use tower_http::trace::TraceLayer;
+use tokio::sync::RwLock;
+use tokio_stream::StreamExt;
+use tower_http::{compression::CompressionLayer, limit::RequestBodyLimitLayer, trace::TraceLayer};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-// use cookbook::recipe_service_server::{RecipeService, RecipeServiceServer};
-// use cookbook::{ListTableOfContentsRequest, TableOfContentsResponse};
-
-// use tonic::{transport::Server, Request, Response, Status};
-
-// pub mod cookbook {
-//     tonic::include_proto!("cookbook");
-// }
-
-// #[derive(Default)]
-// pub struct MyService {}
-
-// #[tonic::async_trait]
-// impl RecipeService for MyService {
-//     async fn list_table_of_contents(
-//         &self,
-//         request: Request<ListTableOfContentsRequest>,
-//     ) -> Result<Response<TableOfContentsResponse>, Status> {
-//         println!("Got a request from {:?}", request.remote_addr());
-//         let reply = cookbook::TableOfContentsResponse {
-//             greeting: "Das Wars".to_string(),
-//         };
-
-//         Ok(Response::new(reply))
-//     }
-// }
-
-// #[tokio::main]
-// async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//     let addr = "[::1]:50051".parse().unwrap();
-//     let service = MyService::default();
-
-//     println!("Service listening on {}", addr);
-//     Server::builder()
-//         .add_service(RecipeServiceServer::new(service))
-//         .serve(addr)
-//         .await?;
-
-//     Ok(())
-// }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -70,65 +36,356 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let repository = Arc::new(RwLock::new(Repository::new()));
+    let options = options(std::env::args().skip(1))?;
+    let mut repository = memory::Repository::new();
+    if let Some(json) = options.seed {
+        let ids = seed::seed_from_json(&mut repository, &json).await?;
+        tracing::info!("seeded the repository with {} recipes", ids.len());
+    }
+
+    let repository: AppState = Arc::new(RwLock::new(InstrumentedRepository::new(repository)));
+    let app = router(repository.clone(), options.body_limit);
+
+    tracing::debug!("listening to 0.0.0.0:8080");
+    let http = axum::Server::bind(&"0.0.0.0:8080".parse().unwrap()).serve(app.into_make_service());
+
+    tracing::debug!("gRPC service listening to [::1]:50051");
+    let grpc = tonic::transport::Server::builder()
+        .add_service(RecipeServiceServer::new(grpc::Service::new(repository)))
+        .serve("[::1]:50051".parse().unwrap());
+
+    tokio::try_join!(
+        async { http.await.map_err(Box::<dyn std::error::Error>::from) },
+        async { grpc.await.map_err(Box::<dyn std::error::Error>::from) },
+    )?;
+
+    Ok(())
+}
+
+/// Largest request body accepted by the endpoints changing recipes,
+/// unless `--body-limit` says otherwise.
+const DEFAULT_BODY_LIMIT: usize = 256 * 1024;
+
+/// Settings of the server given on the command line.
+struct Options {
+    /// The JSON array of recipes to seed the repository with.
+    seed: Option<Cow<'static, str>>,
+    /// Largest request body in bytes accepted by the endpoints
+    /// changing recipes.
+    body_limit: usize,
+}
+
+/// Reads the options of the server from the command line.
+///
+/// `--seed` selects the recipes bundled with the cookbook and
+/// `--seed=FILE` the JSON array of recipes in the file. Without either
+/// flag the server starts with an empty repository.
+///
+/// `--body-limit=BYTES` sets the largest request body accepted when
+/// creating, changing or importing recipes.
+fn options(args: impl Iterator<Item = String>) -> Result<Options, Box<dyn std::error::Error>> {
+    let mut options = Options {
+        seed: None,
+        body_limit: DEFAULT_BODY_LIMIT,
+    };
+    for arg in args {
+        if let Some(limit) = arg.strip_prefix("--body-limit=") {
+            options.body_limit = limit
+                .parse()
+                .map_err(|_| format!("invalid body limit {:?}", limit))?;
+            continue;
+        }
+
+        options.seed = match arg.strip_prefix("--seed") {
+            Some("") => Some(Cow::Borrowed(seed::RECIPES)),
+            Some(path) if path.starts_with('=') => {
+                Some(Cow::Owned(std::fs::read_to_string(&path[1..])?))
+            }
+            _ => return Err(format!("unknown argument {:?}", arg).into()),
+        };
+    }
+    Ok(options)
+}
 
-    let app = Router::new()
+/// Creates the routes of the cookbook service on top of the given
+/// repository.
+///
+/// Any implementation of [Repository] will do, the repository is
+/// owned by the router alone. The routes accept request bodies up to
+/// the default limit. The server itself shares its repository with
+/// the gRPC service and uses [router] instead.
+pub fn router_with(repository: impl Repository + 'static) -> Router {
+    router(Arc::new(RwLock::new(repository)), DEFAULT_BODY_LIMIT)
+}
+
+/// Creates the routes of the cookbook service on top of a shared
+/// repository.
+///
+/// Responses are compressed if the client accepts gzip or brotli
+/// encoding. With the `metrics` feature every request is counted and
+/// timed in the metrics served at `/metrics`.
+///
+/// Requests changing recipes are rejected with 413 Payload Too Large
+/// if their body exceeds `body_limit` bytes.
+///
+/// The recipe routes answer `OPTIONS` with the methods they allow.
+fn router(repository: AppState, body_limit: usize) -> Router {
+    let router = Router::new()
         .route("/", routing::get(|| async { "Hello World!" }))
+        .route("/openapi.json", routing::get(openapi))
         .route(
             "/cookbook/recipe",
             routing::get(recipes_get)
                 .post(recipes_post)
+                .delete(recipes_delete)
+                .options(|| allow("GET, HEAD, POST, DELETE, OPTIONS"))
                 .with_state(repository.clone())
+                // The error type cannot be inferred through the limited
+                // body type, but the layer adds no errors.
+                .layer::<_, _, Infallible>(RequestBodyLimitLayer::new(body_limit))
                 .layer(TraceLayer::new_for_http()),
         )
         .route(
             "/cookbook/recipe/:id",
             routing::get(recipe_get)
+                .head(recipe_head)
                 .put(recipe_put)
                 .delete(recipe_delete)
+                .options(|| allow("GET, HEAD, PUT, DELETE, OPTIONS"))
                 .with_state(repository.clone())
+                .layer::<_, _, Infallible>(RequestBodyLimitLayer::new(body_limit))
+                .layer(TraceLayer::new_for_http()),
+        )
+        .route(
+            "/cookbook/recipe/export",
+            routing::get(recipes_export)
+                .options(|| allow("GET, HEAD, OPTIONS"))
+                .with_state(repository.clone())
+                .layer(TraceLayer::new_for_http()),
+        )
+        .route(
+            "/cookbook/recipe/import",
+            routing::post(recipes_import)
+                .options(|| allow("POST, OPTIONS"))
+                .with_state(repository.clone())
+                .layer::<_, _, Infallible>(RequestBodyLimitLayer::new(body_limit))
                 .layer(TraceLayer::new_for_http()),
         )
         .route(
             "/cookbook/recipe/share",
-            routing::get(recipe_share).with_state(repository.clone()),
+            routing::get(recipe_share).with_state(repository),
         );
 
-    tracing::debug!("listening to 0.0.0.0:8080");
-    axum::Server::bind(&"0.0.0.0:8080".parse().unwrap())
-        .serve(app.into_make_service())
-        .await?;
+    #[cfg(feature = "metrics")]
+    let router = telemetry::instrument(router);
+    router.layer(CompressionLayer::new())
+}
 
-    Ok(())
+/// Metrics of the requests and of the repository.
+#[cfg(feature = "metrics")]
+mod telemetry {
+    use std::{sync::OnceLock, time::Instant};
+
+    use axum::{
+        extract::MatchedPath,
+        http::Request,
+        middleware::{self, Next},
+        response::Response,
+        routing, Router,
+    };
+    use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+    /// Serves the metrics at `/metrics` and counts and times every
+    /// request to the routes of the router.
+    ///
+    /// Installs the recorder, so the metrics of the repository are
+    /// kept from now on as well.
+    pub fn instrument(router: Router) -> Router {
+        prometheus();
+        router
+            .route("/metrics", routing::get(render))
+            .route_layer(middleware::from_fn(track))
+    }
+
+    /// Returns the handle to the metrics of the process.
+    ///
+    /// The Prometheus recorder is installed on first use. There is
+    /// only one recorder per process, so all routers share their
+    /// metrics.
+    fn prometheus() -> &'static PrometheusHandle {
+        static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+        HANDLE.get_or_init(|| {
+            PrometheusBuilder::new()
+                .install_recorder()
+                .expect("no other metrics recorder is installed")
+        })
+    }
+
+    /// Counts the requests and records their durations per route.
+    ///
+    /// The route is the matched path like `/cookbook/recipe/:id`, not
+    /// the requested one, which keeps the number of label values
+    /// bounded.
+    async fn track<B>(request: Request<B>, next: Next<B>) -> Response {
+        let path = match request.extensions().get::<MatchedPath>() {
+            Some(path) => path.as_str().to_owned(),
+            None => request.uri().path().to_owned(),
+        };
+        let method = request.method().to_string();
+
+        let start = Instant::now();
+        let response = next.run(request).await;
+        let seconds = start.elapsed().as_secs_f64();
+
+        let labels = [
+            ("method", method),
+            ("path", path),
+            ("status", response.status().as_u16().to_string()),
+        ];
+        metrics::increment_counter!("http_requests_total", &labels);
+        metrics::histogram!("http_requests_duration_seconds", seconds, &labels);
+        response
+    }
+
+    /// Serves the metrics in the Prometheus text format.
+    async fn render() -> String {
+        prometheus().render()
+    }
 }
 
-type AppState = Arc<RwLock<Repository>>;
+/// The repository behind the handlers, independent of its backend.
+type DynRepository = dyn Repository;
+
+type AppState = Arc<RwLock<DynRepository>>;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        recipes_get,
+        recipes_post,
+        recipes_import,
+        recipes_export,
+        recipes_delete,
+        recipe_get,
+        recipe_head,
+        recipe_put,
+        recipe_delete
+    ),
+    components(schemas(Recipe, Ingredient, ImageRef, Difficulty, TableOfContents, Summary))
+)]
+struct ApiDoc;
+
+/// Serves the OpenAPI document of the cookbook service.
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct Search {
+    /// Selects the recipes whose title starts with `q`, ignoring case.
     q: Option<String>,
+    /// Distinguishes upper and lower case in `q`.
+    #[serde(default)]
+    case_sensitive: bool,
+    /// Selects the recipes containing `q` anywhere in the title, the
+    /// ingredients or the preparation.
+    #[serde(default)]
+    fulltext: bool,
 }
 
+/// Lists the table of contents of the cookbook.
+///
+/// With a search, every summary whose title matches marks the
+/// matching part in `highlight`.
+#[utoipa::path(
+    get,
+    path = "/cookbook/recipe",
+    params(
+        Search,
+        ("Range" = Option<String>, Header, description = "Range of the summaries, e.g. `bytes=0-9` or `bytes=10-`. Only a single range with a first position is supported"),
+    ),
+    responses(
+        (status = 200, description = "Table of contents", body = TableOfContents),
+        (status = 206, description = "Part of the table of contents", body = TableOfContents),
+        (status = 400, description = "Malformed, suffix or more than one range requested"),
+        (status = 416, description = "The range starts behind the last recipe or ends before it starts"),
+    )
+)]
 async fn recipes_get(
     State(state): State<AppState>,
     Query(parameter): Query<Search>,
-    TypedHeader(range): TypedHeader<Range>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+    range: Option<TypedHeader<Range>>,
+) -> Result<Response, (StatusCode, String)> {
     let search = parameter.q.unwrap_or("".into());
 
-    let it: (Bound<u64>, Bound<u64>) = range
-        .iter()
-        .nth(0)
-        .unwrap_or((Bound::Unbounded, Bound::Unbounded));
+    let page = match range {
+        Some(TypedHeader(range)) => match Page::from_range_header(&range) {
+            Ok(page) => Some(page),
+            // Answered like a range behind the last recipe below.
+            Err(RangeError::Unsatisfiable) => Some(Page::first(0)),
+            Err(error) => return Err((StatusCode::BAD_REQUEST, error.to_string())),
+        },
+        None => None,
+    };
+
+    tracing::debug!("found page {:?}", page);
 
-    for r in range.iter() {
-        tracing::debug!("found range {:?}", r)
+    let mode = if parameter.fulltext {
+        SearchMode::Contains
+    } else {
+        SearchMode::Prefix
+    };
+    let query = if parameter.case_sensitive {
+        SearchQuery::case_sensitive(&search, mode)
+    } else {
+        SearchQuery::new(&search, mode)
+    };
+    let filter = if parameter.fulltext {
+        Filter::new().search(query.clone().fields(SearchFields::All))
+    } else {
+        Filter::new().search(query.clone())
+    };
+    let requested = page.unwrap_or_default();
+    let mut toc = state
+        .read()
+        .await
+        .list(&requested, &filter)
+        .await
+        .map_err(internal_error)?;
+    if !search.is_empty() {
+        toc.highlight(&query);
     }
 
-    let repository = state.read().unwrap();
-    let toc = repository.list2(&it, &search).map_err(internal_error)?;
+    let first = page.map_or(0, |page| page.offset);
 
-    Ok(Json(toc))
+    // Ranges select from the filtered recipes.
+    let selected = toc.content().len() as u64;
+    let total = toc.filtered() as u64;
+
+    if page.is_some_and(|page| page.is_empty()) || (selected == 0 && first > 0) {
+        // The range starts behind the last recipe.
+        return Ok((
+            StatusCode::RANGE_NOT_SATISFIABLE,
+            [(header::CONTENT_RANGE, format!("items */{}", total))],
+        )
+            .into_response());
+    }
+
+    if page.is_some() && selected < total {
+        let last = first + selected.saturating_sub(1);
+        return Ok((
+            StatusCode::PARTIAL_CONTENT,
+            [(
+                header::CONTENT_RANGE,
+                format!("items {}-{}/{}", first, last, total),
+            )],
+            Json(toc),
+        )
+            .into_response());
+    }
+
+    Ok(Json(toc).into_response())
 }
 
 /// Utility function for mapping any error into a `500 Internal Server Error`
@@ -140,53 +397,1979 @@ where
     (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
 }
 
+/// Answers `OPTIONS` with the methods of a route.
+async fn allow(methods: &'static str) -> impl IntoResponse {
+    (StatusCode::NO_CONTENT, [(header::ALLOW, methods)])
+}
+
+/// Responds with `422 Unprocessable Entity` listing every problem of
+/// an invalid recipe.
+fn unprocessable(errors: Vec<ValidationError>) -> Response {
+    (StatusCode::UNPROCESSABLE_ENTITY, Json(errors)).into_response()
+}
+
+/// Adds a new recipe to the cookbook.
+///
+/// Rejects the recipe if it is invalid or if the cookbook already
+/// contains the same recipe, see [Recipe::semantically_equals]. The
+/// recipe may be sent as YAML or TOML instead of JSON, see [Payload].
+#[utoipa::path(
+    post,
+    path = "/cookbook/recipe",
+    request_body = Recipe,
+    responses(
+        (status = 201, description = "Recipe created", body = Uuid),
+        (status = 409, description = "The same recipe exists"),
+        (status = 422, description = "The recipe is invalid, the body lists all problems"),
+        (status = 413, description = "The body is larger than the limit of the server"),
+    )
+)]
 async fn recipes_post(
     State(state): State<AppState>,
-    Json(payload): Json<Recipe>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+    Payload(payload): Payload<Recipe>,
+) -> Result<Response, (StatusCode, String)> {
     println!("recipes post called");
     println!("got recipe {:?}", payload);
 
-    let mut repository = state.write().unwrap();
-    let id = repository.insert(&payload).map_err(internal_error)?;
+    if let Err(errors) = payload.validate() {
+        return Ok(unprocessable(errors));
+    }
+
+    match state.write().await.insert_unique(&payload).await {
+        Ok(InsertOutcome::Inserted(id)) => Ok(created(id)),
+        Ok(InsertOutcome::Duplicate(id)) => {
+            Err((StatusCode::CONFLICT, format!("recipe {} is the same", id)))
+        }
+        Err(error) => Err(internal_error(error)),
+    }
+}
+
+/// Outcome of importing a single recipe.
+#[derive(Debug, Serialize)]
+struct ImportResult {
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<ValidationError>,
+}
+
+/// Adds many recipes to the cookbook at once.
+///
+/// If every recipe is valid, the response contains the ids of the new
+/// recipes in the order of the request. Otherwise only the valid
+/// recipes are added and the multi-status response contains the
+/// outcome of every recipe in the order of the request: the status
+/// 201 with the id or the status 422 with the validation errors.
+#[utoipa::path(
+    post,
+    path = "/cookbook/recipe/import",
+    request_body = Vec<Recipe>,
+    responses(
+        (status = 201, description = "All recipes created", body = Vec<Uuid>),
+        (status = 207, description = "Some recipes are invalid and were skipped"),
+        (status = 413, description = "The body is larger than the limit of the server"),
+    )
+)]
+async fn recipes_import(
+    State(state): State<AppState>,
+    Json(payload): Json<Vec<Recipe>>,
+) -> Result<Response, (StatusCode, String)> {
+    let checked: Vec<Result<&Recipe, Vec<ValidationError>>> = payload
+        .iter()
+        .map(|recipe| recipe.validate().map(|()| recipe))
+        .collect();
+
+    let valid: Vec<Recipe> = checked
+        .iter()
+        .filter_map(|result| result.as_ref().ok().map(|recipe| (*recipe).clone()))
+        .collect();
+
+    let ids: Vec<Uuid> = state
+        .write()
+        .await
+        .insert_all(&valid, false)
+        .await
+        .map_err(internal_error)?
+        .iter()
+        .map(InsertOutcome::id)
+        .collect();
+
+    if valid.len() == payload.len() {
+        return Ok((StatusCode::CREATED, Json(ids)).into_response());
+    }
+
+    let mut ids = ids.into_iter();
+    let results: Vec<ImportResult> = checked
+        .into_iter()
+        .map(|result| match result {
+            Ok(_) => ImportResult {
+                status: StatusCode::CREATED.as_u16(),
+                id: ids.next(),
+                errors: vec![],
+            },
+            Err(errors) => ImportResult {
+                status: StatusCode::UNPROCESSABLE_ENTITY.as_u16(),
+                id: None,
+                errors,
+            },
+        })
+        .collect();
+
+    Ok((StatusCode::MULTI_STATUS, Json(results)).into_response())
+}
+
+/// Downloads all recipes of the cookbook, for example as backup.
+///
+/// The body is newline-delimited JSON with one recipe per line,
+/// ordered by id. The recipes are read one at a time while the body
+/// is sent, so the collection is never copied as a whole. Recipes
+/// removed during the download are left out.
+#[utoipa::path(
+    get,
+    path = "/cookbook/recipe/export",
+    responses(
+        (status = 200, description = "One recipe per line", body = Recipe, content_type = "application/x-ndjson"),
+    )
+)]
+async fn recipes_export(State(state): State<AppState>) -> Result<Response, (StatusCode, String)> {
+    let ids = state.read().await.ids().await.map_err(internal_error)?;
+
+    let recipes = tokio_stream::iter(ids).then(move |id| {
+        let state = Arc::clone(&state);
+        async move { state.read().await.get(&id).await }
+    });
+    let lines = recipes.filter_map(|recipe| {
+        let line = match recipe {
+            Ok(Some(stored)) => serde_json::to_vec(stored.value()).map_err(axum::BoxError::from),
+            Ok(None) => return None,
+            Err(err) => Err(axum::BoxError::from(err)),
+        };
+
+        Some(line.map(|mut line| {
+            line.push(b'\n');
+            line
+        }))
+    });
 
     Ok((
-        StatusCode::CREATED,
-        [(header::LOCATION, format!("/cookbook/recipe/{}", id))],
-        Json(id),
-    ))
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        StreamBody::new(lines),
+    )
+        .into_response())
+}
+
+/// Removes all recipes with the given ids from the cookbook.
+///
+/// Unknown ids are ignored. The response contains the number of
+/// recipes removed.
+#[utoipa::path(
+    delete,
+    path = "/cookbook/recipe",
+    request_body = Vec<Uuid>,
+    responses((status = 200, description = "Number of recipes removed", body = u64))
+)]
+async fn recipes_delete(
+    State(state): State<AppState>,
+    Json(ids): Json<Vec<Uuid>>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let removed = state
+        .write()
+        .await
+        .remove_all(&ids)
+        .await
+        .map_err(internal_error)?;
+
+    Ok(Json(removed))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct View {
+    /// Scales the quantities of the ingredients to this number of
+    /// servings. The stored recipe is not changed.
+    servings: Option<u8>,
+}
+
+/// Formats in which a recipe can be sent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "toml")]
+    Toml,
+}
+
+impl Format {
+    /// Picks the first supported media type of the `Accept` header.
+    ///
+    /// Quality values are ignored. JSON is the default if the header
+    /// is missing or names no supported media type.
+    fn accepted(headers: &HeaderMap) -> Format {
+        headers
+            .get_all(header::ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .find_map(|range| {
+                let media_type = range.split(';').next().unwrap_or_default();
+                Format::from_media_type(media_type.trim())
+            })
+            .unwrap_or(Format::Json)
+    }
+
+    fn from_media_type(media_type: &str) -> Option<Format> {
+        match media_type {
+            "application/json" => Some(Format::Json),
+            #[cfg(feature = "yaml")]
+            "application/yaml" => Some(Format::Yaml),
+            #[cfg(feature = "toml")]
+            "application/toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+
+    fn media_type(self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            #[cfg(feature = "yaml")]
+            Format::Yaml => "application/yaml",
+            #[cfg(feature = "toml")]
+            Format::Toml => "application/toml",
+        }
+    }
+
+    /// Serializes the value into a response in this format.
+    fn respond<T: Serialize>(self, value: &T) -> Response {
+        let body = match self {
+            Format::Json => serde_json::to_string(value).map_err(|e| e.to_string()),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+            #[cfg(feature = "toml")]
+            Format::Toml => toml::to_string(value).map_err(|e| e.to_string()),
+        };
+
+        match body {
+            Ok(body) => ([(header::CONTENT_TYPE, self.media_type())], body).into_response(),
+            Err(reason) => (StatusCode::INTERNAL_SERVER_ERROR, reason).into_response(),
+        }
+    }
+
+    /// Deserializes a value from a body in this format.
+    fn parse<T: DeserializeOwned>(self, body: &[u8]) -> Result<T, String> {
+        let value = match self {
+            Format::Json => serde_json::from_slice(body).map_err(|e| e.to_string()),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => serde_yaml::from_slice(body).map_err(|e| e.to_string()),
+            #[cfg(feature = "toml")]
+            Format::Toml => std::str::from_utf8(body)
+                .map_err(|e| e.to_string())
+                .and_then(|text| toml::from_str(text).map_err(|e| e.to_string())),
+        };
+
+        value.map_err(|reason| format!("invalid {} body: {}", self.media_type(), reason))
+    }
+}
+
+/// Extracts the body of a request in the format named by its
+/// `Content-Type` header.
+///
+/// YAML and TOML bodies are read here and rejected with `400 Bad
+/// Request` if they cannot be deserialized. Everything else is left to
+/// the [Json] extractor, which also rejects a missing content type.
+struct Payload<T>(T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for Payload<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let format = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                let media_type = value.split(';').next().unwrap_or_default();
+                Format::from_media_type(media_type.trim())
+            })
+            .unwrap_or(Format::Json);
+
+        if format == Format::Json {
+            let Json(value) = Json::<T>::from_request(request, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return Ok(Payload(value));
+        }
+
+        let body = Bytes::from_request(request, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        format
+            .parse(&body)
+            .map(Payload)
+            .map_err(|reason| (StatusCode::BAD_REQUEST, reason).into_response())
+    }
 }
 
+/// Returns a single recipe.
+///
+/// The recipe is sent as YAML or TOML if the `Accept` header asks for
+/// `application/yaml` or `application/toml`, otherwise as JSON.
+#[utoipa::path(
+    get,
+    path = "/cookbook/recipe/{id}",
+    params(("id" = Uuid, Path, description = "Id of the recipe"), View),
+    responses(
+        (status = 200, description = "The recipe", body = Recipe,
+            content_type = ["application/json", "application/yaml", "application/toml"]),
+        (status = 400, description = "The recipe cannot be scaled to the servings"),
+        (status = 404, description = "Recipe not found"),
+    )
+)]
 async fn recipe_get(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let repository = state.read().map_err(internal_error)?;
-    let recipe = repository.get(&id).map_err(internal_error)?;
-    match recipe {
-        Some(result) => Ok(Json(result.clone())),
-        None => Err((StatusCode::NOT_FOUND, "recipe not found".to_owned())),
+    Query(view): Query<View>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    let format = Format::accepted(&headers);
+    let stored = state.read().await.get(&id).await.map_err(internal_error)?;
+    let recipe = match stored {
+        Some(result) => result.into_value(),
+        None => return Err((StatusCode::NOT_FOUND, "recipe not found".to_owned())),
+    };
+
+    match view.servings {
+        Some(servings) => match recipe.scale_to(servings) {
+            Some(scaled) => Ok(format.respond(&scaled)),
+            None => Err((
+                StatusCode::BAD_REQUEST,
+                format!("recipe cannot be scaled to {} servings", servings),
+            )),
+        },
+        None => Ok(format.respond(&recipe)),
     }
 }
 
+/// Checks whether a recipe exists without sending it.
+#[utoipa::path(
+    head,
+    path = "/cookbook/recipe/{id}",
+    params(("id" = Uuid, Path, description = "Id of the recipe")),
+    responses(
+        (status = 200, description = "Recipe exists"),
+        (status = 404, description = "Recipe not found"),
+    )
+)]
+async fn recipe_head(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let exists = state
+        .read()
+        .await
+        .exists(&id)
+        .await
+        .map_err(internal_error)?;
+    match exists {
+        true => Ok(StatusCode::OK),
+        false => Err((StatusCode::NOT_FOUND, String::new())),
+    }
+}
+
+/// Replaces a recipe or creates it with the given id.
+///
+/// With `If-None-Match: *` the recipe is only created. An existing
+/// recipe is left unchanged and the request fails with `412
+/// Precondition Failed`. The recipe may be sent as YAML or TOML
+/// instead of JSON, see [Payload].
+#[utoipa::path(
+    put,
+    path = "/cookbook/recipe/{id}",
+    params(
+        ("id" = Uuid, Path, description = "Id of the recipe"),
+        ("If-None-Match" = Option<String>, Header, description = "`*` to create the recipe only if it does not exist"),
+    ),
+    request_body = Recipe,
+    responses(
+        (status = 200, description = "Recipe replaced"),
+        (status = 201, description = "Recipe created", body = Uuid),
+        (status = 412, description = "The recipe already exists"),
+        (status = 422, description = "The recipe is invalid, the body lists all problems"),
+        (status = 413, description = "The body is larger than the limit of the server"),
+    )
+)]
 async fn recipe_put(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-    Json(payload): Json<Recipe>,
+    if_none_match: Option<TypedHeader<IfNoneMatch>>,
+    Payload(payload): Payload<Recipe>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let mut repository = state.write().unwrap();
-    let result = repository.update(&id, payload).map_err(internal_error)?;
+    if let Err(errors) = payload.validate() {
+        return Ok(unprocessable(errors));
+    }
+
+    if let Some(TypedHeader(condition)) = if_none_match {
+        if condition == IfNoneMatch::any() {
+            return match state.write().await.insert_with_id(&id, &payload).await {
+                Ok(()) => Ok(created(id)),
+                Err(RepositoryError::DuplicateId(_)) => Err((
+                    StatusCode::PRECONDITION_FAILED,
+                    "recipe already exists".to_owned(),
+                )),
+                Err(error) => Err(internal_error(error)),
+            };
+        }
+    }
+
+    let result = state
+        .write()
+        .await
+        .update(&id, &payload)
+        .await
+        .map_err(internal_error)?;
 
     match result {
-        UpdateResult::Created => Ok(StatusCode::OK.into_response()),
-        UpdateResult::Changed => Ok((
-            StatusCode::CREATED,
-            [(header::LOCATION, format!("/cookbook/recipe/{}", id))],
-            Json(id),
-        )
-            .into_response()),
+        UpdateResult::Created => Ok(created(id)),
+        UpdateResult::Changed => Ok(StatusCode::OK.into_response()),
     }
 }
 
-async fn recipe_delete(State(state): State<AppState>, Path(id): Path<Uuid>) {}
-async fn recipe_share(State(state): State<AppState>) {}
+/// Responds with `201 Created` pointing to the recipe with the id.
+fn created(id: Uuid) -> Response {
+    (
+        StatusCode::CREATED,
+        [(header::LOCATION, format!("/cookbook/recipe/{}", id))],
+        Json(id),
+    )
+        .into_response()
+}
+
+/// Deletes a recipe.
+#[utoipa::path(
+    delete,
+    path = "/cookbook/recipe/{id}",
+    params(("id" = Uuid, Path, description = "Id of the recipe")),
+    responses(
+        (status = 204, description = "Recipe deleted"),
+        (status = 404, description = "Recipe not found"),
+    )
+)]
+async fn recipe_delete(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let removed = state
+        .write()
+        .await
+        .remove(&id)
+        .await
+        .map_err(internal_error)?;
+    match removed {
+        RemoveResult::Removed => Ok(StatusCode::NO_CONTENT),
+        RemoveResult::NotFound => Err((StatusCode::NOT_FOUND, "recipe not found".to_owned())),
+    }
+}
+
+async fn recipe_share(State(_state): State<AppState>) {}
+
+#[cfg(test)]
+mod test {
+    mod assertion;
+
+    use super::*;
+    use assertion::{ResponseValidationError, ResponseValidator};
+    use axum::{
+        body::Body,
+        http::{request, HeaderValue, Method, Request},
+    };
+    use recipers::{
+        repository::{RepositoryEvent, SequentialIds},
+        testing::sample_recipes,
+        Stored,
+    };
+    use serde::Serialize;
+    use tokio::sync::broadcast;
+    use tower::ServiceExt;
+
+    /// Test environment for the handlers of the cookbook service.
+    ///
+    /// The testbed routes requests directly into the router without
+    /// opening a network connection. Its repository numbers the
+    /// inserted recipes from 1, so tests know their ids in advance.
+    struct Testbed {
+        repository: AppState,
+    }
+
+    impl Testbed {
+        fn new() -> Testbed {
+            Testbed {
+                repository: Arc::new(RwLock::new(memory::Repository::with_id_generator(
+                    SequentialIds::new(),
+                ))),
+            }
+        }
+
+        /// Inserts the recipes into the repository of the testbed.
+        async fn given(self, recipes: &[Recipe]) -> Testbed {
+            {
+                let mut repository = self.repository.write().await;
+                for recipe in recipes {
+                    repository.insert(recipe).await.unwrap();
+                }
+            }
+            self
+        }
+
+        async fn send(&self, request: Request<Body>) -> ResponseValidator {
+            let response = router(self.repository.clone(), DEFAULT_BODY_LIMIT)
+                .oneshot(request)
+                .await
+                .unwrap();
+
+            ResponseValidator::new(response)
+        }
+
+        /// Starts a request which is sent to the router of the
+        /// testbed.
+        fn request(&self, method: Method, uri: &str) -> TestRequest<'_> {
+            TestRequest {
+                testbed: self,
+                builder: Request::builder().method(method).uri(uri),
+                body: Body::empty(),
+            }
+        }
+
+        fn get(&self, uri: &str) -> TestRequest<'_> {
+            self.request(Method::GET, uri)
+        }
+
+        fn post<T: Serialize>(&self, uri: &str, body: &T) -> TestRequest<'_> {
+            self.request(Method::POST, uri).json(body)
+        }
+
+        fn put<T: Serialize>(&self, uri: &str, body: &T) -> TestRequest<'_> {
+            self.request(Method::PUT, uri).json(body)
+        }
+
+        fn patch<T: Serialize>(&self, uri: &str, body: &T) -> TestRequest<'_> {
+            self.request(Method::PATCH, uri).json(body)
+        }
+
+        fn delete<T: Serialize>(&self, uri: &str, body: &T) -> TestRequest<'_> {
+            self.request(Method::DELETE, uri).json(body)
+        }
+    }
+
+    /// A request under construction, created by [Testbed::request].
+    struct TestRequest<'a> {
+        testbed: &'a Testbed,
+        builder: request::Builder,
+        body: Body,
+    }
+
+    /// Returns a `Range` header selecting the recipes from `start` to
+    /// `end` of the table of contents, both included.
+    ///
+    /// The server counts recipes, not bytes, but expects the unit of
+    /// byte ranges.
+    fn item_range(start: u64, end: u64) -> HeaderValue {
+        HeaderValue::from_str(&format!("bytes={}-{}", start, end)).unwrap()
+    }
+
+    impl TestRequest<'_> {
+        fn header<V>(mut self, name: header::HeaderName, value: V) -> Self
+        where
+            HeaderValue: TryFrom<V>,
+            <HeaderValue as TryFrom<V>>::Error: std::fmt::Debug,
+        {
+            let value = HeaderValue::try_from(value).unwrap();
+            self.builder = self.builder.header::<_, HeaderValue>(name, value);
+            self
+        }
+
+        /// Sends the text as body of the given content type.
+        fn text(mut self, content_type: &str, body: &str) -> Self {
+            self.body = Body::from(body.to_owned());
+            self.header(header::CONTENT_TYPE, content_type)
+        }
+
+        /// Sends the value as JSON body.
+        fn json<T: Serialize>(mut self, body: &T) -> Self {
+            self.body = Body::from(serde_json::to_vec(body).unwrap());
+            self.header(header::CONTENT_TYPE, "application/json")
+        }
+
+        async fn send(self) -> ResponseValidator {
+            let request = self.builder.body(self.body).unwrap();
+            self.testbed.send(request).await
+        }
+    }
+
+    #[test]
+    fn body_error_shows_diff() {
+        let error = ResponseValidationError::Body {
+            want: "{\n  a: 1,\n  b: 2,\n}".into(),
+            got: "{\n  a: 1,\n  b: 3,\n}".into(),
+        };
+
+        assert_eq!(
+            "unexpected body (- want, + got):\n  {\n    a: 1,\n-   b: 2,\n+   b: 3,\n  }\n",
+            error.to_string()
+        );
+    }
+
+    /// Returns the number of requests to the route answered with the
+    /// status, as scraped from the metrics endpoint.
+    #[cfg(feature = "metrics")]
+    async fn requests_total(
+        testbed: &Testbed,
+        route: &str,
+        status: StatusCode,
+    ) -> Result<u64, ResponseValidationError> {
+        let text = testbed
+            .get("/metrics")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .string()
+            .await?;
+
+        let labels = [
+            "method=\"GET\"".to_string(),
+            format!("path=\"{}\"", route),
+            format!("status=\"{}\"", status.as_u16()),
+        ];
+        let total = text
+            .lines()
+            .filter(|line| line.starts_with("http_requests_total{"))
+            .find(|line| labels.iter().all(|label| line.contains(label.as_str())))
+            .and_then(|line| line.rsplit(' ').next())
+            .map(|value| value.parse().unwrap())
+            .unwrap_or(0);
+        Ok(total)
+    }
+
+    #[cfg(feature = "metrics")]
+    #[tokio::test]
+    async fn metrics_count_requests() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let route = "/cookbook/recipe/:id";
+        let before = requests_total(&testbed, route, StatusCode::NOT_FOUND).await?;
+
+        testbed
+            .get(&format!("/cookbook/recipe/{}", Uuid::new_v4()))
+            .send()
+            .await
+            .status(StatusCode::NOT_FOUND)?;
+
+        // Other tests share the metrics and may have counted requests
+        // in the meantime.
+        let after = requests_total(&testbed, route, StatusCode::NOT_FOUND).await?;
+        assert!(
+            after > before,
+            "{} requests before, {} after",
+            before,
+            after
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_openapi() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+
+        let request = Request::get("/openapi.json").body(Body::empty()).unwrap();
+
+        let document: serde_json::Value = testbed
+            .send(request)
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+
+        let paths = &document["paths"];
+        assert!(paths["/cookbook/recipe"]["get"].is_object());
+        assert!(paths["/cookbook/recipe"]["post"].is_object());
+        assert!(paths["/cookbook/recipe/{id}"]["get"].is_object());
+        assert!(paths["/cookbook/recipe/{id}"]["put"].is_object());
+        assert!(document["components"]["schemas"]["Recipe"].is_object());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_empty() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+
+        let request = Request::get("/cookbook/recipe")
+            .header(header::RANGE, item_range(0, 9))
+            .body(Body::empty())
+            .unwrap();
+
+        let toc: serde_json::Value = testbed
+            .send(request)
+            .await
+            .status(StatusCode::OK)?
+            .header_absent(header::CONTENT_RANGE)?
+            .extract()
+            .await?;
+
+        assert_eq!(toc["total"], 0);
+        assert_eq!(toc["content"].as_array().unwrap().len(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_filled() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        let request = Request::get("/cookbook/recipe")
+            .header(header::RANGE, item_range(0, 9))
+            .body(Body::empty())
+            .unwrap();
+
+        let toc: serde_json::Value = testbed
+            .send(request)
+            .await
+            .status(StatusCode::PARTIAL_CONTENT)?
+            .header_eq(header::CONTENT_RANGE, "items 0-9/100")?
+            .extract()
+            .await?;
+
+        assert_eq!(toc["total"], 100);
+        assert_eq!(toc["content"].as_array().unwrap().len(), 10);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_complete_range() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(5)).await;
+
+        let request = Request::get("/cookbook/recipe")
+            .header(header::RANGE, item_range(0, 9))
+            .body(Body::empty())
+            .unwrap();
+
+        testbed
+            .send(request)
+            .await
+            .status(StatusCode::OK)?
+            .header_absent(header::CONTENT_RANGE)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_partial() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        let request = Request::get("/cookbook/recipe")
+            .header(header::RANGE, item_range(10, 19))
+            .body(Body::empty())
+            .unwrap();
+
+        let toc: serde_json::Value = testbed
+            .send(request)
+            .await
+            .status(StatusCode::PARTIAL_CONTENT)?
+            .header_eq(header::CONTENT_RANGE, "items 10-19/100")?
+            .extract()
+            .await?;
+
+        assert_eq!(toc["content"].as_array().unwrap().len(), 10);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_unsatisfiable() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(10)).await;
+
+        let request = Request::get("/cookbook/recipe")
+            .header(header::RANGE, item_range(500, 600))
+            .body(Body::empty())
+            .unwrap();
+
+        testbed
+            .send(request)
+            .await
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)?
+            .header_eq(header::CONTENT_RANGE, "items */10")?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_recipes() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(10)).await;
+
+        let toc: serde_json::Value = testbed
+            .get("/cookbook/recipe")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+
+        let mut ids: Vec<serde_json::Value> = toc["content"].as_array().unwrap()[..4]
+            .iter()
+            .map(|summary| summary["id"].clone())
+            .collect();
+        ids.push(serde_json::json!(Uuid::new_v4()));
+
+        let removed: u64 = testbed
+            .delete("/cookbook/recipe", &ids)
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+
+        assert_eq!(removed, 4);
+        assert_eq!(
+            testbed
+                .repository
+                .read()
+                .await
+                .list(&Page::all(), &Filter::new())
+                .await
+                .unwrap()
+                .content()
+                .len(),
+            6
+        );
+        Ok(())
+    }
+
+    /// A repository whose backend is never reachable.
+    struct Unavailable;
+
+    impl Unavailable {
+        fn error() -> RepositoryError {
+            RepositoryError::Backend(Box::new(std::io::Error::other("unreachable")))
+        }
+    }
+
+    #[async_trait]
+    impl Repository for Unavailable {
+        async fn insert(&mut self, _: &Recipe) -> Result<Uuid, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn insert_unique(&mut self, _: &Recipe) -> Result<InsertOutcome, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn insert_with_id(&mut self, _: &Uuid, _: &Recipe) -> Result<(), RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn list(&self, _: &Page, _: &Filter) -> Result<TableOfContents, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn get(&self, _: &Uuid) -> Result<Option<Stored<Recipe>>, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn exists(&self, _: &Uuid) -> Result<bool, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn count(&self) -> Result<u64, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn find_by_title(&self, _: &str) -> Result<Vec<(Uuid, Recipe)>, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn remove(&mut self, _: &Uuid) -> Result<RemoveResult, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn for_each(
+            &self,
+            _: &mut (dyn for<'r> FnMut(&'r Uuid, &'r Recipe) + Send),
+        ) -> Result<(), RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn import_all(&mut self, _: &[(Uuid, Recipe)]) -> Result<usize, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn remove_all(&mut self, _: &[Uuid]) -> Result<u64, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn clear(&mut self) -> Result<u64, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn update(&mut self, _: &Uuid, _: &Recipe) -> Result<UpdateResult, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        async fn update_if_match(
+            &mut self,
+            _: &Uuid,
+            _: &Recipe,
+            _: u64,
+        ) -> Result<UpdateResult, RepositoryError> {
+            Err(Unavailable::error())
+        }
+
+        fn watch(&self) -> broadcast::Receiver<RepositoryEvent> {
+            // Nothing changes, so the sender is dropped at once.
+            broadcast::channel(1).1
+        }
+    }
+
+    #[tokio::test]
+    async fn router_with_other_repository() -> Result<(), ResponseValidationError> {
+        let router = router_with(Unavailable);
+
+        let request = Request::get(format!("/cookbook/recipe/{}", Uuid::new_v4()))
+            .body(Body::empty())
+            .unwrap();
+        ResponseValidator::new(router.clone().oneshot(request).await.unwrap())
+            .status(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        let request = Request::get("/cookbook/recipe")
+            .body(Body::empty())
+            .unwrap();
+        ResponseValidator::new(router.oneshot(request).await.unwrap())
+            .status(StatusCode::INTERNAL_SERVER_ERROR)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_recipe() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(1)).await;
+        let id = Uuid::new_v4();
+        testbed
+            .repository
+            .write()
+            .await
+            .insert_with_id(&id, &sample_recipes(2)[1])
+            .await
+            .unwrap();
+        let uri = format!("/cookbook/recipe/{}", id);
+
+        testbed
+            .request(Method::DELETE, &uri)
+            .send()
+            .await
+            .status(StatusCode::NO_CONTENT)?;
+        testbed
+            .get(&uri)
+            .send()
+            .await
+            .status(StatusCode::NOT_FOUND)?;
+        testbed
+            .request(Method::DELETE, &uri)
+            .send()
+            .await
+            .status(StatusCode::NOT_FOUND)?;
+
+        assert_eq!(1, testbed.repository.read().await.count().await.unwrap());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_recipe_not_found() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(1)).await;
+
+        let request = Request::get(format!("/cookbook/recipe/{}", Uuid::new_v4()))
+            .body(Body::empty())
+            .unwrap();
+
+        testbed
+            .send(request)
+            .await
+            .status(StatusCode::NOT_FOUND)?
+            .content_type("text/plain")?
+            .body_len("recipe not found".len())
+            .await?
+            .text("recipe not found")
+            .await
+    }
+
+    #[tokio::test]
+    async fn head_recipe() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(1)).await;
+
+        for (id, status) in [
+            (Uuid::from_u128(1), StatusCode::OK),
+            (Uuid::from_u128(2), StatusCode::NOT_FOUND),
+        ] {
+            testbed
+                .request(Method::HEAD, &format!("/cookbook/recipe/{}", id))
+                .send()
+                .await
+                .status(status)?
+                .body_len(0)
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn post_recipe() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let recipe = &sample_recipes(1)[0];
+
+        let id: Uuid = testbed
+            .post("/cookbook/recipe", recipe)
+            .send()
+            .await
+            .status(StatusCode::CREATED)?
+            .header_eq(
+                header::LOCATION,
+                "/cookbook/recipe/00000000-0000-0000-0000-000000000001",
+            )?
+            .extract()
+            .await?;
+        assert_eq!(Uuid::from_u128(1), id);
+
+        testbed
+            .get(&format!("/cookbook/recipe/{}", id))
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .body(recipe)
+            .await
+    }
+
+    #[tokio::test]
+    async fn post_recipe_with_random_id() -> Result<(), ResponseValidationError> {
+        // Unlike the one of Testbed::new this repository chooses random
+        // ids, so the location is not known in advance.
+        let testbed = Testbed {
+            repository: Arc::new(RwLock::new(memory::Repository::new())),
+        };
+
+        let id: Uuid = testbed
+            .post("/cookbook/recipe", &sample_recipes(1)[0])
+            .send()
+            .await
+            .status(StatusCode::CREATED)?
+            .header_present(header::LOCATION)?
+            .extract()
+            .await?;
+        assert_eq!(Some(uuid::Version::Random), id.get_version());
+
+        testbed
+            .get(&format!("/cookbook/recipe/{}", id))
+            .send()
+            .await
+            .status(StatusCode::OK)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn post_recipe_duplicate() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let recipe = &sample_recipes(1)[0];
+
+        let id: Uuid = testbed
+            .post("/cookbook/recipe", recipe)
+            .send()
+            .await
+            .status(StatusCode::CREATED)?
+            .extract()
+            .await?;
+
+        testbed
+            .post("/cookbook/recipe", recipe)
+            .send()
+            .await
+            .status(StatusCode::CONFLICT)?
+            .text(&format!("recipe {} is the same", id))
+            .await?;
+
+        let toc: serde_json::Value = testbed
+            .get("/cookbook/recipe")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+        assert_eq!(1, toc["total"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn post_and_put_invalid_recipe() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let recipe = serde_json::json!({
+            "title": "",
+            "servings": 0,
+            "preparation": "Alles verrühren.",
+            "ingredients": [{"name": "", "quantity": "0", "unit": "g"}],
+        });
+        let want = serde_json::json!([
+            {"field": "title", "code": "required"},
+            {"field": "servings", "code": "not_positive"},
+            {"field": "ingredients[0].name", "code": "required"},
+            {"field": "ingredients[0].quantity", "code": "not_positive"},
+        ]);
+
+        let requests = [
+            testbed.post("/cookbook/recipe", &recipe),
+            testbed.put(&format!("/cookbook/recipe/{}", Uuid::new_v4()), &recipe),
+        ];
+        for request in requests {
+            request
+                .send()
+                .await
+                .status(StatusCode::UNPROCESSABLE_ENTITY)?
+                .body(&want)
+                .await?;
+        }
+
+        let toc: serde_json::Value = testbed
+            .get("/cookbook/recipe")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+        assert_eq!(0, toc["total"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_oversized_body() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let recipe = serde_json::json!({
+            "title": "Pfannkuchen",
+            "servings": 4,
+            "preparation": "Rühren. ".repeat(DEFAULT_BODY_LIMIT / 8),
+            "ingredients": [],
+        });
+        let length = serde_json::to_vec(&recipe).unwrap().len();
+        assert!(length > DEFAULT_BODY_LIMIT);
+
+        let requests = [
+            testbed.post("/cookbook/recipe", &recipe),
+            // Rejected before reading the body.
+            testbed
+                .post("/cookbook/recipe", &recipe)
+                .header(header::CONTENT_LENGTH, &length.to_string()),
+            testbed.put(&format!("/cookbook/recipe/{}", Uuid::new_v4()), &recipe),
+            testbed.post("/cookbook/recipe/import", &[&recipe]),
+        ];
+        for request in requests {
+            request.send().await.status(StatusCode::PAYLOAD_TOO_LARGE)?;
+        }
+
+        let toc: serde_json::Value = testbed
+            .get("/cookbook/recipe")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+        assert_eq!(0, toc["total"]);
+        Ok(())
+    }
+
+    /// Returns the bundled recipe for pancakes, which sets most of the
+    /// fields of a recipe.
+    fn pancakes() -> Recipe {
+        let mut recipes: Vec<Recipe> = serde_json::from_str(seed::RECIPES).unwrap();
+        recipes.remove(1)
+    }
+
+    #[cfg(feature = "yaml")]
+    #[tokio::test]
+    async fn get_recipe_as_yaml() -> Result<(), ResponseValidationError> {
+        let recipe = pancakes();
+        let testbed = Testbed::new().given(std::slice::from_ref(&recipe)).await;
+
+        let text = testbed
+            .get(&format!("/cookbook/recipe/{}", Uuid::from_u128(1)))
+            .header(header::ACCEPT, "application/yaml")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .content_type("application/yaml")?
+            .string()
+            .await?;
+
+        assert_eq!(recipe, serde_yaml::from_str::<Recipe>(&text).unwrap());
+        Ok(())
+    }
+
+    #[cfg(feature = "toml")]
+    #[tokio::test]
+    async fn get_recipe_as_toml() -> Result<(), ResponseValidationError> {
+        let recipe = pancakes();
+        let testbed = Testbed::new().given(std::slice::from_ref(&recipe)).await;
+
+        let text = testbed
+            .get(&format!("/cookbook/recipe/{}", Uuid::from_u128(1)))
+            .header(header::ACCEPT, "application/toml")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .content_type("application/toml")?
+            .string()
+            .await?;
+
+        assert_eq!(recipe, toml::from_str::<Recipe>(&text).unwrap());
+        Ok(())
+    }
+
+    #[cfg(feature = "yaml")]
+    #[tokio::test]
+    async fn post_and_put_yaml_recipe() -> Result<(), ResponseValidationError> {
+        let yaml = "
+title: Pfannkuchen
+servings: 4
+preparation: Alles verrühren und ausbacken.
+ingredients:
+  - name: Mehl
+    quantity: 250
+    unit: g
+  - name: Milch
+    quantity: ½
+    unit: l
+tags: [süß]
+difficulty: easy
+";
+        let json = serde_json::json!({
+            "title": "Pfannkuchen",
+            "servings": 4,
+            "preparation": "Alles verrühren und ausbacken.",
+            "ingredients": [
+                {"name": "Mehl", "quantity": "250", "unit": "g"},
+                {"name": "Milch", "quantity": "1/2", "unit": "l"},
+            ],
+            "tags": ["süß"],
+            "difficulty": "easy",
+        });
+        let want: Recipe = serde_json::from_value(json.clone()).unwrap();
+
+        let testbed = Testbed::new();
+        let posted: Uuid = testbed
+            .request(Method::POST, "/cookbook/recipe")
+            .text("application/yaml", yaml)
+            .send()
+            .await
+            .status(StatusCode::CREATED)?
+            .extract()
+            .await?;
+        let put = Uuid::new_v4();
+        testbed
+            .request(Method::PUT, &format!("/cookbook/recipe/{}", put))
+            .text("application/yaml; charset=utf-8", yaml)
+            .send()
+            .await
+            .status(StatusCode::CREATED)?;
+
+        let from_json = Uuid::new_v4();
+        testbed
+            .put(&format!("/cookbook/recipe/{}", from_json), &json)
+            .send()
+            .await
+            .status(StatusCode::CREATED)?;
+
+        for id in [posted, put, from_json] {
+            testbed
+                .get(&format!("/cookbook/recipe/{}", id))
+                .send()
+                .await
+                .status(StatusCode::OK)?
+                .body(&want)
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "yaml")]
+    #[tokio::test]
+    async fn post_invalid_yaml() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let bodies = ["title: [Pfannkuchen", "title: Pfannkuchen\nservings: viele"];
+
+        for body in bodies {
+            testbed
+                .request(Method::POST, "/cookbook/recipe")
+                .text("application/yaml", body)
+                .send()
+                .await
+                .status(StatusCode::BAD_REQUEST)?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn post_unsupported_content_type() -> Result<(), ResponseValidationError> {
+        Testbed::new()
+            .request(Method::POST, "/cookbook/recipe")
+            .text("text/plain", "Pfannkuchen")
+            .send()
+            .await
+            .status(StatusCode::UNSUPPORTED_MEDIA_TYPE)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_recipe_as_json_by_default() -> Result<(), ResponseValidationError> {
+        let recipe = pancakes();
+        let testbed = Testbed::new().given(std::slice::from_ref(&recipe)).await;
+        let uri = format!("/cookbook/recipe/{}", Uuid::from_u128(1));
+
+        let accepts = [
+            None,
+            Some("*/*"),
+            Some("text/html"),
+            Some("text/html, application/json;q=0.9"),
+        ];
+        for accept in accepts {
+            let mut request = testbed.get(&uri);
+            if let Some(accept) = accept {
+                request = request.header(header::ACCEPT, accept);
+            }
+            request
+                .send()
+                .await
+                .status(StatusCode::OK)?
+                .content_type("application/json")?
+                .body(&recipe)
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_scaled_recipe() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let recipe: Recipe = serde_json::from_value(serde_json::json!({
+            "title": "Pfannkuchen",
+            "servings": 4,
+            "yield": "12 Stück",
+            "preparation": "Alles verrühren und ausbacken.",
+            "ingredients": [
+                {"name": "Mehl", "quantity": "250", "unit": "g"},
+                {"name": "Eier", "quantity": "3", "unit": ""},
+            ],
+        }))
+        .unwrap();
+
+        let id: Uuid = testbed
+            .post("/cookbook/recipe", &recipe)
+            .send()
+            .await
+            .status(StatusCode::CREATED)?
+            .extract()
+            .await?;
+        let uri = format!("/cookbook/recipe/{}", id);
+
+        let scaled: serde_json::Value = testbed
+            .get(&format!("{}?servings=6", uri))
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+        assert_eq!(6, scaled["servings"]);
+        assert_eq!("12 Stück", scaled["yield"]);
+        assert_eq!("375", scaled["ingredients"][0]["quantity"]);
+        assert_eq!("4½", scaled["ingredients"][1]["quantity"]);
+
+        testbed
+            .get(&format!("{}?servings=0", uri))
+            .send()
+            .await
+            .status(StatusCode::BAD_REQUEST)?;
+
+        testbed
+            .get(&uri)
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .body(&recipe)
+            .await
+    }
+
+    #[tokio::test]
+    async fn get_scaled_recipe_too_large() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let recipe: Recipe = serde_json::from_value(serde_json::json!({
+            "title": "Großküche",
+            "servings": 2,
+            "preparation": "Teig rühren.",
+            "ingredients": [
+                {"name": "Mehl", "quantity": "9223372036854775807", "unit": "g"},
+            ],
+        }))
+        .unwrap();
+
+        let id: Uuid = testbed
+            .post("/cookbook/recipe", &recipe)
+            .send()
+            .await
+            .status(StatusCode::CREATED)?
+            .extract()
+            .await?;
+        let uri = format!("/cookbook/recipe/{}", id);
+
+        testbed
+            .get(&format!("{}?servings=6", uri))
+            .send()
+            .await
+            .status(StatusCode::BAD_REQUEST)?
+            .text("recipe cannot be scaled to 6 servings")
+            .await?;
+
+        testbed
+            .get(&uri)
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .body(&recipe)
+            .await
+    }
+
+    #[tokio::test]
+    async fn export_recipes() -> Result<(), ResponseValidationError> {
+        let recipes = sample_recipes(100);
+        let testbed = Testbed::new().given(&recipes).await;
+
+        let mut exported: Vec<Recipe> = testbed
+            .get("/cookbook/recipe/export")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .content_type("application/x-ndjson")?
+            .extract_lines()
+            .await?;
+
+        assert_eq!(recipes.len(), exported.len());
+        for recipe in &recipes {
+            let position = exported.iter().position(|exported| exported == recipe);
+            exported.swap_remove(position.expect("recipe is exported"));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn export_empty_cookbook() -> Result<(), ResponseValidationError> {
+        Testbed::new()
+            .get("/cookbook/recipe/export")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .content_type("application/x-ndjson")?
+            .text("")
+            .await
+    }
+
+    #[tokio::test]
+    async fn import_recipes() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let recipes = sample_recipes(100);
+
+        let ids: Vec<Uuid> = testbed
+            .post("/cookbook/recipe/import", &recipes)
+            .send()
+            .await
+            .status(StatusCode::CREATED)?
+            .extract()
+            .await?;
+        assert_eq!(100, ids.len());
+
+        testbed
+            .get(&format!("/cookbook/recipe/{}", ids[42]))
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .body(&recipes[42])
+            .await?;
+
+        let toc: serde_json::Value = testbed
+            .get("/cookbook/recipe")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+        assert_eq!(100, toc["total"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn import_recipes_partially() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let recipes = serde_json::json!([
+            sample_recipes(1)[0],
+            {"title": " ", "servings": 1, "preparation": "Kochen.", "ingredients": []},
+            sample_recipes(2)[1],
+        ]);
+
+        let results: serde_json::Value = testbed
+            .post("/cookbook/recipe/import", &recipes)
+            .send()
+            .await
+            .status(StatusCode::MULTI_STATUS)?
+            .extract()
+            .await?;
+
+        assert_eq!(201, results[0]["status"]);
+        assert!(results[0]["id"].is_string());
+        assert_eq!(422, results[1]["status"]);
+        assert_eq!("title", results[1]["errors"][0]["field"]);
+        assert!(results[1].get("id").is_none());
+        assert_eq!(201, results[2]["status"]);
+
+        let id = results[2]["id"].as_str().unwrap();
+        testbed
+            .get(&format!("/cookbook/recipe/{}", id))
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .body(&sample_recipes(2)[1])
+            .await
+    }
+
+    #[tokio::test]
+    async fn put_recipe_if_none_match() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let id = Uuid::new_v4();
+        let uri = format!("/cookbook/recipe/{}", id);
+        let recipe = &sample_recipes(1)[0];
+
+        let created: Uuid = testbed
+            .put(&uri, recipe)
+            .header(header::IF_NONE_MATCH, "*")
+            .send()
+            .await
+            .status(StatusCode::CREATED)?
+            .header_eq(header::LOCATION, uri.as_str())?
+            .extract()
+            .await?;
+
+        assert_eq!(created, id);
+
+        testbed
+            .put(&uri, recipe)
+            .header(header::IF_NONE_MATCH, "*")
+            .send()
+            .await
+            .status(StatusCode::PRECONDITION_FAILED)?
+            .text_contains("already exists")
+            .await
+    }
+
+    #[tokio::test]
+    async fn put_recipe() -> Result<(), ResponseValidationError> {
+        let recipes = sample_recipes(100);
+
+        for (existing, want) in [(false, StatusCode::CREATED), (true, StatusCode::OK)] {
+            let testbed = Testbed::new();
+            let id = Uuid::new_v4();
+            let uri = format!("/cookbook/recipe/{}", id);
+            if existing {
+                testbed
+                    .put(&uri, &recipes[0])
+                    .send()
+                    .await
+                    .status(StatusCode::CREATED)?;
+            }
+
+            let response = testbed.put(&uri, &recipes[1]).send().await.status(want)?;
+            if existing {
+                response.header_absent(header::LOCATION)?;
+            } else {
+                response.header_eq(header::LOCATION, uri.as_str())?;
+            }
+
+            testbed
+                .get(&uri)
+                .send()
+                .await
+                .status(StatusCode::OK)?
+                .body(&recipes[1])
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn options_list_allowed_methods() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let recipe = format!("/cookbook/recipe/{}", Uuid::new_v4());
+
+        for (uri, methods) in [
+            ("/cookbook/recipe", "GET, HEAD, POST, DELETE, OPTIONS"),
+            (&recipe, "GET, HEAD, PUT, DELETE, OPTIONS"),
+            ("/cookbook/recipe/export", "GET, HEAD, OPTIONS"),
+            ("/cookbook/recipe/import", "POST, OPTIONS"),
+        ] {
+            testbed
+                .request(Method::OPTIONS, uri)
+                .send()
+                .await
+                .status(StatusCode::NO_CONTENT)?
+                .header_eq(header::ALLOW, methods)?
+                .body_len(0)
+                .await?;
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn patch_recipe_not_allowed() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new();
+        let uri = format!("/cookbook/recipe/{}", Uuid::new_v4());
+
+        testbed
+            .patch(&uri, &serde_json::json!({"servings": 2}))
+            .send()
+            .await
+            .status(StatusCode::METHOD_NOT_ALLOWED)?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn repository_unlocked_after_panic() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(10)).await;
+
+        let repository = testbed.repository.clone();
+        let panicked = tokio::spawn(async move {
+            let _guard = repository.write().await;
+            panic!("handler failed while holding the lock");
+        })
+        .await;
+
+        assert!(panicked.is_err());
+
+        let request = Request::get("/cookbook/recipe")
+            .body(Body::empty())
+            .unwrap();
+
+        let toc: serde_json::Value = testbed
+            .send(request)
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+
+        assert_eq!(toc["total"], 10);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_without_range() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        let request = Request::get("/cookbook/recipe")
+            .body(Body::empty())
+            .unwrap();
+
+        let toc: serde_json::Value = testbed
+            .send(request)
+            .await
+            .status(StatusCode::OK)?
+            .header_absent(header::CONTENT_RANGE)?
+            .extract()
+            .await?;
+
+        assert_eq!(toc["content"].as_array().unwrap().len(), 100);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_multiple_ranges() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        testbed
+            .get("/cookbook/recipe")
+            .header(header::RANGE, "bytes=0-9,20-29")
+            .send()
+            .await
+            .status(StatusCode::BAD_REQUEST)?
+            .text_contains("multiple ranges")
+            .await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_unsupported_ranges() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(10)).await;
+
+        for (range, message) in [
+            ("bytes=-5", "suffix ranges"),
+            ("bytes=abc", "malformed range"),
+        ] {
+            testbed
+                .get("/cookbook/recipe")
+                .header(header::RANGE, range)
+                .send()
+                .await
+                .status(StatusCode::BAD_REQUEST)?
+                .text_contains(message)
+                .await?;
+        }
+
+        testbed
+            .get("/cookbook/recipe")
+            .header(header::RANGE, item_range(9, 5))
+            .send()
+            .await
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)?
+            .header_eq(header::CONTENT_RANGE, "items */10")?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_fulltext() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        // Only the preparation of "Recipe 42" mentions "recipe 42".
+        for (uri, want) in [
+            ("/cookbook/recipe?q=of+recipe+42", 0),
+            ("/cookbook/recipe?q=of+recipe+42&fulltext=true", 1),
+            ("/cookbook/recipe?q=Preparation&fulltext=true", 100),
+            (
+                "/cookbook/recipe?q=preparation&fulltext=true&case_sensitive=true",
+                0,
+            ),
+        ] {
+            let toc: serde_json::Value = testbed
+                .get(uri)
+                .send()
+                .await
+                .status(StatusCode::OK)?
+                .extract()
+                .await?;
+            assert_eq!(want, toc["filtered"], "{}", uri);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_highlight() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        for (uri, want) in [
+            ("/cookbook/recipe?q=recipe+42", serde_json::json!([0, 9])),
+            (
+                "/cookbook/recipe?q=ipe+42&fulltext=true",
+                serde_json::json!([3, 9]),
+            ),
+            // Only the preparation matches.
+            (
+                "/cookbook/recipe?q=of+recipe+42&fulltext=true",
+                serde_json::json!(null),
+            ),
+        ] {
+            let toc: serde_json::Value = testbed
+                .get(uri)
+                .send()
+                .await
+                .status(StatusCode::OK)?
+                .extract()
+                .await?;
+            assert_eq!(want, toc["content"][0]["highlight"], "{}", uri);
+        }
+
+        let toc: serde_json::Value = testbed
+            .get("/cookbook/recipe")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+        assert_eq!(None, toc["content"][0].get("highlight"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_search_case() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        for (uri, want) in [
+            ("/cookbook/recipe?q=recipe+1", 11),
+            ("/cookbook/recipe?q=recipe+1&case_sensitive=false", 11),
+            ("/cookbook/recipe?q=recipe+1&case_sensitive=true", 0),
+            ("/cookbook/recipe?q=Recipe+1&case_sensitive=true", 11),
+        ] {
+            let toc: serde_json::Value = testbed
+                .get(uri)
+                .send()
+                .await
+                .status(StatusCode::OK)?
+                .extract()
+                .await?;
+            assert_eq!(toc["filtered"], want, "{}", uri);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_search_counts() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        let toc: serde_json::Value = testbed
+            .get("/cookbook/recipe?q=Recipe+1")
+            .send()
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+        assert_eq!(toc["total"], 100);
+        assert_eq!(toc["filtered"], 11);
+
+        testbed
+            .get("/cookbook/recipe?q=Recipe+1")
+            .header(header::RANGE, item_range(0, 4))
+            .send()
+            .await
+            .status(StatusCode::PARTIAL_CONTENT)?
+            .header_eq(header::CONTENT_RANGE, "items 0-4/11")?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_search() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        for uri in [
+            "/cookbook/recipe?q=Recipe%201",
+            "/cookbook/recipe?q=Recipe+1",
+        ] {
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+
+            let toc: serde_json::Value = testbed
+                .send(request)
+                .await
+                .status(StatusCode::OK)?
+                .extract()
+                .await?;
+
+            let mut titles: Vec<&str> = toc["content"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|summary| summary["title"].as_str().unwrap())
+                .collect();
+            titles.sort();
+
+            let mut want = vec!["Recipe 1".to_string()];
+            want.extend((10..20).map(|n| format!("Recipe {}", n)));
+            assert_eq!(titles, want, "{}", uri);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_search_is_anchored() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        let request = Request::get("/cookbook/recipe?q=cipe")
+            .body(Body::empty())
+            .unwrap();
+
+        let toc: serde_json::Value = testbed
+            .send(request)
+            .await
+            .status(StatusCode::OK)?
+            .extract()
+            .await?;
+
+        assert_eq!(toc["content"].as_array().unwrap().len(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_compressed() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        let request = Request::get("/cookbook/recipe")
+            .header(header::RANGE, item_range(0, 99))
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+
+        testbed
+            .send(request)
+            .await
+            .status(StatusCode::OK)?
+            .content_type("application/json")?
+            .header(|headers| {
+                headers
+                    .get(header::CONTENT_ENCODING)
+                    .is_some_and(|encoding| encoding == "gzip")
+            })?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn get_toc_uncompressed() -> Result<(), ResponseValidationError> {
+        let testbed = Testbed::new().given(&sample_recipes(100)).await;
+
+        let request = Request::get("/cookbook/recipe")
+            .header(header::RANGE, item_range(0, 99))
+            .body(Body::empty())
+            .unwrap();
+
+        testbed
+            .send(request)
+            .await
+            .status(StatusCode::OK)?
+            .header_absent(header::CONTENT_ENCODING)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn seed_flag() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let seed = |args: Vec<String>| options(args.into_iter()).map(|options| options.seed);
+
+        assert!(seed(args(&[])).unwrap().is_none());
+        assert_eq!(
+            Some(seed::RECIPES),
+            seed(args(&["--seed"])).unwrap().as_deref()
+        );
+        assert!(seed(args(&["--seed=/does/not/exist.json"])).is_err());
+        assert!(seed(args(&["--seeds"])).is_err());
+        assert!(seed(args(&["--verbose"])).is_err());
+    }
+
+    #[test]
+    fn body_limit_flag() {
+        let body_limit = |args: &[&str]| {
+            options(args.iter().map(|arg| arg.to_string())).map(|options| options.body_limit)
+        };
+
+        assert_eq!(DEFAULT_BODY_LIMIT, body_limit(&[]).unwrap());
+        assert_eq!(1024, body_limit(&["--body-limit=1024"]).unwrap());
+        assert_eq!(1024, body_limit(&["--seed", "--body-limit=1024"]).unwrap());
+        assert!(body_limit(&["--body-limit=1k"]).is_err());
+        assert!(body_limit(&["--body-limit"]).is_err());
+    }
+}
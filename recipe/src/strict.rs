@@ -0,0 +1,117 @@
+use crate::{deserialize_tags, Category, Difficulty, ImageRef, Ingredient, Recipe};
+use serde::{Deserialize, Deserializer};
+
+/// Mirror of [Recipe] which rejects unknown fields.
+///
+/// The conversion into a recipe lists every field, so a field added
+/// to [Recipe] but not here does not compile.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct StrictRecipe {
+    title: String,
+    #[serde(default)]
+    preparation: String,
+    servings: u8,
+    #[serde(default, rename = "yield")]
+    yield_description: Option<String>,
+    ingredients: Vec<Ingredient>,
+    #[serde(default, deserialize_with = "deserialize_tags")]
+    tags: Vec<String>,
+    #[serde(default)]
+    prep_time_minutes: Option<u32>,
+    #[serde(default)]
+    cook_time_minutes: Option<u32>,
+    #[serde(default)]
+    images: Vec<ImageRef>,
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    difficulty: Option<Difficulty>,
+    #[serde(default)]
+    category: Option<Category>,
+}
+
+impl From<StrictRecipe> for Recipe {
+    fn from(strict: StrictRecipe) -> Self {
+        Recipe {
+            title: strict.title,
+            preparation: strict.preparation,
+            servings: strict.servings,
+            yield_description: strict.yield_description,
+            ingredients: strict.ingredients,
+            tags: strict.tags,
+            prep_time_minutes: strict.prep_time_minutes,
+            cook_time_minutes: strict.cook_time_minutes,
+            images: strict.images,
+            source: strict.source,
+            author: strict.author,
+            difficulty: strict.difficulty,
+            category: strict.category,
+        }
+    }
+}
+
+impl Recipe {
+    /// Deserializes a recipe and fails on unknown fields.
+    ///
+    /// The lenient [Deserialize] implementation silently drops
+    /// misspelled fields like "serivngs". This variant reports them
+    /// instead. Only the fields of the recipe itself are checked.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use recipers::Recipe;
+    ///
+    /// let json = r#"{"title": "Tee", "serivngs": 1, "ingredients": []}"#;
+    /// let mut deserializer = serde_json::Deserializer::from_str(json);
+    /// let error = Recipe::deserialize_strict(&mut deserializer).unwrap_err();
+    /// assert!(error.to_string().contains("serivngs"));
+    /// ```
+    pub fn deserialize_strict<'de, D>(deserializer: D) -> Result<Recipe, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        StrictRecipe::deserialize(deserializer).map(Recipe::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Recipe;
+    use spucky::spec;
+
+    fn strict(json: &str) -> serde_json::Result<Recipe> {
+        Recipe::deserialize_strict(&mut serde_json::Deserializer::from_str(json))
+    }
+
+    #[test]
+    fn strict_accepts_fixture() {
+        let json = include_str!("fixture/lasagne.json");
+        let lenient: Recipe = serde_json::from_str(json).unwrap();
+
+        assert_eq!(lenient, strict(json).unwrap());
+    }
+
+    spec! {
+        strict_rejects_unknown_field {
+            case misspelled {
+                let json = r#"{"title": "Tee", "servings": 1, "serivngs": 4, "ingredients": []}"#;
+                let field = "serivngs";
+            }
+
+            case unknown {
+                let json = r#"{"title": "Tee", "servings": 1, "ingredients": [], "rating": 5}"#;
+                let field = "rating";
+            }
+
+            let error = strict(json).unwrap_err();
+            assert!(error.to_string().contains(field), "{}", error);
+
+            let lenient: Recipe = serde_json::from_str(json).unwrap();
+            assert_eq!("Tee", lenient.title);
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use axum::{
+    body::{boxed, Full},
+    http::{
+        header::{self, HeaderName},
+        HeaderMap, StatusCode,
+    },
+    response::Response,
+};
+use serde::de::DeserializeOwned;
+use std::{error, fmt, fmt::Debug};
+
+/// Checks the properties of a response in a chain of assertions.
+///
+/// Each assertion consumes the validator and returns it again on
+/// success, so the checks can be combined with the `?` operator.
+pub struct ResponseValidator {
+    response: Response,
+}
+
+impl ResponseValidator {
+    pub fn new(response: Response) -> ResponseValidator {
+        ResponseValidator { response }
+    }
+
+    /// Asserts the status code of the response.
+    pub fn status(self, want: StatusCode) -> Result<Self, ResponseValidationError> {
+        let got = self.response.status();
+        if got == want {
+            Ok(self)
+        } else {
+            Err(ResponseValidationError::Status { want, got })
+        }
+    }
+
+    /// Asserts that the headers of the response fulfill the predicate.
+    pub fn header<F>(self, predicate: F) -> Result<Self, ResponseValidationError>
+    where
+        F: FnOnce(&HeaderMap) -> bool,
+    {
+        if predicate(self.response.headers()) {
+            Ok(self)
+        } else {
+            Err(ResponseValidationError::Header)
+        }
+    }
+
+    /// Asserts that the response contains the header with the value.
+    pub fn header_eq(self, name: HeaderName, want: &str) -> Result<Self, ResponseValidationError> {
+        match self.response.headers().get(&name) {
+            Some(got) if got == want => Ok(self),
+            got => Err(ResponseValidationError::HeaderValue {
+                name,
+                want: want.to_string(),
+                got: got.map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned()),
+            }),
+        }
+    }
+
+    /// Asserts that the response contains the header.
+    pub fn header_present(self, name: HeaderName) -> Result<Self, ResponseValidationError> {
+        if self.response.headers().contains_key(&name) {
+            Ok(self)
+        } else {
+            Err(ResponseValidationError::HeaderMissing(name))
+        }
+    }
+
+    /// Asserts that the response does not contain the header.
+    pub fn header_absent(self, name: HeaderName) -> Result<Self, ResponseValidationError> {
+        if self.response.headers().contains_key(&name) {
+            Err(ResponseValidationError::HeaderUnexpected(name))
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Asserts the media type of the response.
+    ///
+    /// Parameters like `charset` are ignored.
+    pub fn content_type(self, want: &str) -> Result<Self, ResponseValidationError> {
+        let got = self
+            .response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| {
+                value
+                    .split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            });
+
+        match got {
+            Some(ref media_type) if media_type == want => Ok(self),
+            got => Err(ResponseValidationError::HeaderValue {
+                name: header::CONTENT_TYPE,
+                want: want.to_string(),
+                got,
+            }),
+        }
+    }
+
+    /// Asserts the number of bytes of the body as sent, that is after
+    /// compression.
+    ///
+    /// The body is buffered, so further assertions on it are
+    /// possible.
+    pub async fn body_len(self, want: usize) -> Result<Self, ResponseValidationError> {
+        let (parts, body) = self.response.into_parts();
+        let bytes = hyper::body::to_bytes(body)
+            .await
+            .map_err(|err| ResponseValidationError::Decode(err.to_string()))?;
+
+        if bytes.len() != want {
+            return Err(ResponseValidationError::Body {
+                want: format!("{} bytes", want),
+                got: format!("{} bytes", bytes.len()),
+            });
+        }
+
+        Ok(ResponseValidator::new(Response::from_parts(
+            parts,
+            boxed(Full::from(bytes)),
+        )))
+    }
+
+    /// Deserializes the JSON body of the response.
+    pub async fn extract<T>(self) -> Result<T, ResponseValidationError>
+    where
+        T: DeserializeOwned,
+    {
+        let bytes = self.bytes().await?;
+
+        serde_json::from_slice(&bytes)
+            .map_err(|err| ResponseValidationError::Decode(err.to_string()))
+    }
+
+    /// Deserializes a body of newline-delimited JSON, one value per
+    /// line.
+    pub async fn extract_lines<T>(self) -> Result<Vec<T>, ResponseValidationError>
+    where
+        T: DeserializeOwned,
+    {
+        let text = self.string().await?;
+
+        text.lines()
+            .map(|line| {
+                serde_json::from_str(line)
+                    .map_err(|err| ResponseValidationError::Decode(err.to_string()))
+            })
+            .collect()
+    }
+
+    /// Asserts that the JSON body of the response equals `want`.
+    ///
+    /// On mismatch the error shows a line diff of both values.
+    pub async fn body<T>(self, want: &T) -> Result<(), ResponseValidationError>
+    where
+        T: DeserializeOwned + PartialEq + Debug,
+    {
+        let got: T = self.extract().await?;
+        if &got == want {
+            Ok(())
+        } else {
+            Err(ResponseValidationError::Body {
+                want: format!("{:#?}", want),
+                got: format!("{:#?}", got),
+            })
+        }
+    }
+
+    /// Asserts that the body of the response is the text.
+    pub async fn text(self, want: &str) -> Result<(), ResponseValidationError> {
+        let got = self.string().await?;
+        if got == want {
+            Ok(())
+        } else {
+            Err(ResponseValidationError::Body {
+                want: want.to_string(),
+                got,
+            })
+        }
+    }
+
+    /// Asserts that the body of the response contains the text.
+    pub async fn text_contains(self, needle: &str) -> Result<(), ResponseValidationError> {
+        let got = self.string().await?;
+        if got.contains(needle) {
+            Ok(())
+        } else {
+            Err(ResponseValidationError::Body {
+                want: format!("text containing {:?}", needle),
+                got,
+            })
+        }
+    }
+
+    /// Returns the body of the response as text.
+    pub async fn string(self) -> Result<String, ResponseValidationError> {
+        let bytes = self.bytes().await?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|err| ResponseValidationError::Decode(err.to_string()))
+    }
+
+    async fn bytes(self) -> Result<hyper::body::Bytes, ResponseValidationError> {
+        hyper::body::to_bytes(self.response.into_body())
+            .await
+            .map_err(|err| ResponseValidationError::Decode(err.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub enum ResponseValidationError {
+    Status {
+        want: StatusCode,
+        got: StatusCode,
+    },
+    Header,
+    HeaderValue {
+        name: HeaderName,
+        want: String,
+        got: Option<String>,
+    },
+    HeaderMissing(HeaderName),
+    HeaderUnexpected(HeaderName),
+    /// The body differs from the expected one.
+    Body {
+        want: String,
+        got: String,
+    },
+    /// The body could not be read or deserialized.
+    Decode(String),
+}
+
+impl fmt::Display for ResponseValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResponseValidationError::Status { want, got } => {
+                write!(f, "unexpected status: want {}, got {}", want, got)
+            }
+            ResponseValidationError::Header => write!(f, "unexpected headers"),
+            ResponseValidationError::HeaderValue {
+                name,
+                want,
+                got: Some(got),
+            } => write!(
+                f,
+                "unexpected header {}: want {:?}, got {:?}",
+                name, want, got
+            ),
+            ResponseValidationError::HeaderValue {
+                name,
+                want,
+                got: None,
+            } => write!(f, "missing header {}: want {:?}", name, want),
+            ResponseValidationError::HeaderMissing(name) => write!(f, "missing header {}", name),
+            ResponseValidationError::HeaderUnexpected(name) => {
+                write!(f, "unexpected header {}", name)
+            }
+            ResponseValidationError::Body { want, got } => {
+                writeln!(f, "unexpected body (- want, + got):")?;
+                write_diff(f, want, got)
+            }
+            ResponseValidationError::Decode(reason) => write!(f, "unreadable body: {}", reason),
+        }
+    }
+}
+
+/// Writes both texts line by line. Equal lines are written once,
+/// differing lines are marked with "-" for `want` and "+" for `got`.
+fn write_diff(f: &mut fmt::Formatter<'_>, want: &str, got: &str) -> fmt::Result {
+    let mut want = want.lines();
+    let mut got = got.lines();
+
+    loop {
+        match (want.next(), got.next()) {
+            (None, None) => return Ok(()),
+            (Some(w), Some(g)) if w == g => writeln!(f, "  {}", w)?,
+            (w, g) => {
+                if let Some(w) = w {
+                    writeln!(f, "- {}", w)?;
+                }
+                if let Some(g) = g {
+                    writeln!(f, "+ {}", g)?;
+                }
+            }
+        }
+    }
+}
+
+impl error::Error for ResponseValidationError {}
@@ -0,0 +1,85 @@
+//! Recipes for tests, also of code using the cookbook.
+//!
+//! Only built with the `test-util` feature.
+
+use crate::{rat, Category, Difficulty, Ingredient, Rational, Recipe};
+
+/// Creates a valid Lasagne for 4 servings with two ingredients, 500 g
+/// pasta and 2 tomatoes.
+///
+/// # Example
+///
+/// ```rust
+/// use recipers::testing::lasagne;
+///
+/// assert_eq!(Ok(()), lasagne().validate());
+/// assert_eq!(2, lasagne().ingredients().len());
+/// ```
+pub fn lasagne() -> Recipe {
+    Recipe {
+        title: "Lasagne".into(),
+        preparation: "Nudeln kochen.\nSoße darüber.".into(),
+        servings: 4,
+        ingredients: vec![
+            Ingredient {
+                name: "Pasta".into(),
+                quantity: rat!(500),
+                unit: "g".into(),
+            },
+            Ingredient {
+                name: "Tomaten".into(),
+                quantity: rat!(2),
+                unit: "".into(),
+            },
+        ],
+        ..Default::default()
+    }
+}
+
+/// Creates `n` recipes with the titles "Recipe 0", "Recipe 1" and so
+/// on.
+///
+/// The recipes differ in servings, tags, difficulty and category in
+/// a fixed pattern, so filters select a predictable part of them:
+/// recipe `i` serves `i % 3 + 1`, is tagged "vegetarisch" unless
+/// `i % 3 == 2` and "schnell" if `i % 3 == 0`, has no difficulty if
+/// `i % 4 == 3`, and is a main course if `i % 5 == 0` and a dessert
+/// if `i % 5 == 1`.
+///
+/// # Example
+///
+/// ```rust
+/// use recipers::testing::sample_recipes;
+///
+/// let recipes = sample_recipes(100);
+/// assert_eq!(100, recipes.len());
+/// assert_eq!(1, recipes[42].servings());
+/// assert_eq!(2, recipes[43].servings());
+/// ```
+pub fn sample_recipes(n: usize) -> Vec<Recipe> {
+    (0..n)
+        .map(|i| Recipe {
+            title: format!("Recipe {}", i),
+            preparation: format!("Preparation of recipe {}", i),
+            servings: (i % 3) as u8 + 1,
+            ingredients: vec![],
+            tags: match i % 3 {
+                0 => vec!["vegetarisch".into(), "schnell".into()],
+                1 => vec!["vegetarisch".into()],
+                _ => vec![],
+            },
+            difficulty: match i % 4 {
+                0 => Some(Difficulty::Easy),
+                1 => Some(Difficulty::Medium),
+                2 => Some(Difficulty::Hard),
+                _ => None,
+            },
+            category: match i % 5 {
+                0 => Some(Category::Main),
+                1 => Some(Category::Dessert),
+                _ => None,
+            },
+            ..Default::default()
+        })
+        .collect()
+}
@@ -0,0 +1,167 @@
+use crate::Recipe;
+use std::fmt;
+
+/// Maximum width of a line of the preparation.
+const LINE_WIDTH: usize = 80;
+
+impl fmt::Display for Recipe {
+    /// Renders the recipe as plain text.
+    ///
+    /// The text contains the title, the yield or else the number of
+    /// servings, the indented list of ingredients and the preparation
+    /// wrapped at 80 columns. The alternate form `{:#}` adds the
+    /// difficulty, the times and the tags of the recipe if they are
+    /// known.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.title)?;
+        match &self.yield_description {
+            Some(description) => writeln!(f, "Ergibt: {}", description)?,
+            None => writeln!(f, "Portionen: {}", self.servings)?,
+        }
+
+        if f.alternate() {
+            if let Some(difficulty) = self.difficulty {
+                writeln!(f, "Schwierigkeit: {}", difficulty)?;
+            }
+            if let Some(minutes) = self.prep_time_minutes {
+                writeln!(f, "Vorbereitung: {} min", minutes)?;
+            }
+            if let Some(minutes) = self.cook_time_minutes {
+                writeln!(f, "Kochzeit: {} min", minutes)?;
+            }
+            if !self.tags.is_empty() {
+                writeln!(f, "Tags: {}", self.tags.join(", "))?;
+            }
+        }
+
+        if !self.ingredients.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Zutaten:")?;
+            for ingredient in &self.ingredients {
+                writeln!(f, "  {}", ingredient)?;
+            }
+        }
+
+        let paragraphs: Vec<&str> = self.steps().collect();
+        if !paragraphs.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Zubereitung:")?;
+            for paragraph in paragraphs {
+                for line in wrap(paragraph, LINE_WIDTH) {
+                    writeln!(f, "{}", line)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Breaks the text into lines of at most `width` characters.
+///
+/// Lines are only broken between words. A word longer than `width`
+/// gets a line of its own.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let length = line.chars().count();
+        if length > 0 && length + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::wrap;
+    use crate::{Difficulty, Recipe};
+    use spucky::spec;
+
+    #[test]
+    fn display_lasagne() {
+        let recipe: Recipe = serde_json::from_str(include_str!("fixture/lasagne.json")).unwrap();
+        let want = include_str!("fixture/lasagne.txt");
+
+        assert_eq!(want, recipe.to_string());
+    }
+
+    #[test]
+    fn display_alternate() {
+        let recipe = Recipe {
+            title: "Tee".into(),
+            servings: 1,
+            preparation: "Aufgießen.".into(),
+            prep_time_minutes: Some(1),
+            cook_time_minutes: Some(5),
+            tags: vec!["heiß".into(), "schnell".into()],
+            difficulty: Some(Difficulty::Easy),
+            ..Default::default()
+        };
+
+        let want = "Tee\nPortionen: 1\nSchwierigkeit: einfach\nVorbereitung: 1 min\nKochzeit: 5 min\nTags: heiß, schnell\n\nZubereitung:\nAufgießen.\n";
+        assert_eq!(want, format!("{:#}", recipe));
+        assert_eq!(
+            "Tee\nPortionen: 1\n\nZubereitung:\nAufgießen.\n",
+            recipe.to_string()
+        );
+    }
+
+    #[test]
+    fn display_yield() {
+        let recipe = Recipe {
+            title: "Blechkuchen".into(),
+            servings: 12,
+            yield_description: Some("1 Blech".into()),
+            ..Default::default()
+        };
+
+        assert_eq!("Blechkuchen\nErgibt: 1 Blech\n", recipe.to_string());
+    }
+
+    spec! {
+        wrap_text {
+            case empty {
+                let text = "";
+                let want: Vec<&str> = vec![];
+            }
+
+            case short {
+                let text = "  Tee   kochen ";
+                let want = vec!["Tee kochen"];
+            }
+
+            case exact_width {
+                let text = "aaaa bbbbb";
+                let want = vec!["aaaa bbbbb"];
+            }
+
+            case break_between_words {
+                let text = "aaaa bbbb cc";
+                let want = vec!["aaaa bbbb", "cc"];
+            }
+
+            case long_word {
+                let text = "a bbbbbbbbbbbb c";
+                let want = vec!["a", "bbbbbbbbbbbb", "c"];
+            }
+
+            case umlauts {
+                let text = "ääää öööö üü";
+                let want = vec!["ääää öööö", "üü"];
+            }
+
+            assert_eq!(want, wrap(text, 10));
+        }
+    }
+}
@@ -0,0 +1,336 @@
+//! Units of ingredients.
+//!
+//! Recipes spell the same unit in many ways, like "g", "gr" or
+//! "Gramm". Known spellings are mapped to a canonical unit when an
+//! ingredient is read, so quantities of the same unit can be added.
+//! Quantities of units measuring the same dimension can be converted
+//! into each other with [convert].
+
+use crate::Rational;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Unit of the quantity of an ingredient.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum Unit {
+    Gram,
+    Kilogram,
+    Milliliter,
+    Liter,
+    /// Teelöffel
+    Teaspoon,
+    /// Esslöffel
+    Tablespoon,
+    Pinch,
+    /// Stück
+    Piece,
+    /// A unit without known aliases, kept as written. An empty text
+    /// means the ingredient has no unit, like "3 Eier".
+    Other(String),
+}
+
+/// Spellings of the known units in lowercase.
+const ALIASES: &[(&str, Unit)] = &[
+    ("g", Unit::Gram),
+    ("gr", Unit::Gram),
+    ("gramm", Unit::Gram),
+    ("gram", Unit::Gram),
+    ("grams", Unit::Gram),
+    ("kg", Unit::Kilogram),
+    ("kilo", Unit::Kilogram),
+    ("kilogramm", Unit::Kilogram),
+    ("kilogram", Unit::Kilogram),
+    ("kilograms", Unit::Kilogram),
+    ("ml", Unit::Milliliter),
+    ("milliliter", Unit::Milliliter),
+    ("milliliters", Unit::Milliliter),
+    ("millilitre", Unit::Milliliter),
+    ("l", Unit::Liter),
+    ("liter", Unit::Liter),
+    ("liters", Unit::Liter),
+    ("litre", Unit::Liter),
+    ("tl", Unit::Teaspoon),
+    ("teelöffel", Unit::Teaspoon),
+    ("tsp", Unit::Teaspoon),
+    ("teaspoon", Unit::Teaspoon),
+    ("teaspoons", Unit::Teaspoon),
+    ("el", Unit::Tablespoon),
+    ("esslöffel", Unit::Tablespoon),
+    ("tbsp", Unit::Tablespoon),
+    ("tablespoon", Unit::Tablespoon),
+    ("tablespoons", Unit::Tablespoon),
+    ("prise", Unit::Pinch),
+    ("prisen", Unit::Pinch),
+    ("pinch", Unit::Pinch),
+    ("pinches", Unit::Pinch),
+    ("pc", Unit::Piece),
+    ("pcs", Unit::Piece),
+    ("piece", Unit::Piece),
+    ("pieces", Unit::Piece),
+    ("stk", Unit::Piece),
+    ("stück", Unit::Piece),
+];
+
+impl Unit {
+    /// Returns the canonical spelling of the unit.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Milliliter => "ml",
+            Unit::Liter => "l",
+            Unit::Teaspoon => "TL",
+            Unit::Tablespoon => "EL",
+            Unit::Pinch => "Prise",
+            Unit::Piece => "pc",
+            Unit::Other(text) => text,
+        }
+    }
+
+    /// Returns true if the ingredient has no unit.
+    pub fn is_empty(&self) -> bool {
+        self.as_str().is_empty()
+    }
+
+    /// Returns what the unit measures and its size in the base unit
+    /// of that dimension, gram or milliliter.
+    ///
+    /// A pinch and a piece have no defined size.
+    fn measure(&self) -> Option<(Dimension, Rational)> {
+        match self {
+            Unit::Gram => Some((Dimension::Mass, Rational::ONE)),
+            Unit::Kilogram => Some((Dimension::Mass, Rational::from(1000))),
+            Unit::Milliliter => Some((Dimension::Volume, Rational::ONE)),
+            Unit::Liter => Some((Dimension::Volume, Rational::from(1000))),
+            Unit::Teaspoon => Some((Dimension::Volume, Rational::from(5))),
+            Unit::Tablespoon => Some((Dimension::Volume, Rational::from(15))),
+            Unit::Pinch | Unit::Piece | Unit::Other(_) => None,
+        }
+    }
+}
+
+impl Default for Unit {
+    fn default() -> Self {
+        Unit::Other(String::new())
+    }
+}
+
+impl From<&str> for Unit {
+    /// Looks up the unit by one of its spellings.
+    ///
+    /// The lookup ignores case and surrounding whitespace. Unknown
+    /// spellings become [Unit::Other].
+    fn from(text: &str) -> Self {
+        let key = text.trim().to_lowercase();
+        ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == key)
+            .map(|(_, unit)| unit.clone())
+            .unwrap_or_else(|| Unit::Other(text.to_string()))
+    }
+}
+
+impl From<String> for Unit {
+    fn from(text: String) -> Self {
+        Unit::from(text.as_str())
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Unit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Unit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Unit::from)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Dimension {
+    Mass,
+    Volume,
+}
+
+/// Converts a quantity from one unit into another.
+///
+/// Units of mass convert into each other, as do units of volume. A
+/// teaspoon counts as 5 ml and a tablespoon as 15 ml. Returns `None`
+/// if the units measure different dimensions or a unit has no known
+/// size, unless both units are the same.
+///
+/// # Example
+///
+/// ```rust
+/// use recipers::{rat, units::convert, Rational, Unit};
+///
+/// assert_eq!(Some(rat!(1, 2)), convert(rat!(500), &Unit::Gram, &Unit::Kilogram));
+/// assert_eq!(None, convert(rat!(500), &Unit::Gram, &Unit::Milliliter));
+/// ```
+pub fn convert(quantity: Rational, from: &Unit, to: &Unit) -> Option<Rational> {
+    if from == to {
+        return Some(quantity);
+    }
+
+    match (from.measure(), to.measure()) {
+        (Some((from_dimension, from_size)), Some((to_dimension, to_size)))
+            if from_dimension == to_dimension =>
+        {
+            Some(quantity * from_size / to_size)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{convert, Unit};
+    use crate::{rat, Rational};
+    use spucky::spec;
+
+    spec! {
+        convert_quantity {
+            case gram_to_kilogram {
+                let quantity = rat!(500);
+                let from = Unit::Gram;
+                let to = Unit::Kilogram;
+                let want = Some(rat!(1, 2));
+            }
+
+            case kilogram_to_gram {
+                let quantity = rat!(3, 2);
+                let from = Unit::Kilogram;
+                let to = Unit::Gram;
+                let want = Some(rat!(1500));
+            }
+
+            case milliliter_to_liter {
+                let quantity = rat!(250);
+                let from = Unit::Milliliter;
+                let to = Unit::Liter;
+                let want = Some(rat!(1, 4));
+            }
+
+            case liter_to_milliliter {
+                let quantity = rat!(3, 4);
+                let from = Unit::Liter;
+                let to = Unit::Milliliter;
+                let want = Some(rat!(750));
+            }
+
+            case tablespoon_to_teaspoon {
+                let quantity = rat!(2);
+                let from = Unit::Tablespoon;
+                let to = Unit::Teaspoon;
+                let want = Some(rat!(6));
+            }
+
+            case gram_to_milliliter {
+                let quantity = rat!(500);
+                let from = Unit::Gram;
+                let to = Unit::Milliliter;
+                let want = None;
+            }
+
+            case milliliter_to_gram {
+                let quantity = rat!(500);
+                let from = Unit::Milliliter;
+                let to = Unit::Gram;
+                let want = None;
+            }
+
+            case pinch_to_gram {
+                let quantity = rat!(1);
+                let from = Unit::Pinch;
+                let to = Unit::Gram;
+                let want = None;
+            }
+
+            case same_unknown_unit {
+                let quantity = rat!(2);
+                let from = Unit::from("Bund");
+                let to = Unit::from("Bund");
+                let want = Some(rat!(2));
+            }
+
+            case different_unknown_units {
+                let quantity = rat!(2);
+                let from = Unit::from("Bund");
+                let to = Unit::from("Dose");
+                let want = None;
+            }
+
+            assert_eq!(want, convert(quantity, &from, &to));
+        }
+    }
+
+    spec! {
+        unit_from_alias {
+            case gram {
+                let aliases = ["g", "gr", "Gramm", "gram", "grams", " G "];
+                let want = Unit::Gram;
+            }
+
+            case teaspoon {
+                let aliases = ["TL", "tl", "Teelöffel", "tsp", "teaspoons", "Tsp"];
+                let want = Unit::Teaspoon;
+            }
+
+            case liter {
+                let aliases = ["l", "L", "Liter", "liters", "litre", "LITER"];
+                let want = Unit::Liter;
+            }
+
+            case piece {
+                let aliases = ["pc", "Stück", "Stk", "pieces"];
+                let want = Unit::Piece;
+            }
+
+            for alias in aliases {
+                assert_eq!(want, Unit::from(alias), "{:?}", alias);
+            }
+        }
+    }
+
+    spec! {
+        unknown_unit_passes_through {
+            case can {
+                let text = "Dose";
+            }
+
+            case bundle {
+                let text = "Bund";
+            }
+
+            case empty {
+                let text = "";
+            }
+
+            let unit = Unit::from(text);
+            assert_eq!(Unit::Other(text.to_string()), unit);
+            assert_eq!(text, unit.to_string());
+        }
+    }
+
+    #[test]
+    fn deserialize_normalizes_unit() {
+        let units: Vec<Unit> = serde_json::from_str(r#"["grams", "Esslöffel", "Dose"]"#).unwrap();
+
+        assert_eq!(
+            vec![Unit::Gram, Unit::Tablespoon, Unit::Other("Dose".into())],
+            units
+        );
+        assert_eq!(
+            r#"["g","EL","Dose"]"#,
+            serde_json::to_string(&units).unwrap()
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use crate::{is_valid_source, Ingredient, Recipe};
+use serde::Serialize;
+use std::{error, fmt};
+
+/// Maximum number of characters of a title.
+const MAX_TITLE_LENGTH: usize = 100;
+
+/// Describes a single violation found while validating a recipe.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct ValidationError {
+    /// Path of the invalid field, for example "ingredients[2].name".
+    field: String,
+    code: ValidationCode,
+    /// The maximum number of characters of a field which is too long.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_length: Option<usize>,
+}
+
+impl ValidationError {
+    fn new(field: impl Into<String>, code: ValidationCode) -> ValidationError {
+        ValidationError {
+            field: field.into(),
+            code,
+            max_length: None,
+        }
+    }
+
+    /// Creates a [ValidationCode::TooLong] error for a field limited
+    /// to `max_length` characters.
+    fn too_long(field: impl Into<String>, max_length: usize) -> ValidationError {
+        ValidationError {
+            max_length: Some(max_length),
+            ..ValidationError::new(field, ValidationCode::TooLong)
+        }
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    pub fn code(&self) -> ValidationCode {
+        self.code
+    }
+
+    /// Returns the maximum length of a field which is too long.
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Prepends the path of the enclosing field.
+    fn within(self, parent: &str) -> ValidationError {
+        ValidationError {
+            field: format!("{}.{}", parent, self.field),
+            ..self
+        }
+    }
+}
+
+/// Machine-readable kind of a validation error.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationCode {
+    /// The field must not be empty.
+    Required,
+    /// The field exceeds its maximum length.
+    TooLong,
+    /// The number must be greater than 0.
+    NotPositive,
+    /// The field is not a valid url.
+    InvalidUrl,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.code {
+            ValidationCode::Required => "must not be empty".to_string(),
+            ValidationCode::TooLong => match self.max_length {
+                Some(max_length) => format!("must not exceed {} characters", max_length),
+                None => "is too long".to_string(),
+            },
+            ValidationCode::NotPositive => "must be greater than 0".to_string(),
+            ValidationCode::InvalidUrl => "is not a valid url".to_string(),
+        };
+        write!(f, "{} {}", self.field, reason)
+    }
+}
+
+impl error::Error for ValidationError {}
+
+impl Recipe {
+    /// Checks the recipe for invalid fields.
+    ///
+    /// All violations are collected instead of stopping at the first
+    /// one. The title must not be blank or longer than
+    /// `MAX_TITLE_LENGTH` characters, there must be at least one
+    /// serving, every ingredient must be valid, the preparation must
+    /// not be blank and the urls of all images must be valid. A
+    /// source which looks like a url must be a valid url.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        let title = self.title.trim();
+        if title.is_empty() {
+            errors.push(ValidationError::new("title", ValidationCode::Required));
+        } else if title.chars().count() > MAX_TITLE_LENGTH {
+            errors.push(ValidationError::too_long("title", MAX_TITLE_LENGTH));
+        }
+
+        if self.servings < 1 {
+            errors.push(ValidationError::new(
+                "servings",
+                ValidationCode::NotPositive,
+            ));
+        }
+
+        for (index, ingredient) in self.ingredients.iter().enumerate() {
+            if let Err(invalid) = ingredient.validate() {
+                let parent = format!("ingredients[{}]", index);
+                errors.extend(invalid.into_iter().map(|error| error.within(&parent)));
+            }
+        }
+
+        if self.preparation.trim().is_empty() {
+            errors.push(ValidationError::new(
+                "preparation",
+                ValidationCode::Required,
+            ));
+        }
+
+        for (index, image) in self.images.iter().enumerate() {
+            if image.validate().is_err() {
+                errors.push(ValidationError::new(
+                    format!("images[{}].url", index),
+                    ValidationCode::InvalidUrl,
+                ));
+            }
+        }
+
+        if let Some(source) = &self.source {
+            if !is_valid_source(source) {
+                errors.push(ValidationError::new("source", ValidationCode::InvalidUrl));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl Ingredient {
+    /// Checks the ingredient for invalid fields.
+    ///
+    /// The name must not be blank and the quantity must be greater
+    /// than 0.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        if self.name.trim().is_empty() {
+            errors.push(ValidationError::new("name", ValidationCode::Required));
+        }
+
+        if !self.quantity.is_positive() {
+            errors.push(ValidationError::new(
+                "quantity",
+                ValidationCode::NotPositive,
+            ));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::lasagne;
+    use crate::{rat, ImageRef, Rational};
+    use spucky::spec;
+
+    #[test]
+    fn valid_recipe() {
+        assert_eq!(Ok(()), lasagne().validate());
+    }
+
+    spec! {
+        validate_recipe {
+            case blank_title {
+                let recipe = Recipe { title: "  ".into(), ..lasagne() };
+                let want = vec![ValidationError::new("title", ValidationCode::Required)];
+            }
+
+            case long_title {
+                let recipe = Recipe { title: "x".repeat(MAX_TITLE_LENGTH + 1), ..lasagne() };
+                let want = vec![ValidationError::too_long("title", MAX_TITLE_LENGTH)];
+            }
+
+            case title_at_limit {
+                let recipe = Recipe { title: "ä".repeat(MAX_TITLE_LENGTH), ..lasagne() };
+                let want: Vec<ValidationError> = vec![];
+            }
+
+            case no_servings {
+                let recipe = Recipe { servings: 0, ..lasagne() };
+                let want = vec![ValidationError::new("servings", ValidationCode::NotPositive)];
+            }
+
+            case blank_preparation {
+                let recipe = Recipe { preparation: "\n  \n".into(), ..lasagne() };
+                let want = vec![ValidationError::new("preparation", ValidationCode::Required)];
+            }
+
+            case invalid_ingredients {
+                let recipe = Recipe {
+                    ingredients: vec![
+                        Ingredient { name: "Pasta".into(), quantity: rat!(1), unit: "pc".into() },
+                        Ingredient { name: "".into(), quantity: rat!(0), unit: "g".into() },
+                        Ingredient { name: "Salz".into(), quantity: rat!(-1), unit: "".into() },
+                    ],
+                    ..lasagne()
+                };
+                let want = vec![
+                    ValidationError::new("ingredients[1].name", ValidationCode::Required),
+                    ValidationError::new("ingredients[1].quantity", ValidationCode::NotPositive),
+                    ValidationError::new("ingredients[2].quantity", ValidationCode::NotPositive),
+                ];
+            }
+
+            case invalid_image {
+                let recipe = Recipe {
+                    images: vec![ImageRef { url: "ftp://example.com/a.jpg".into(), caption: None }],
+                    ..lasagne()
+                };
+                let want = vec![ValidationError::new("images[0].url", ValidationCode::InvalidUrl)];
+            }
+
+            case book_source {
+                let recipe = Recipe { source: Some("Das große Kochbuch, S. 12".into()), ..lasagne() };
+                let want: Vec<ValidationError> = vec![];
+            }
+
+            case url_source {
+                let recipe = Recipe { source: Some("https://example.com/lasagne".into()), ..lasagne() };
+                let want: Vec<ValidationError> = vec![];
+            }
+
+            case malformed_source {
+                let recipe = Recipe { source: Some("https://exa mple.com/lasagne".into()), ..lasagne() };
+                let want = vec![ValidationError::new("source", ValidationCode::InvalidUrl)];
+            }
+
+            case several_defects {
+                let recipe = Recipe {
+                    title: "".into(),
+                    servings: 0,
+                    ingredients: vec![
+                        Ingredient { name: " ".into(), quantity: rat!(2), unit: "".into() },
+                        Ingredient { name: "Mehl".into(), quantity: rat!(0), unit: "g".into() },
+                    ],
+                    ..lasagne()
+                };
+                let want = vec![
+                    ValidationError::new("title", ValidationCode::Required),
+                    ValidationError::new("servings", ValidationCode::NotPositive),
+                    ValidationError::new("ingredients[0].name", ValidationCode::Required),
+                    ValidationError::new("ingredients[1].quantity", ValidationCode::NotPositive),
+                ];
+            }
+
+            case everything_wrong {
+                let recipe = Recipe::default();
+                let want = vec![
+                    ValidationError::new("title", ValidationCode::Required),
+                    ValidationError::new("servings", ValidationCode::NotPositive),
+                    ValidationError::new("preparation", ValidationCode::Required),
+                ];
+            }
+
+            let got = recipe.validate().err().unwrap_or_default();
+            assert_eq!(want, got);
+        }
+    }
+
+    #[test]
+    fn serialize_validation_error() {
+        let error = ValidationError::new("ingredients[0].name", ValidationCode::Required);
+        let json = serde_json::to_string(&error).unwrap();
+        assert_eq!(r#"{"field":"ingredients[0].name","code":"required"}"#, json);
+    }
+
+    #[test]
+    fn too_long_names_the_limit() {
+        let error = ValidationError::too_long("title", 12);
+        assert_eq!(Some(12), error.max_length());
+        assert_eq!("title must not exceed 12 characters", error.to_string());
+        assert_eq!(
+            r#"{"field":"title","code":"too_long","max_length":12}"#,
+            serde_json::to_string(&error).unwrap()
+        );
+    }
+}
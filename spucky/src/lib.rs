@@ -27,13 +27,18 @@ use syn::{braced, parse_macro_input, token, Block, Ident, ItemType, Stmt, Token}
 /// werden ist:
 ///
 /// ```bnf
-/// specification : ident '{' <case>+ <body> '}'
+/// specification : 'async'? ident '{' <case>+ <body> '}'
 /// case : 'case' ident '{' <body> '}'
 /// body : stmt*
 /// ```
 ///
 /// Ident für case muss eindeutig innerhalb der Spezifikation sein.
 ///
+/// Beginnt die Spezifikation mit `async`, werden asynchrone
+/// Testfunktionen mit `#[tokio::test]` erzeugt. Im Rumpf kann dann
+/// `.await` verwendet werden. Der Crate mit den Tests muss dafür
+/// `tokio` mit den Features `macros` und `rt` einbinden.
+///
 ///
 /// # Examples
 ///
@@ -63,7 +68,7 @@ use syn::{braced, parse_macro_input, token, Block, Ident, ItemType, Stmt, Token}
 ///
 /// Das Beispiel erzeugt folgende Testfunktionen:
 ///
-/// ```
+/// ```ignore
 /// mod example {
 ///   #[test]
 ///   fn test_1() {
@@ -76,13 +81,17 @@ use syn::{braced, parse_macro_input, token, Block, Ident, ItemType, Stmt, Token}
 ///
 ///   // ...
 /// }
-///
+/// ```
 #[proc_macro]
 pub fn spec(input: TokenStream) -> TokenStream {
     let spec = parse_macro_input!(input as Spec);
     let spec_name = &spec.ident;
     let body = spec.body.stmts;
     let opt_ret_type = spec.body.output;
+    let (attribute, asyncness) = match spec.asyncness {
+        Some(asyncness) => (quote! { #[tokio::test] }, Some(asyncness)),
+        None => (quote! { #[test] }, None),
+    };
 
     let tests = spec.body.cases.into_iter().map(|c| {
         let ident = c.case_id;
@@ -92,8 +101,8 @@ pub fn spec(input: TokenStream) -> TokenStream {
             Some(ref ret_type) => {
                 let ty = ret_type.ty.clone();
                 quote! {
-                    #[test]
-                    fn #ident() -> #ty {
+                    #attribute
+                    #asyncness fn #ident() -> #ty {
                         #(#prelude)*
                         #(#body)*
                     }
@@ -101,8 +110,8 @@ pub fn spec(input: TokenStream) -> TokenStream {
             }
             None => {
                 quote! {
-                    #[test]
-                    fn #ident() {
+                    #attribute
+                    #asyncness fn #ident() {
                         #(#prelude)*
                         #(#body)*
                     }
@@ -122,6 +131,7 @@ pub fn spec(input: TokenStream) -> TokenStream {
 }
 
 struct Spec {
+    asyncness: Option<Token![async]>,
     ident: Ident,
     body: SpecBody,
 }
@@ -130,11 +140,16 @@ impl Parse for Spec {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let content;
 
+        let asyncness: Option<Token![async]> = input.parse()?;
         let ident: Ident = input.parse()?;
         let _brace_token: token::Brace = braced!(content in input);
 
         let body = content.call(SpecBody::parse)?;
-        Ok(Spec { ident, body })
+        Ok(Spec {
+            asyncness,
+            ident,
+            body,
+        })
     }
 }
 
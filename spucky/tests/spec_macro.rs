@@ -39,6 +39,28 @@ spec! {
     }
 }
 
+spec! {
+    async asynchronous {
+        type Output = Result<(), Box<dyn std::error::Error>>;
+
+        case ready {
+            let value = async { 3 };
+            let want = 3;
+        }
+
+        case yielded {
+            let value = async {
+                tokio::task::yield_now().await;
+                4
+            };
+            let want = 4;
+        }
+
+        assert_eq!(want, value.await);
+        Ok(())
+    }
+}
+
 // Oder besser diese Syntax?
 //
 // spec! {